@@ -25,10 +25,6 @@ tpm_server &
 sleep 5
 tpm2_startup -c -T mssim
 
-# Find PKCS 11 slot number
-CONFIG_PATH="run_config.toml"
-../tests/per_provider/provider_cfg/pkcs11/find_slot_number.sh $CONFIG_PATH
-
 # Create corpus if it doesn't exist
 mkdir -p corpus/fuzz_service
 cp init_corpus/* corpus/fuzz_service
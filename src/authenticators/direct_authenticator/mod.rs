@@ -22,6 +22,7 @@
 
 use super::ApplicationName;
 use super::Authenticate;
+use crate::key_id_managers::INTERNAL_APP_NAME;
 use log::error;
 use parsec_interface::requests::request::RequestAuth;
 use parsec_interface::requests::{ResponseStatus, Result};
@@ -37,6 +38,10 @@ impl Authenticate for DirectAuthenticator {
             Err(ResponseStatus::AuthenticationError)
         } else {
             match str::from_utf8(auth.bytes()) {
+                Ok(INTERNAL_APP_NAME) => {
+                    error!("The application name is reserved for the keys used by the service.");
+                    Err(ResponseStatus::AuthenticationError)
+                }
                 Ok(str) => Ok(ApplicationName(String::from(str))),
                 Err(_) => {
                     error!("Error parsing the authentication value as a UTF-8 string.");
@@ -51,6 +56,7 @@ impl Authenticate for DirectAuthenticator {
 mod test {
     use super::super::Authenticate;
     use super::DirectAuthenticator;
+    use crate::key_id_managers::INTERNAL_APP_NAME;
     use parsec_interface::requests::request::RequestAuth;
     use parsec_interface::requests::ResponseStatus;
 
@@ -87,4 +93,15 @@ mod test {
 
         assert_eq!(status, ResponseStatus::AuthenticationError);
     }
+    #[test]
+    fn internal_app_name_auth() {
+        let authenticator = DirectAuthenticator {};
+        let status = authenticator
+            .authenticate(&RequestAuth::from_bytes(
+                INTERNAL_APP_NAME.to_string().into_bytes(),
+            ))
+            .expect_err("Authentication with the internal application name should have failed");
+
+        assert_eq!(status, ResponseStatus::AuthenticationError);
+    }
 }
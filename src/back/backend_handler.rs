@@ -20,6 +20,7 @@
 use crate::authenticators::ApplicationName;
 use crate::providers::Provide;
 use derivative::Derivative;
+use parsec_interface::operations::psa_key_derivation;
 use parsec_interface::operations::Convert;
 use parsec_interface::operations::{NativeOperation, NativeResult};
 use parsec_interface::requests::{
@@ -27,6 +28,7 @@ use parsec_interface::requests::{
 };
 use parsec_interface::requests::{BodyType, ProviderID};
 use std::io::{Error, ErrorKind};
+use std::sync::Arc;
 
 /// Back end handler component
 ///
@@ -40,12 +42,15 @@ use std::io::{Error, ErrorKind};
 pub struct BackEndHandler {
     // Send and Sync are required for Arc<FrontEndHandler> to be Send.
     #[derivative(Debug = "ignore")]
-    provider: Box<dyn Provide + Send + Sync>,
+    provider: Arc<dyn Provide + Send + Sync>,
     #[derivative(Debug = "ignore")]
     converter: Box<dyn Convert + Send + Sync>,
     provider_id: ProviderID,
     content_type: BodyType,
     accept_type: BodyType,
+    // Maximum size of the data that can be requested to be produced by an operation, as the
+    // response body containing it would not be accepted by the client otherwise.
+    body_len_limit: usize,
 }
 
 impl BackEndHandler {
@@ -139,6 +144,13 @@ impl BackEndHandler {
                     .psa_export_public_key(app_name, op_export_public_key));
                 self.result_to_response(NativeResult::PsaExportPublicKey(result), header)
             }
+            NativeOperation::PsaExportKey(op_export_key) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result =
+                    unwrap_or_else_return!(self.provider.psa_export_key(app_name, op_export_key));
+                self.result_to_response(NativeResult::PsaExportKey(result), header)
+            }
             NativeOperation::PsaDestroyKey(op_destroy_key) => {
                 let app_name =
                     unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
@@ -160,6 +172,165 @@ impl BackEndHandler {
                     unwrap_or_else_return!(self.provider.psa_verify_hash(app_name, op_verify_hash));
                 self.result_to_response(NativeResult::PsaVerifyHash(result), header)
             }
+            NativeOperation::PsaAsymmetricEncrypt(op_asymmetric_encrypt) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_asymmetric_encrypt(app_name, op_asymmetric_encrypt));
+                self.result_to_response(NativeResult::PsaAsymmetricEncrypt(result), header)
+            }
+            NativeOperation::PsaAsymmetricDecrypt(op_asymmetric_decrypt) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_asymmetric_decrypt(app_name, op_asymmetric_decrypt));
+                self.result_to_response(NativeResult::PsaAsymmetricDecrypt(result), header)
+            }
+            NativeOperation::PsaAeadEncrypt(op_aead_encrypt) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_aead_encrypt(app_name, op_aead_encrypt));
+                self.result_to_response(NativeResult::PsaAeadEncrypt(result), header)
+            }
+            NativeOperation::PsaAeadDecrypt(op_aead_decrypt) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_aead_decrypt(app_name, op_aead_decrypt));
+                self.result_to_response(NativeResult::PsaAeadDecrypt(result), header)
+            }
+            NativeOperation::PsaCipherEncrypt(op_cipher_encrypt) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_cipher_encrypt(app_name, op_cipher_encrypt));
+                self.result_to_response(NativeResult::PsaCipherEncrypt(result), header)
+            }
+            NativeOperation::PsaCipherDecrypt(op_cipher_decrypt) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_cipher_decrypt(app_name, op_cipher_decrypt));
+                self.result_to_response(NativeResult::PsaCipherDecrypt(result), header)
+            }
+            NativeOperation::PsaMacCompute(op_mac_compute) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result =
+                    unwrap_or_else_return!(self.provider.psa_mac_compute(app_name, op_mac_compute));
+                self.result_to_response(NativeResult::PsaMacCompute(result), header)
+            }
+            NativeOperation::PsaMacVerify(op_mac_verify) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result =
+                    unwrap_or_else_return!(self.provider.psa_mac_verify(app_name, op_mac_verify));
+                self.result_to_response(NativeResult::PsaMacVerify(result), header)
+            }
+            NativeOperation::PsaHashCompute(op_hash_compute) => {
+                let result =
+                    unwrap_or_else_return!(self.provider.psa_hash_compute(op_hash_compute));
+                self.result_to_response(NativeResult::PsaHashCompute(result), header)
+            }
+            NativeOperation::PsaHashCompare(op_hash_compare) => {
+                let result =
+                    unwrap_or_else_return!(self.provider.psa_hash_compare(op_hash_compare));
+                self.result_to_response(NativeResult::PsaHashCompare(result), header)
+            }
+            NativeOperation::PsaKeyDerivation(op_key_derivation) => {
+                if let psa_key_derivation::Output::Bytes { length } = op_key_derivation.output {
+                    if length > self.body_len_limit {
+                        return Response::from_request_header(
+                            header,
+                            ResponseStatus::BodySizeExceedsLimit,
+                        );
+                    }
+                }
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_key_derivation(app_name, op_key_derivation));
+                self.result_to_response(NativeResult::PsaKeyDerivation(result), header)
+            }
+            NativeOperation::PsaRawKeyAgreement(op_raw_key_agreement) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_raw_key_agreement(app_name, op_raw_key_agreement));
+                self.result_to_response(NativeResult::PsaRawKeyAgreement(result), header)
+            }
+            NativeOperation::PsaGenerateRandom(op_generate_random) => {
+                if op_generate_random.size > self.body_len_limit {
+                    return Response::from_request_header(
+                        header,
+                        ResponseStatus::BodySizeExceedsLimit,
+                    );
+                }
+                let result =
+                    unwrap_or_else_return!(self.provider.psa_generate_random(op_generate_random));
+                self.result_to_response(NativeResult::PsaGenerateRandom(result), header)
+            }
+            NativeOperation::PsaGetKeyAttributes(op_get_key_attributes) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_get_key_attributes(app_name, op_get_key_attributes));
+                self.result_to_response(NativeResult::PsaGetKeyAttributes(result), header)
+            }
+            NativeOperation::PsaCopyKey(op_copy_key) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result =
+                    unwrap_or_else_return!(self.provider.psa_copy_key(app_name, op_copy_key));
+                self.result_to_response(NativeResult::PsaCopyKey(result), header)
+            }
+            NativeOperation::PsaSignMessage(op_sign_message) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_sign_message(app_name, op_sign_message));
+                self.result_to_response(NativeResult::PsaSignMessage(result), header)
+            }
+            NativeOperation::PsaVerifyMessage(op_verify_message) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result = unwrap_or_else_return!(self
+                    .provider
+                    .psa_verify_message(app_name, op_verify_message));
+                self.result_to_response(NativeResult::PsaVerifyMessage(result), header)
+            }
+            NativeOperation::AttestKey(op_attest_key) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result =
+                    unwrap_or_else_return!(self.provider.attest_key(app_name, op_attest_key));
+                self.result_to_response(NativeResult::AttestKey(result), header)
+            }
+            NativeOperation::SealData(op_seal_data) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result =
+                    unwrap_or_else_return!(self.provider.seal_data(app_name, op_seal_data));
+                self.result_to_response(NativeResult::SealData(result), header)
+            }
+            NativeOperation::UnsealData(op_unseal_data) => {
+                let app_name =
+                    unwrap_or_else_return!(app_name.ok_or(ResponseStatus::NotAuthenticated));
+                let result =
+                    unwrap_or_else_return!(self.provider.unseal_data(app_name, op_unseal_data));
+                self.result_to_response(NativeResult::UnsealData(result), header)
+            }
         }
     }
 }
@@ -169,12 +340,13 @@ impl BackEndHandler {
 #[derivative(Debug)]
 pub struct BackEndHandlerBuilder {
     #[derivative(Debug = "ignore")]
-    provider: Option<Box<dyn Provide + Send + Sync>>,
+    provider: Option<Arc<dyn Provide + Send + Sync>>,
     #[derivative(Debug = "ignore")]
     converter: Option<Box<dyn Convert + Send + Sync>>,
     provider_id: Option<ProviderID>,
     content_type: Option<BodyType>,
     accept_type: Option<BodyType>,
+    body_len_limit: Option<usize>,
 }
 
 impl BackEndHandlerBuilder {
@@ -185,10 +357,11 @@ impl BackEndHandlerBuilder {
             provider_id: None,
             content_type: None,
             accept_type: None,
+            body_len_limit: None,
         }
     }
 
-    pub fn with_provider(mut self, provider: Box<dyn Provide + Send + Sync>) -> Self {
+    pub fn with_provider(mut self, provider: Arc<dyn Provide + Send + Sync>) -> Self {
         self.provider = Some(provider);
         self
     }
@@ -213,6 +386,11 @@ impl BackEndHandlerBuilder {
         self
     }
 
+    pub fn with_body_len_limit(mut self, body_len_limit: usize) -> Self {
+        self.body_len_limit = Some(body_len_limit);
+        self
+    }
+
     pub fn build(self) -> std::io::Result<BackEndHandler> {
         Ok(BackEndHandler {
             provider: self
@@ -230,6 +408,9 @@ impl BackEndHandlerBuilder {
             accept_type: self
                 .accept_type
                 .ok_or_else(|| Error::new(ErrorKind::InvalidData, "accept_type is missing"))?,
+            body_len_limit: self
+                .body_len_limit
+                .ok_or_else(|| Error::new(ErrorKind::InvalidData, "body_len_limit is missing"))?,
         })
     }
 }
@@ -53,6 +53,7 @@ use std::sync::{
 };
 use std::time::Duration;
 use structopt::StructOpt;
+use zeroize::Zeroizing;
 
 /// Parsec is the Platform AbstRaction for SECurity, a new open-source initiative to provide a
 /// common API to secure services in a platform-agnostic way.
@@ -83,8 +84,11 @@ fn main() -> Result<()> {
     let _ = flag::register(SIGTERM, kill_signal.clone())?;
     let _ = flag::register(SIGHUP, reload_signal.clone())?;
 
-    let mut config_file = ::std::fs::read_to_string(opts.config.clone())?;
-    let mut config: ServiceConfig = toml::from_str(&config_file).or_else(|e| {
+    // The configuration file might contain secrets, it is wiped from memory once parsed.
+    let config_file = Zeroizing::new(::std::fs::read_to_string(opts.config.clone())?);
+    let parsed_config = toml::from_str(&config_file);
+    drop(config_file);
+    let mut config: ServiceConfig = parsed_config.or_else(|e| {
         Err(Error::new(
             ErrorKind::InvalidInput,
             format!("Failed to parse service configuration ({})", e),
@@ -96,6 +100,8 @@ fn main() -> Result<()> {
     info!("Parsec started. Configuring the service...");
 
     let front_end_handler = ServiceBuilder::build_service(&config)?;
+    // The providers configuration, which might contain secrets, is not needed anymore.
+    config.provider = None;
     // Multiple threads can not just have a reference of the front end handler because they could
     // outlive the run function. It is needed to give them all ownership of the front end handler
     // through an Arc.
@@ -122,14 +128,17 @@ fn main() -> Result<()> {
             drop(listener);
             drop(threadpool);
 
-            config_file = ::std::fs::read_to_string(opts.config.clone())?;
-            config = toml::from_str(&config_file).or_else(|e| {
+            let config_file = Zeroizing::new(::std::fs::read_to_string(opts.config.clone())?);
+            let parsed_config = toml::from_str(&config_file);
+            drop(config_file);
+            config = parsed_config.or_else(|e| {
                 Err(Error::new(
                     ErrorKind::InvalidInput,
                     format!("Failed to parse service configuration ({})", e),
                 ))
             })?;
             front_end_handler = Arc::from(ServiceBuilder::build_service(&config)?);
+            config.provider = None;
             listener = ServiceBuilder::start_listener(config.listener)?;
             threadpool = ServiceBuilder::build_threadpool(config.core_settings.thread_pool_size);
 
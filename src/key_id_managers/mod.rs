@@ -40,6 +40,11 @@ pub struct KeyIdManagerConfig {
     pub store_path: Option<String>,
 }
 
+/// Application name of the keys and records that the providers create and use internally, for
+/// example the records of the keys adopted by the PKCS 11 provider. The authenticators never
+/// return this name so that the clients can not access those entries through their requests.
+pub const INTERNAL_APP_NAME: &str = "parsec-internal";
+
 /// This structure corresponds to a unique identifier of the key. It is used internally by the Key
 /// ID manager to refer to a key.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -78,6 +83,21 @@ impl KeyTriple {
         }
     }
 
+    /// Creates the key triple of a key used internally by a provider, under the
+    /// `INTERNAL_APP_NAME` application name.
+    pub fn new_internal(provider_id: ProviderID, key_name: String) -> KeyTriple {
+        KeyTriple::new(
+            ApplicationName::new(String::from(INTERNAL_APP_NAME)),
+            provider_id,
+            key_name,
+        )
+    }
+
+    /// Checks if this key is used internally by a provider.
+    pub fn is_internal(&self) -> bool {
+        self.app_name.get_name() == INTERNAL_APP_NAME
+    }
+
     /// Checks if this key belongs to a specific provider.
     pub fn belongs_to_provider(&self, provider_id: ProviderID) -> bool {
         self.provider_id == provider_id
@@ -17,35 +17,63 @@
 //! The core provider acts as a source of information for the Parsec service,
 //! aiding clients in discovering the capabilities offered by their underlying
 //! platform.
+//!
+//! Operations that do not involve any key, such as hashing, can also be sent to
+//! the core provider which forwards them to a cryptographic provider able to
+//! execute them.
 use super::Provide;
-use log::error;
+use derivative::Derivative;
+use log::{error, info};
 use parsec_interface::operations::list_providers::ProviderInfo;
-use parsec_interface::operations::{list_opcodes, list_providers, ping};
+use parsec_interface::operations::{
+    list_opcodes, list_providers, ping, psa_hash_compare, psa_hash_compute,
+};
 use parsec_interface::requests::{Opcode, ProviderID, ResponseStatus, Result};
+use std::collections::HashSet;
 use std::io::{Error, ErrorKind};
 use std::str::FromStr;
+use std::sync::Arc;
 use uuid::Uuid;
 use version::{version, Version};
 
 const SUPPORTED_OPCODES: [Opcode; 3] = [Opcode::ListProviders, Opcode::ListOpcodes, Opcode::Ping];
 
+/// Opcodes forwarded to the hash provider, if there is one.
+const HASH_OPCODES: [Opcode; 2] = [Opcode::PsaHashCompute, Opcode::PsaHashCompare];
+
 /// Service information provider
 ///
 /// The core provider is a non-cryptographic provider tasked with offering
 /// structured information about the status of the service and the providers
-/// available.
-#[derive(Debug)]
+/// available. Hash operations are forwarded to the hash provider when one was
+/// given at build time.
+#[derive(Derivative)]
+#[derivative(Debug)]
 pub struct CoreProvider {
     wire_protocol_version_min: u8,
     wire_protocol_version_maj: u8,
     providers: Vec<ProviderInfo>,
+    #[derivative(Debug = "ignore")]
+    hash_provider: Option<Arc<dyn Provide + Send + Sync>>,
+}
+
+impl CoreProvider {
+    fn hash_provider(&self) -> Result<&Arc<dyn Provide + Send + Sync>> {
+        self.hash_provider.as_ref().ok_or_else(|| {
+            error!("No provider able to execute hash operations is available.");
+            ResponseStatus::PsaErrorNotSupported
+        })
+    }
 }
 
 impl Provide for CoreProvider {
     fn list_opcodes(&self, _op: list_opcodes::Operation) -> Result<list_opcodes::Result> {
-        Ok(list_opcodes::Result {
-            opcodes: SUPPORTED_OPCODES.iter().copied().collect(),
-        })
+        let mut opcodes: HashSet<Opcode> = SUPPORTED_OPCODES.iter().copied().collect();
+        if self.hash_provider.is_some() {
+            opcodes.extend(HASH_OPCODES.iter().copied());
+        }
+
+        Ok(list_opcodes::Result { opcodes })
     }
 
     fn list_providers(&self, _op: list_providers::Operation) -> Result<list_providers::Result> {
@@ -80,14 +108,33 @@ impl Provide for CoreProvider {
 
         Ok(result)
     }
+
+    fn psa_hash_compute(
+        &self,
+        op: psa_hash_compute::Operation,
+    ) -> Result<psa_hash_compute::Result> {
+        info!("Core Provider - Hash Compute");
+        self.hash_provider()?.psa_hash_compute(op)
+    }
+
+    fn psa_hash_compare(
+        &self,
+        op: psa_hash_compare::Operation,
+    ) -> Result<psa_hash_compare::Result> {
+        info!("Core Provider - Hash Compare");
+        self.hash_provider()?.psa_hash_compare(op)
+    }
 }
 
 /// Builder for CoreProvider
-#[derive(Debug, Default)]
+#[derive(Default, Derivative)]
+#[derivative(Debug)]
 pub struct CoreProviderBuilder {
     version_maj: Option<u8>,
     version_min: Option<u8>,
     providers: Option<Vec<ProviderInfo>>,
+    #[derivative(Debug = "ignore")]
+    hash_provider: Option<Arc<dyn Provide + Send + Sync>>,
 }
 
 impl CoreProviderBuilder {
@@ -96,6 +143,7 @@ impl CoreProviderBuilder {
             version_maj: None,
             version_min: None,
             providers: None,
+            hash_provider: None,
         }
     }
 
@@ -122,6 +170,13 @@ impl CoreProviderBuilder {
         self
     }
 
+    /// Provider to which the key-less hash operations are forwarded.
+    pub fn with_hash_provider(mut self, hash_provider: Arc<dyn Provide + Send + Sync>) -> Self {
+        self.hash_provider = Some(hash_provider);
+
+        self
+    }
+
     pub fn build(self) -> std::io::Result<CoreProvider> {
         let mut core_provider = CoreProvider {
             wire_protocol_version_maj: self
@@ -133,6 +188,7 @@ impl CoreProviderBuilder {
             providers: self
                 .providers
                 .ok_or_else(|| Error::new(ErrorKind::InvalidData, "provider info is missing"))?,
+            hash_provider: self.hash_provider,
         };
 
         core_provider
@@ -158,6 +214,7 @@ mod tests {
             wire_protocol_version_min: 8,
             wire_protocol_version_maj: 10,
             providers: Vec::new(),
+            hash_provider: None,
         };
         let op = ping::Operation {};
         let result = provider.ping(op).unwrap();
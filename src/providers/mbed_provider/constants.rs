@@ -58,6 +58,7 @@ pub const PSA_KEY_TYPE_AES: psa_key_type_t = 0x4000_0001;
 pub const PSA_KEY_TYPE_DES: psa_key_type_t = 0x4000_0002;
 pub const PSA_KEY_TYPE_CAMELLIA: psa_key_type_t = 0x4000_0003;
 pub const PSA_KEY_TYPE_ARC4: psa_key_type_t = 0x4000_0004;
+pub const PSA_KEY_TYPE_CHACHA20: psa_key_type_t = 0x4000_0005;
 pub const PSA_KEY_TYPE_RSA_PUBLIC_KEY: psa_key_type_t = 0x6001_0000;
 pub const PSA_KEY_TYPE_RSA_KEYPAIR: psa_key_type_t = 0x7001_0000;
 pub const PSA_KEY_TYPE_DSA_PUBLIC_KEY: psa_key_type_t = 0x6002_0000;
@@ -127,6 +128,7 @@ pub const PSA_ALG_ANY_HASH: psa_algorithm_t = 0x0100_00ff;
 pub const PSA_ALG_MAC_SUBCATEGORY_MASK: psa_algorithm_t = 0x00c0_0000;
 pub const PSA_ALG_HMAC_BASE: psa_algorithm_t = 0x0280_0000;
 pub const PSA_ALG_MAC_TRUNCATION_MASK: psa_algorithm_t = 0x0000_3f00;
+pub const PSA_MAC_TRUNCATION_OFFSET: psa_algorithm_t = 8;
 pub const PSA_ALG_CIPHER_MAC_BASE: psa_algorithm_t = 0x02c0_0000;
 pub const PSA_ALG_CBC_MAC: psa_algorithm_t = 0x02c0_0001;
 pub const PSA_ALG_CMAC: psa_algorithm_t = 0x02c0_0002;
@@ -134,6 +136,7 @@ pub const PSA_ALG_GMAC: psa_algorithm_t = 0x02c0_0003;
 pub const PSA_ALG_CIPHER_STREAM_FLAG: psa_algorithm_t = 0x0080_0000;
 pub const PSA_ALG_CIPHER_FROM_BLOCK_FLAG: psa_algorithm_t = 0x0040_0000;
 pub const PSA_ALG_ARC4: psa_algorithm_t = 0x0480_0001;
+pub const PSA_ALG_CHACHA20: psa_algorithm_t = 0x0480_0005;
 pub const PSA_ALG_CTR: psa_algorithm_t = 0x04c0_0001;
 pub const PSA_ALG_CFB: psa_algorithm_t = 0x04c0_0002;
 pub const PSA_ALG_OFB: psa_algorithm_t = 0x04c0_0003;
@@ -142,7 +145,9 @@ pub const PSA_ALG_CBC_NO_PADDING: psa_algorithm_t = 0x0460_0100;
 pub const PSA_ALG_CBC_PKCS7: psa_algorithm_t = 0x0460_0101;
 pub const PSA_ALG_CCM: psa_algorithm_t = 0x0600_1001;
 pub const PSA_ALG_GCM: psa_algorithm_t = 0x0600_1002;
+pub const PSA_ALG_CHACHA20_POLY1305: psa_algorithm_t = 0x0600_1005;
 pub const PSA_ALG_AEAD_TAG_LENGTH_MASK: psa_algorithm_t = 0x0000_3f00;
+pub const PSA_AEAD_TAG_LENGTH_OFFSET: psa_algorithm_t = 8;
 pub const PSA_ALG_RSA_PKCS1V15_SIGN_BASE: psa_algorithm_t = 0x1002_0000;
 pub const PSA_ALG_RSA_PSS_BASE: psa_algorithm_t = 0x1003_0000;
 pub const PSA_ALG_DSA_BASE: psa_algorithm_t = 0x1004_0000;
@@ -155,6 +160,11 @@ pub const PSA_ALG_RSA_OAEP_BASE: psa_algorithm_t = 0x1203_0000;
 pub const PSA_ALG_HKDF_BASE: psa_algorithm_t = 0x3000_0100;
 pub const PSA_ALG_TLS12_PRF_BASE: psa_algorithm_t = 0x3000_0200;
 pub const PSA_ALG_TLS12_PSK_TO_MS_BASE: psa_algorithm_t = 0x3000_0300;
+pub const PSA_KEY_DERIVATION_INPUT_SECRET: psa_key_derivation_step_t = 0x0101;
+pub const PSA_KEY_DERIVATION_INPUT_LABEL: psa_key_derivation_step_t = 0x0201;
+pub const PSA_KEY_DERIVATION_INPUT_SALT: psa_key_derivation_step_t = 0x0202;
+pub const PSA_KEY_DERIVATION_INPUT_INFO: psa_key_derivation_step_t = 0x0203;
+pub const PSA_KEY_DERIVATION_INPUT_SEED: psa_key_derivation_step_t = 0x0204;
 pub const PSA_ALG_KEY_DERIVATION_MASK: psa_algorithm_t = 0x010f_ffff;
 pub const PSA_ALG_SELECT_RAW: psa_algorithm_t = 0x3100_0001;
 pub const PSA_ALG_FFDH_BASE: psa_algorithm_t = 0x2210_0000;
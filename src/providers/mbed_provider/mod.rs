@@ -12,7 +12,7 @@
 // WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-use super::Provide;
+use super::{check_copy_policy, signature_hash_alg, Provide};
 use crate::authenticators::ApplicationName;
 use crate::key_id_managers;
 use crate::key_id_managers::{KeyInfo, KeyTriple, ManageKeyIDs};
@@ -20,14 +20,19 @@ use constants::PSA_SUCCESS;
 use derivative::Derivative;
 use log::{error, info, warn};
 use parsec_interface::operations::list_providers::ProviderInfo;
+use parsec_interface::operations::psa_algorithm::AsymmetricSignature;
 use parsec_interface::operations::psa_key_attributes::KeyAttributes;
 use parsec_interface::operations::{
-    list_opcodes, psa_destroy_key, psa_export_public_key, psa_generate_key, psa_import_key,
-    psa_sign_hash, psa_verify_hash,
+    list_opcodes, psa_aead_decrypt, psa_aead_encrypt, psa_asymmetric_decrypt,
+    psa_asymmetric_encrypt, psa_cipher_decrypt, psa_cipher_encrypt, psa_copy_key, psa_destroy_key,
+    psa_export_key, psa_export_public_key, psa_generate_key, psa_generate_random, psa_hash_compare,
+    psa_hash_compute, psa_import_key, psa_key_derivation, psa_mac_compute, psa_mac_verify,
+    psa_raw_key_agreement, psa_sign_hash, psa_sign_message, psa_verify_hash, psa_verify_message,
 };
 use parsec_interface::requests::{Opcode, ProviderID, ResponseStatus, Result};
 use psa_crypto_binding::psa_key_id_t;
 use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::io::{Error, ErrorKind};
 use std::sync::{Arc, Mutex, RwLock};
 use std_semaphore::Semaphore;
@@ -52,13 +57,31 @@ mod utils;
 
 type LocalIdStore = HashSet<psa_key_id_t>;
 
-const SUPPORTED_OPCODES: [Opcode; 7] = [
+const SUPPORTED_OPCODES: [Opcode; 25] = [
     Opcode::PsaGenerateKey,
     Opcode::PsaDestroyKey,
     Opcode::PsaSignHash,
     Opcode::PsaVerifyHash,
     Opcode::PsaImportKey,
     Opcode::PsaExportPublicKey,
+    Opcode::PsaExportKey,
+    Opcode::PsaAsymmetricEncrypt,
+    Opcode::PsaAsymmetricDecrypt,
+    Opcode::PsaAeadEncrypt,
+    Opcode::PsaAeadDecrypt,
+    Opcode::PsaCipherEncrypt,
+    Opcode::PsaCipherDecrypt,
+    Opcode::PsaMacCompute,
+    Opcode::PsaMacVerify,
+    Opcode::PsaHashCompute,
+    Opcode::PsaHashCompare,
+    Opcode::PsaKeyDerivation,
+    Opcode::PsaRawKeyAgreement,
+    Opcode::PsaGenerateRandom,
+    Opcode::PsaGetKeyAttributes,
+    Opcode::PsaCopyKey,
+    Opcode::PsaSignMessage,
+    Opcode::PsaVerifyMessage,
     Opcode::ListOpcodes,
 ];
 
@@ -89,12 +112,21 @@ pub struct MbedProvider {
 /// Wrapper around the get method of the Key ID Manager to convert the key ID to the psa_key_id_t
 /// type.
 fn get_key_id(key_triple: &KeyTriple, store_handle: &dyn ManageKeyIDs) -> Result<psa_key_id_t> {
+    let (key_id, _) = get_key_info(key_triple, store_handle)?;
+    Ok(key_id)
+}
+
+/// Gets a PSA Key ID and the key attributes stored with it from the Key ID Manager.
+fn get_key_info(
+    key_triple: &KeyTriple,
+    store_handle: &dyn ManageKeyIDs,
+) -> Result<(psa_key_id_t, KeyAttributes)> {
     match store_handle.get(key_triple) {
         Ok(Some(key_info)) => {
             if key_info.id.len() == 4 {
                 let mut dst = [0; 4];
                 dst.copy_from_slice(&key_info.id);
-                Ok(u32::from_ne_bytes(dst))
+                Ok((u32::from_ne_bytes(dst), key_info.attributes))
             } else {
                 error!("Stored Key ID is not valid.");
                 Err(ResponseStatus::KeyIDManagerError)
@@ -234,6 +266,101 @@ impl MbedProvider {
 
         Some(mbed_provider)
     }
+
+    /// Signs a hash with the key of the given ID and attributes.
+    ///
+    /// The caller needs to hold an access to the key slot semaphore.
+    fn sign_hash_with_key(
+        &self,
+        key_id: psa_key_id_t,
+        key_attributes: KeyAttributes,
+        alg: AsymmetricSignature,
+        hash: &[u8],
+    ) -> Result<Vec<u8>> {
+        // Everything that can fail is done before opening the key so that its handle is always
+        // closed.
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+        let buffer_size = utils::psa_asymmetric_sign_output_size(key_attributes)?;
+        let mut signature = vec![0u8; buffer_size];
+        let mut signature_size: usize = 0;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let sign_status;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * the caller holds self.key_slot_semaphore which prevents overflowing key slots
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            sign_status = psa_crypto_binding::psa_asymmetric_sign(
+                key_handle.raw(),
+                psa_alg,
+                hash.as_ptr(),
+                hash.len(),
+                signature.as_mut_ptr(),
+                buffer_size,
+                &mut signature_size,
+            );
+            key_handle.close()?;
+        }
+
+        if sign_status == PSA_SUCCESS {
+            signature.truncate(signature_size);
+            Ok(signature)
+        } else {
+            error!("Sign status: {}", sign_status);
+            Err(utils::convert_status(sign_status))
+        }
+    }
+
+    /// Verifies the signature of a hash with the key of the given ID.
+    ///
+    /// The caller needs to hold an access to the key slot semaphore.
+    fn verify_hash_with_key(
+        &self,
+        key_id: psa_key_id_t,
+        alg: AsymmetricSignature,
+        hash: &[u8],
+        signature: &[u8],
+    ) -> Result<()> {
+        // Converted before opening the key so that its handle is always closed.
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let verify_status;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * the caller holds self.key_slot_semaphore which prevents overflowing key slots
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            verify_status = psa_crypto_binding::psa_asymmetric_verify(
+                key_handle.raw(),
+                psa_alg,
+                hash.as_ptr(),
+                hash.len(),
+                signature.as_ptr(),
+                signature.len(),
+            );
+            key_handle.close()?;
+        }
+
+        if verify_status == PSA_SUCCESS {
+            Ok(())
+        } else {
+            Err(utils::convert_status(verify_status))
+        }
+    }
 }
 
 impl Provide for MbedProvider {
@@ -256,6 +383,10 @@ impl Provide for MbedProvider {
         })
     }
 
+    fn key_id_store(&self) -> Option<&Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>> {
+        Some(&self.key_id_store)
+    }
+
     fn psa_generate_key(
         &self,
         app_name: ApplicationName,
@@ -416,6 +547,73 @@ impl Provide for MbedProvider {
         Ok(psa_export_public_key::Result { data: buffer })
     }
 
+    fn psa_export_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_export_key::Operation,
+    ) -> Result<psa_export_key::Result> {
+        info!("Mbed Provider - Export Key");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.key_name;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_export()?;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let mut key_attrs;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            key_attrs = key_handle.attributes()?;
+        }
+
+        let buffer_size = utils::psa_export_key_size(key_attrs.as_ref());
+        let buffer_size = match buffer_size {
+            Ok(buffer_size) => buffer_size,
+            Err(e) => {
+                // Safety: same conditions than above.
+                unsafe {
+                    key_attrs.reset();
+                    key_handle.close()?;
+                }
+                return Err(e);
+            }
+        };
+        let mut buffer = vec![0u8; buffer_size];
+        let mut actual_size = 0;
+
+        let export_status;
+        // Safety: same conditions than above.
+        unsafe {
+            export_status = psa_crypto_binding::psa_export_key(
+                key_handle.raw(),
+                buffer.as_mut_ptr(),
+                buffer_size,
+                &mut actual_size,
+            );
+            key_attrs.reset();
+            key_handle.close()?;
+        };
+
+        if export_status != PSA_SUCCESS {
+            error!("Export status: {}", export_status);
+            return Err(utils::convert_status(export_status));
+        }
+
+        buffer.resize(actual_size, 0);
+        Ok(psa_export_key::Result { data: buffer })
+    }
+
     fn psa_destroy_key(
         &self,
         app_name: ApplicationName,
@@ -467,12 +665,108 @@ impl Provide for MbedProvider {
     ) -> Result<psa_sign_hash::Result> {
         info!("Mbed Provider - Asym Sign");
         let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, op.key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        let signature = self.sign_hash_with_key(key_id, key_attributes, op.alg, &op.hash)?;
+
+        Ok(psa_sign_hash::Result { signature })
+    }
+
+    fn psa_verify_hash(
+        &self,
+        app_name: ApplicationName,
+        op: psa_verify_hash::Operation,
+    ) -> Result<psa_verify_hash::Result> {
+        info!("Mbed Provider - Asym Verify");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, op.key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let key_id = get_key_id(&key_triple, &*store_handle)?;
+
+        self.verify_hash_with_key(key_id, op.alg, &op.hash, &op.signature)?;
+
+        Ok(psa_verify_hash::Result {})
+    }
+
+    fn psa_sign_message(
+        &self,
+        app_name: ApplicationName,
+        op: psa_sign_message::Operation,
+    ) -> Result<psa_sign_message::Result> {
+        info!("Mbed Provider - Sign Message");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let alg = op.alg;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, op.key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_sign_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let hash_alg = utils::convert_hash_algorithm(signature_hash_alg(alg)?)?;
+        // Safety: at this point the provider has been instantiated so Mbed Crypto has been
+        // initialized. No key is involved in the operation.
+        let hash = unsafe { utils::psa_hash_compute(hash_alg, &op.message)? };
+
+        let signature = self.sign_hash_with_key(key_id, key_attributes, alg, &hash)?;
+
+        Ok(psa_sign_message::Result { signature })
+    }
+
+    fn psa_verify_message(
+        &self,
+        app_name: ApplicationName,
+        op: psa_verify_message::Operation,
+    ) -> Result<psa_verify_message::Result> {
+        info!("Mbed Provider - Verify Message");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let alg = op.alg;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, op.key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_verify_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let hash_alg = utils::convert_hash_algorithm(signature_hash_alg(alg)?)?;
+        // Safety: at this point the provider has been instantiated so Mbed Crypto has been
+        // initialized. No key is involved in the operation.
+        let hash = unsafe { utils::psa_hash_compute(hash_alg, &op.message)? };
+
+        self.verify_hash_with_key(key_id, alg, &hash, &op.signature)?;
+
+        Ok(psa_verify_message::Result {})
+    }
+
+    fn psa_asymmetric_encrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_asymmetric_encrypt::Operation,
+    ) -> Result<psa_asymmetric_encrypt::Result> {
+        info!("Mbed Provider - Asym Encrypt");
+        let _semaphore_guard = self.key_slot_semaphore.access();
         let key_name = op.key_name;
-        let hash = op.hash;
+        let plaintext = op.plaintext;
         let alg = op.alg;
+        let salt = op.salt.unwrap_or_default();
         let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
         let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
-        let key_id = get_key_id(&key_triple, &*store_handle)?;
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_encrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        // Everything that can fail is done before opening the key so that its handle is always
+        // closed.
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+        let buffer_size = utils::psa_asymmetric_crypt_output_size(key_attributes)?;
+        let mut ciphertext = vec![0u8; buffer_size];
+        let mut ciphertext_size: usize = 0;
 
         let _guard = self
             .key_handle_mutex
@@ -480,64 +774,185 @@ impl Provide for MbedProvider {
             .expect("Grabbing key handle mutex failed");
 
         let mut key_handle;
-        let mut key_attrs;
+        let encrypt_status;
         // Safety:
         //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
         //   * self.key_handle_mutex prevents concurrent accesses
         //   * self.key_slot_semaphore prevents overflowing key slots
         unsafe {
             key_handle = KeyHandle::open(key_id)?;
-            key_attrs = key_handle.attributes()?;
+            encrypt_status = psa_crypto_binding::psa_asymmetric_encrypt(
+                key_handle.raw(),
+                psa_alg,
+                plaintext.as_ptr(),
+                plaintext.len(),
+                salt.as_ptr(),
+                salt.len(),
+                ciphertext.as_mut_ptr(),
+                buffer_size,
+                &mut ciphertext_size,
+            );
+            key_handle.close()?;
         }
 
-        let buffer_size = utils::psa_asymmetric_sign_output_size(key_attrs.as_ref())?;
-        let mut signature = vec![0u8; buffer_size];
-        let mut signature_size: usize = 0;
+        if encrypt_status == PSA_SUCCESS {
+            ciphertext.resize(ciphertext_size, 0);
+            Ok(psa_asymmetric_encrypt::Result { ciphertext })
+        } else {
+            error!("Encrypt status: {}", encrypt_status);
+            Err(utils::convert_status(encrypt_status))
+        }
+    }
 
-        let sign_status;
-        // Safety: same conditions than above.
+    fn psa_asymmetric_decrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_asymmetric_decrypt::Operation,
+    ) -> Result<psa_asymmetric_decrypt::Result> {
+        info!("Mbed Provider - Asym Decrypt");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.key_name;
+        let ciphertext = op.ciphertext;
+        let alg = op.alg;
+        let salt = op.salt.unwrap_or_default();
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_decrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        // Everything that can fail is done before opening the key so that its handle is always
+        // closed.
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+        let buffer_size = utils::psa_asymmetric_crypt_output_size(key_attributes)?;
+        let mut plaintext = vec![0u8; buffer_size];
+        let mut plaintext_size: usize = 0;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let decrypt_status;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
         unsafe {
-            sign_status = psa_crypto_binding::psa_asymmetric_sign(
+            key_handle = KeyHandle::open(key_id)?;
+            decrypt_status = psa_crypto_binding::psa_asymmetric_decrypt(
                 key_handle.raw(),
-                utils::convert_algorithm(&alg.into())?,
-                hash.as_ptr(),
-                hash.len(),
-                signature.as_mut_ptr(),
+                psa_alg,
+                ciphertext.as_ptr(),
+                ciphertext.len(),
+                salt.as_ptr(),
+                salt.len(),
+                plaintext.as_mut_ptr(),
                 buffer_size,
-                &mut signature_size,
+                &mut plaintext_size,
             );
-            key_attrs.reset();
             key_handle.close()?;
-        };
+        }
 
-        if sign_status == PSA_SUCCESS {
-            let mut res = psa_sign_hash::Result {
-                signature: Vec::new(),
-            };
-            res.signature.resize(signature_size, 0);
-            res.signature.copy_from_slice(&signature[0..signature_size]);
+        if decrypt_status == PSA_SUCCESS {
+            plaintext.resize(plaintext_size, 0);
+            Ok(psa_asymmetric_decrypt::Result { plaintext })
+        } else {
+            error!("Decrypt status: {}", decrypt_status);
+            Err(utils::convert_status(decrypt_status))
+        }
+    }
 
-            Ok(res)
+    fn psa_aead_encrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_aead_encrypt::Operation,
+    ) -> Result<psa_aead_encrypt::Result> {
+        info!("Mbed Provider - AEAD Encrypt");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let nonce = op.nonce;
+        let additional_data = op.additional_data;
+        let plaintext = op.plaintext;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_encrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+        let buffer_size = utils::psa_aead_encrypt_output_size(psa_alg, plaintext.len());
+        let mut ciphertext = vec![0u8; buffer_size];
+        let mut ciphertext_size: usize = 0;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let encrypt_status;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            encrypt_status = psa_crypto_binding::psa_aead_encrypt(
+                key_handle.raw(),
+                psa_alg,
+                nonce.as_ptr(),
+                nonce.len(),
+                additional_data.as_ptr(),
+                additional_data.len(),
+                plaintext.as_ptr(),
+                plaintext.len(),
+                ciphertext.as_mut_ptr(),
+                buffer_size,
+                &mut ciphertext_size,
+            );
+            key_handle.close()?;
+        }
+
+        if encrypt_status == PSA_SUCCESS {
+            ciphertext.resize(ciphertext_size, 0);
+            Ok(psa_aead_encrypt::Result { ciphertext })
         } else {
-            error!("Sign status: {}", sign_status);
-            Err(utils::convert_status(sign_status))
+            error!("AEAD encrypt status: {}", encrypt_status);
+            Err(utils::convert_status(encrypt_status))
         }
     }
 
-    fn psa_verify_hash(
+    fn psa_aead_decrypt(
         &self,
         app_name: ApplicationName,
-        op: psa_verify_hash::Operation,
-    ) -> Result<psa_verify_hash::Result> {
-        info!("Mbed Provider - Asym Verify");
+        op: psa_aead_decrypt::Operation,
+    ) -> Result<psa_aead_decrypt::Result> {
+        info!("Mbed Provider - AEAD Decrypt");
         let _semaphore_guard = self.key_slot_semaphore.access();
         let key_name = op.key_name;
-        let hash = op.hash;
         let alg = op.alg;
-        let signature = op.signature;
+        let nonce = op.nonce;
+        let additional_data = op.additional_data;
+        let ciphertext = op.ciphertext;
         let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
         let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
-        let key_id = get_key_id(&key_triple, &*store_handle)?;
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_decrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+        let buffer_size = utils::psa_aead_decrypt_output_size(psa_alg, ciphertext.len());
+        let mut plaintext = vec![0u8; buffer_size];
+        let mut plaintext_size: usize = 0;
 
         let _guard = self
             .key_handle_mutex
@@ -545,32 +960,500 @@ impl Provide for MbedProvider {
             .expect("Grabbing key handle mutex failed");
 
         let mut key_handle;
-        let mut key_attrs;
-        let verify_status;
+        let decrypt_status;
         // Safety:
         //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
         //   * self.key_handle_mutex prevents concurrent accesses
         //   * self.key_slot_semaphore prevents overflowing key slots
         unsafe {
             key_handle = KeyHandle::open(key_id)?;
-            key_attrs = key_handle.attributes()?;
-            verify_status = psa_crypto_binding::psa_asymmetric_verify(
+            decrypt_status = psa_crypto_binding::psa_aead_decrypt(
                 key_handle.raw(),
-                utils::convert_algorithm(&alg.into())?,
-                hash.as_ptr(),
-                hash.len(),
-                signature.as_ptr(),
-                signature.len(),
+                psa_alg,
+                nonce.as_ptr(),
+                nonce.len(),
+                additional_data.as_ptr(),
+                additional_data.len(),
+                ciphertext.as_ptr(),
+                ciphertext.len(),
+                plaintext.as_mut_ptr(),
+                buffer_size,
+                &mut plaintext_size,
             );
-            key_attrs.reset();
             key_handle.close()?;
         }
 
-        if verify_status == PSA_SUCCESS {
-            Ok(psa_verify_hash::Result {})
+        if decrypt_status == PSA_SUCCESS {
+            plaintext.resize(plaintext_size, 0);
+            Ok(psa_aead_decrypt::Result { plaintext })
         } else {
-            Err(utils::convert_status(verify_status))
+            error!("AEAD decrypt status: {}", decrypt_status);
+            Err(utils::convert_status(decrypt_status))
+        }
+    }
+
+    fn psa_cipher_encrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_cipher_encrypt::Operation,
+    ) -> Result<psa_cipher_encrypt::Result> {
+        info!("Mbed Provider - Cipher Encrypt");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let iv = op.iv;
+        let plaintext = op.plaintext;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_encrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let ciphertext;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
+        //   * the key handle is only closed after the operation
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            ciphertext = utils::psa_cipher_crypt(&key_handle, psa_alg, &iv, &plaintext, true);
+            key_handle.close()?;
+        }
+
+        Ok(psa_cipher_encrypt::Result {
+            ciphertext: ciphertext?,
+        })
+    }
+
+    fn psa_cipher_decrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_cipher_decrypt::Operation,
+    ) -> Result<psa_cipher_decrypt::Result> {
+        info!("Mbed Provider - Cipher Decrypt");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let iv = op.iv;
+        let ciphertext = op.ciphertext;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_decrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let plaintext;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
+        //   * the key handle is only closed after the operation
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            plaintext = utils::psa_cipher_crypt(&key_handle, psa_alg, &iv, &ciphertext, false);
+            key_handle.close()?;
         }
+
+        Ok(psa_cipher_decrypt::Result {
+            plaintext: plaintext?,
+        })
+    }
+
+    fn psa_mac_compute(
+        &self,
+        app_name: ApplicationName,
+        op: psa_mac_compute::Operation,
+    ) -> Result<psa_mac_compute::Result> {
+        info!("Mbed Provider - MAC Compute");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let input = op.input;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_sign_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let mac;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
+        //   * the key handle is only closed after the operation
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            mac = utils::psa_mac_compute(&key_handle, psa_alg, &input);
+            key_handle.close()?;
+        }
+
+        Ok(psa_mac_compute::Result { mac: mac? })
+    }
+
+    fn psa_mac_verify(
+        &self,
+        app_name: ApplicationName,
+        op: psa_mac_verify::Operation,
+    ) -> Result<psa_mac_verify::Result> {
+        info!("Mbed Provider - MAC Verify");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let input = op.input;
+        let mac = op.mac;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_verify_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let verify_result;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
+        //   * the key handle is only closed after the operation
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            verify_result = utils::psa_mac_verify(&key_handle, psa_alg, &input, &mac);
+            key_handle.close()?;
+        }
+
+        verify_result?;
+        Ok(psa_mac_verify::Result {})
+    }
+
+    fn psa_hash_compute(
+        &self,
+        op: psa_hash_compute::Operation,
+    ) -> Result<psa_hash_compute::Result> {
+        info!("Mbed Provider - Hash Compute");
+        if let Some(hash) = utils::sha3_hash(op.alg, &op.input) {
+            return Ok(psa_hash_compute::Result { hash });
+        }
+
+        let psa_alg = utils::convert_hash_algorithm(op.alg)?;
+
+        // Safety: at this point the provider has been instantiated so Mbed Crypto has been
+        // initialized. No key is involved in the operation.
+        let hash = unsafe { utils::psa_hash_compute(psa_alg, &op.input)? };
+
+        Ok(psa_hash_compute::Result { hash })
+    }
+
+    fn psa_hash_compare(
+        &self,
+        op: psa_hash_compare::Operation,
+    ) -> Result<psa_hash_compare::Result> {
+        info!("Mbed Provider - Hash Compare");
+        if let Some(hash) = utils::sha3_hash(op.alg, &op.input) {
+            utils::compare_hashes(&hash, &op.hash)?;
+            return Ok(psa_hash_compare::Result {});
+        }
+
+        let psa_alg = utils::convert_hash_algorithm(op.alg)?;
+
+        // Safety: at this point the provider has been instantiated so Mbed Crypto has been
+        // initialized. No key is involved in the operation.
+        unsafe { utils::psa_hash_compare(psa_alg, &op.input, &op.hash)? };
+
+        Ok(psa_hash_compare::Result {})
+    }
+
+    fn psa_key_derivation(
+        &self,
+        app_name: ApplicationName,
+        op: psa_key_derivation::Operation,
+    ) -> Result<psa_key_derivation::Result> {
+        info!("Mbed Provider - Key Derivation");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let salt = op.salt;
+        let info = op.info;
+        let key_triple = KeyTriple::new(app_name.clone(), ProviderID::MbedCrypto, key_name);
+        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
+        let mut local_ids_handle = self.local_ids.write().expect("Local ID lock poisoned");
+        let (base_key_id, base_key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        base_key_attributes.can_derive_from()?;
+        base_key_attributes.permits_alg(alg.into())?;
+        base_key_attributes.compatible_with_alg(alg.into())?;
+
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+
+        match op.output {
+            psa_key_derivation::Output::Bytes { length } => {
+                // Raw output bytes leave the service: the base key must be allowed to be
+                // exported.
+                base_key_attributes.can_export()?;
+
+                let _guard = self
+                    .key_handle_mutex
+                    .lock()
+                    .expect("Grabbing key handle mutex failed");
+
+                let mut base_key_handle;
+                let output;
+                // Safety:
+                //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+                //   * self.key_handle_mutex prevents concurrent accesses
+                //   * self.key_slot_semaphore prevents overflowing key slots
+                //   * the key handle is only closed after the operation
+                unsafe {
+                    base_key_handle = KeyHandle::open(base_key_id)?;
+                    output = utils::psa_key_derivation_output_bytes(
+                        &base_key_handle,
+                        psa_alg,
+                        &salt,
+                        &info,
+                        length,
+                    );
+                    base_key_handle.close()?;
+                }
+
+                Ok(psa_key_derivation::Result { output: output? })
+            }
+            psa_key_derivation::Output::Key {
+                key_name: derived_key_name,
+                attributes: derived_key_attributes,
+            } => {
+                let derived_key_triple =
+                    KeyTriple::new(app_name, ProviderID::MbedCrypto, derived_key_name);
+                if key_id_exists(&derived_key_triple, &*store_handle)? {
+                    return Err(ResponseStatus::PsaErrorAlreadyExists);
+                }
+                let derived_key_id = create_key_id(
+                    derived_key_triple.clone(),
+                    derived_key_attributes,
+                    &mut *store_handle,
+                    &mut local_ids_handle,
+                )?;
+
+                let derived_key_attrs =
+                    utils::convert_key_attributes(&derived_key_attributes, derived_key_id)?;
+
+                let _guard = self
+                    .key_handle_mutex
+                    .lock()
+                    .expect("Grabbing key handle mutex failed");
+
+                let derive_result;
+                // Safety:
+                //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+                //   * self.key_handle_mutex prevents concurrent accesses
+                //   * self.key_slot_semaphore prevents overflowing key slots
+                //   * the base key handle is only closed after the operation
+                unsafe {
+                    derive_result = KeyHandle::open(base_key_id).and_then(|mut base_key_handle| {
+                        let derived_key_handle = KeyHandle::derive(
+                            &derived_key_attrs,
+                            &base_key_handle,
+                            psa_alg,
+                            &salt,
+                            &info,
+                        );
+                        base_key_handle.close()?;
+                        derived_key_handle
+                    });
+                }
+
+                let mut derived_key_handle = derive_result.or_else(|e| {
+                    remove_key_id(
+                        &derived_key_triple,
+                        derived_key_id,
+                        &mut *store_handle,
+                        &mut local_ids_handle,
+                    )?;
+                    Err(e)
+                })?;
+
+                // Safety: same conditions than above.
+                unsafe {
+                    derived_key_handle.close()?;
+                }
+
+                Ok(psa_key_derivation::Result { output: Vec::new() })
+            }
+        }
+    }
+
+    fn psa_raw_key_agreement(
+        &self,
+        app_name: ApplicationName,
+        op: psa_raw_key_agreement::Operation,
+    ) -> Result<psa_raw_key_agreement::Result> {
+        info!("Mbed Provider - Raw Key Agreement");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let key_name = op.private_key_name;
+        let alg = op.alg;
+        let peer_key = op.peer_key;
+        let key_triple = KeyTriple::new(app_name, ProviderID::MbedCrypto, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_info(&key_triple, &*store_handle)?;
+
+        key_attributes.can_derive_from()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let psa_alg = utils::convert_algorithm(&alg.into())?;
+        // The shared secret is the x coordinate of the shared point.
+        // This should never panic on 32 bits or more machines.
+        let shared_secret_size = usize::try_from((key_attributes.key_bits + 7) / 8)
+            .expect("Conversion to usize failed.");
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let mut key_handle;
+        let shared_secret;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
+        //   * the key handle is only closed after the operation
+        unsafe {
+            key_handle = KeyHandle::open(key_id)?;
+            shared_secret =
+                utils::psa_raw_key_agreement(&key_handle, psa_alg, &peer_key, shared_secret_size);
+            key_handle.close()?;
+        }
+
+        Ok(psa_raw_key_agreement::Result {
+            shared_secret: shared_secret?,
+        })
+    }
+
+    fn psa_generate_random(
+        &self,
+        op: psa_generate_random::Operation,
+    ) -> Result<psa_generate_random::Result> {
+        info!("Mbed Provider - Generate Random");
+        let mut random_bytes = vec![0u8; op.size];
+
+        // Safety: at this point the provider has been instantiated so Mbed Crypto has been
+        // initialized. No key is involved in the operation.
+        let status =
+            unsafe { psa_crypto_binding::psa_generate_random(random_bytes.as_mut_ptr(), op.size) };
+
+        if status != PSA_SUCCESS {
+            error!("Generate random status: {}", status);
+            return Err(utils::convert_status(status));
+        }
+
+        Ok(psa_generate_random::Result { random_bytes })
+    }
+
+    fn psa_copy_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_copy_key::Operation,
+    ) -> Result<psa_copy_key::Result> {
+        info!("Mbed Provider - Copy Key");
+        let _semaphore_guard = self.key_slot_semaphore.access();
+        let source_key_triple =
+            KeyTriple::new(app_name.clone(), ProviderID::MbedCrypto, op.key_name);
+        let target_key_triple =
+            KeyTriple::new(app_name, ProviderID::MbedCrypto, op.target_key_name);
+        let target_key_attributes = op.attributes;
+        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
+        let mut local_ids_handle = self.local_ids.write().expect("Local ID lock poisoned");
+        let (source_key_id, source_key_attributes) =
+            get_key_info(&source_key_triple, &*store_handle)?;
+
+        check_copy_policy(source_key_attributes, target_key_attributes)?;
+
+        if key_id_exists(&target_key_triple, &*store_handle)? {
+            return Err(ResponseStatus::PsaErrorAlreadyExists);
+        }
+        let target_key_id = create_key_id(
+            target_key_triple.clone(),
+            target_key_attributes,
+            &mut *store_handle,
+            &mut local_ids_handle,
+        )?;
+
+        let target_key_attrs =
+            utils::convert_key_attributes(&target_key_attributes, target_key_id)?;
+
+        let _guard = self
+            .key_handle_mutex
+            .lock()
+            .expect("Grabbing key handle mutex failed");
+
+        let copy_result;
+        // Safety:
+        //   * at this point the provider has been instantiated so Mbed Crypto has been initialized
+        //   * self.key_handle_mutex prevents concurrent accesses
+        //   * self.key_slot_semaphore prevents overflowing key slots
+        //   * the source key handle is only closed after the operation
+        unsafe {
+            copy_result = KeyHandle::open(source_key_id).and_then(|mut source_key_handle| {
+                let target_key_handle = KeyHandle::copy(&target_key_attrs, &source_key_handle);
+                source_key_handle.close()?;
+                target_key_handle
+            });
+        }
+
+        let mut target_key_handle = copy_result.or_else(|e| {
+            remove_key_id(
+                &target_key_triple,
+                target_key_id,
+                &mut *store_handle,
+                &mut local_ids_handle,
+            )?;
+            Err(e)
+        })?;
+
+        // Safety: same conditions than above.
+        unsafe {
+            target_key_handle.close()?;
+        }
+
+        Ok(psa_copy_key::Result {})
     }
 }
 
@@ -14,15 +14,20 @@
 // limitations under the License.
 use super::constants::*;
 use super::psa_crypto_binding::{
-    self, psa_algorithm_t, psa_core_key_attributes_t, psa_key_attributes_t, psa_key_bits_t,
+    self, psa_algorithm_t, psa_cipher_operation_t, psa_core_key_attributes_t, psa_ecc_curve_t,
+    psa_hash_operation_t, psa_key_attributes_t, psa_key_bits_t, psa_key_derivation_operation_t,
     psa_key_handle_t, psa_key_id_t, psa_key_policy_s, psa_key_type_t, psa_key_usage_t,
-    psa_status_t,
+    psa_mac_operation_t, psa_status_t,
 };
 use log::error;
-use parsec_interface::operations::psa_algorithm::{Algorithm, AsymmetricSignature, Hash};
+use parsec_interface::operations::psa_algorithm::{
+    Aead, AeadWithDefaultLengthTag, Algorithm, AsymmetricEncryption, AsymmetricSignature, Cipher,
+    FullLengthMac, Hash, KeyAgreement, KeyDerivation, Mac, RawKeyAgreement,
+};
 use parsec_interface::operations::psa_key_attributes;
-use parsec_interface::operations::psa_key_attributes::KeyType;
+use parsec_interface::operations::psa_key_attributes::{EccFamily, KeyType};
 use parsec_interface::requests::{ResponseStatus, Result};
+use sha3::{Digest, Sha3_224, Sha3_256, Sha3_384, Sha3_512};
 use std::convert::TryFrom;
 use std::convert::TryInto;
 
@@ -39,7 +44,7 @@ pub fn convert_key_attributes(
 ) -> Result<psa_key_attributes_t> {
     Ok(psa_key_attributes_t {
         core: psa_core_key_attributes_t {
-            type_: convert_key_type(attrs.key_type)?,
+            type_: convert_key_type(attrs.key_type, attrs.key_bits)?,
             lifetime: PSA_KEY_LIFETIME_PERSISTENT,
             id: key_id,
             policy: psa_key_policy_s {
@@ -85,16 +90,53 @@ pub fn convert_key_bits(key_size: u32) -> psa_key_bits_t {
 ///
 /// # Errors
 ///
-/// Only `KeyType::RsaKeypair` and `KeyType::RsaPublicKey` are supported. Returns
+/// Only `KeyType::RsaKeypair`, `KeyType::RsaPublicKey`, `KeyType::EccKeyPair`,
+/// `KeyType::EccPublicKey`, `KeyType::Aes`, `KeyType::Chacha20`, `KeyType::Hmac`,
+/// `KeyType::Derive` and `KeyType::RawData` are supported. Returns
 /// ResponseStatus::PsaErrorNotSupported otherwise.
-pub fn convert_key_type(key_type: KeyType) -> Result<psa_key_type_t> {
+pub fn convert_key_type(key_type: KeyType, key_bits: u32) -> Result<psa_key_type_t> {
     match key_type {
+        KeyType::EccKeyPair { curve_family } => Ok(PSA_KEY_TYPE_ECC_KEYPAIR_BASE
+            | psa_key_type_t::from(convert_ecc_curve(curve_family, key_bits)?)),
+        KeyType::EccPublicKey { curve_family } => Ok(PSA_KEY_TYPE_ECC_PUBLIC_KEY_BASE
+            | psa_key_type_t::from(convert_ecc_curve(curve_family, key_bits)?)),
         KeyType::RsaKeyPair => Ok(PSA_KEY_TYPE_RSA_KEYPAIR),
         KeyType::RsaPublicKey => Ok(PSA_KEY_TYPE_RSA_PUBLIC_KEY),
+        KeyType::Aes => Ok(PSA_KEY_TYPE_AES),
+        KeyType::Chacha20 => Ok(PSA_KEY_TYPE_CHACHA20),
+        KeyType::Hmac => Ok(PSA_KEY_TYPE_HMAC),
+        KeyType::Derive => Ok(PSA_KEY_TYPE_DERIVE),
+        KeyType::RawData => Ok(PSA_KEY_TYPE_RAW_DATA),
         _ => Err(ResponseStatus::PsaErrorNotSupported),
     }
 }
 
+/// Converts a curve family and a key size to the specific Mbed Crypto curve they designate.
+fn convert_ecc_curve(curve_family: EccFamily, key_bits: u32) -> Result<psa_ecc_curve_t> {
+    match (curve_family, key_bits) {
+        (EccFamily::SecpK1, 192) => Ok(PSA_ECC_CURVE_SECP192K1),
+        (EccFamily::SecpK1, 224) => Ok(PSA_ECC_CURVE_SECP224K1),
+        (EccFamily::SecpK1, 256) => Ok(PSA_ECC_CURVE_SECP256K1),
+        (EccFamily::SecpR1, 192) => Ok(PSA_ECC_CURVE_SECP192R1),
+        (EccFamily::SecpR1, 224) => Ok(PSA_ECC_CURVE_SECP224R1),
+        (EccFamily::SecpR1, 256) => Ok(PSA_ECC_CURVE_SECP256R1),
+        (EccFamily::SecpR1, 384) => Ok(PSA_ECC_CURVE_SECP384R1),
+        (EccFamily::SecpR1, 521) => Ok(PSA_ECC_CURVE_SECP521R1),
+        (EccFamily::BrainpoolPR1, 256) => Ok(PSA_ECC_CURVE_BRAINPOOL_P256R1),
+        (EccFamily::BrainpoolPR1, 384) => Ok(PSA_ECC_CURVE_BRAINPOOL_P384R1),
+        (EccFamily::BrainpoolPR1, 512) => Ok(PSA_ECC_CURVE_BRAINPOOL_P512R1),
+        (EccFamily::Montgomery, 255) => Ok(PSA_ECC_CURVE_CURVE25519),
+        (EccFamily::Montgomery, 448) => Ok(PSA_ECC_CURVE_CURVE448),
+        _ => {
+            error!(
+                "The curve of the {:?} family with {} bits is not supported.",
+                curve_family, key_bits
+            );
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
 /// Converts between native and Mbed Crypto key usage values.
 pub fn convert_key_usage(operation: &psa_key_attributes::UsageFlags) -> psa_key_usage_t {
     let mut usage: psa_key_usage_t = 0;
@@ -134,7 +176,10 @@ pub fn convert_key_usage(operation: &psa_key_attributes::UsageFlags) -> psa_key_
 /// # Errors
 ///
 /// Only `AlgorithmInner::Sign` is supported as algorithm with only the
-/// `SignAlgorithm::RsaPkcs1v15Sign` signing algorithm. Will return
+/// `SignAlgorithm::RsaPkcs1v15Sign` and `SignAlgorithm::RsaPss` signing algorithms, as well as
+/// `Algorithm::AsymmetricEncryption` with the `RsaPkcs1v15Crypt` and `RsaOaep` algorithms,
+/// `Algorithm::Aead`, `Algorithm::Cipher` with block cipher modes other than ECB,
+/// `Algorithm::Mac`, `Algorithm::KeyDerivation` and raw ECDH key agreement. Will return
 /// ResponseStatus::PsaErrorNotSupported otherwise.
 pub fn convert_algorithm(alg: &Algorithm) -> Result<psa_algorithm_t> {
     let mut algo_val: psa_algorithm_t;
@@ -145,12 +190,107 @@ pub fn convert_algorithm(alg: &Algorithm) -> Result<psa_algorithm_t> {
                 algo_val |= convert_hash_algorithm(*hash_alg)? & PSA_ALG_HASH_MASK;
                 Ok(algo_val)
             }
+            AsymmetricSignature::RsaPss { hash_alg } => {
+                algo_val = PSA_ALG_RSA_PSS_BASE;
+                algo_val |= convert_hash_algorithm(*hash_alg)? & PSA_ALG_HASH_MASK;
+                Ok(algo_val)
+            }
+            _ => Err(ResponseStatus::PsaErrorNotSupported),
+        },
+        Algorithm::AsymmetricEncryption(asym_encrypt) => match asym_encrypt {
+            AsymmetricEncryption::RsaPkcs1v15Crypt => Ok(PSA_ALG_RSA_PKCS1V15_CRYPT),
+            AsymmetricEncryption::RsaOaep { hash_alg } => {
+                algo_val = PSA_ALG_RSA_OAEP_BASE;
+                algo_val |= convert_hash_algorithm(*hash_alg)? & PSA_ALG_HASH_MASK;
+                Ok(algo_val)
+            }
+        },
+        Algorithm::Aead(aead) => match aead {
+            Aead::AeadWithDefaultLengthTag(aead_alg) => Ok(convert_aead_algorithm(*aead_alg)),
+            Aead::AeadWithShortenedTag {
+                aead_alg,
+                tag_length,
+            } => {
+                let tag_length = psa_algorithm_t::try_from(*tag_length)
+                    .or(Err(ResponseStatus::PsaErrorNotSupported))?;
+                if tag_length > PSA_ALG_AEAD_TAG_LENGTH_MASK >> PSA_AEAD_TAG_LENGTH_OFFSET {
+                    error!("The AEAD tag length can not be encoded.");
+                    return Err(ResponseStatus::PsaErrorNotSupported);
+                }
+                algo_val = convert_aead_algorithm(*aead_alg) & !PSA_ALG_AEAD_TAG_LENGTH_MASK;
+                algo_val |= tag_length << PSA_AEAD_TAG_LENGTH_OFFSET;
+                Ok(algo_val)
+            }
+        },
+        Algorithm::Cipher(cipher) => match cipher {
+            Cipher::Ctr => Ok(PSA_ALG_CTR),
+            Cipher::Cfb => Ok(PSA_ALG_CFB),
+            Cipher::Ofb => Ok(PSA_ALG_OFB),
+            Cipher::Xts => Ok(PSA_ALG_XTS),
+            Cipher::CbcNoPadding => Ok(PSA_ALG_CBC_NO_PADDING),
+            Cipher::CbcPkcs7 => Ok(PSA_ALG_CBC_PKCS7),
+            // The ECB mode is not available in this version of Mbed Crypto.
             _ => Err(ResponseStatus::PsaErrorNotSupported),
         },
+        Algorithm::Mac(mac) => match mac {
+            Mac::FullLength(mac_alg) => convert_full_length_mac_algorithm(*mac_alg),
+            Mac::Truncated {
+                mac_alg,
+                mac_length,
+            } => {
+                let mac_length = psa_algorithm_t::try_from(*mac_length)
+                    .or(Err(ResponseStatus::PsaErrorNotSupported))?;
+                if mac_length > PSA_ALG_MAC_TRUNCATION_MASK >> PSA_MAC_TRUNCATION_OFFSET {
+                    error!("The MAC length can not be encoded.");
+                    return Err(ResponseStatus::PsaErrorNotSupported);
+                }
+                algo_val =
+                    convert_full_length_mac_algorithm(*mac_alg)? & !PSA_ALG_MAC_TRUNCATION_MASK;
+                algo_val |= mac_length << PSA_MAC_TRUNCATION_OFFSET;
+                Ok(algo_val)
+            }
+        },
+        Algorithm::KeyDerivation(key_derivation) => {
+            let (base, hash_alg) = match key_derivation {
+                KeyDerivation::Hkdf { hash_alg } => (PSA_ALG_HKDF_BASE, hash_alg),
+                KeyDerivation::Tls12Prf { hash_alg } => (PSA_ALG_TLS12_PRF_BASE, hash_alg),
+                KeyDerivation::Tls12PskToMs { hash_alg } => {
+                    (PSA_ALG_TLS12_PSK_TO_MS_BASE, hash_alg)
+                }
+            };
+            algo_val = base;
+            algo_val |= convert_hash_algorithm(*hash_alg)? & PSA_ALG_HASH_MASK;
+            Ok(algo_val)
+        }
+        Algorithm::KeyAgreement(KeyAgreement::Raw(RawKeyAgreement::Ecdh)) => {
+            algo_val = PSA_ALG_ECDH_BASE;
+            algo_val |= PSA_ALG_SELECT_RAW & PSA_ALG_KEY_DERIVATION_MASK;
+            Ok(algo_val)
+        }
         _ => Err(ResponseStatus::PsaErrorNotSupported),
     }
 }
 
+/// Converts between native and Mbed Crypto AEAD algorithm values, with the default tag length.
+fn convert_aead_algorithm(aead_alg: AeadWithDefaultLengthTag) -> psa_algorithm_t {
+    match aead_alg {
+        AeadWithDefaultLengthTag::Ccm => PSA_ALG_CCM,
+        AeadWithDefaultLengthTag::Gcm => PSA_ALG_GCM,
+        AeadWithDefaultLengthTag::Chacha20Poly1305 => PSA_ALG_CHACHA20_POLY1305,
+    }
+}
+
+/// Converts between native and Mbed Crypto MAC algorithm values, without truncation.
+fn convert_full_length_mac_algorithm(mac_alg: FullLengthMac) -> Result<psa_algorithm_t> {
+    match mac_alg {
+        FullLengthMac::Hmac { hash_alg } => {
+            Ok(PSA_ALG_HMAC_BASE | (convert_hash_algorithm(hash_alg)? & PSA_ALG_HASH_MASK))
+        }
+        FullLengthMac::CbcMac => Ok(PSA_ALG_CBC_MAC),
+        FullLengthMac::Cmac => Ok(PSA_ALG_CMAC),
+    }
+}
+
 /// Converts between native and Mbed Crypto hash algorithm values.
 pub fn convert_hash_algorithm(hash: Hash) -> Result<psa_algorithm_t> {
     match hash {
@@ -208,16 +348,450 @@ macro_rules! bits_to_bytes {
     };
 }
 
-/// Compute the size of the asymmetric signature, given the key attributes of the signing key.
+/// Compute the size of the asymmetric signature, given the Parsec key attributes of the signing
+/// key.
 /// Implementing `PSA_ASYMMETRIC_SIGN_OUTPUT_SIZE` as defined in `crypto_sizes.h` (Mbed Crypto).
-pub fn psa_asymmetric_sign_output_size(key_attrs: &psa_key_attributes_t) -> Result<usize> {
-    match key_attrs.core.type_ {
-        PSA_KEY_TYPE_RSA_KEYPAIR => Ok(usize::from(bits_to_bytes!(key_attrs.core.bits))),
-        PSA_KEY_TYPE_ECC_KEYPAIR_BASE => Ok(usize::from(bits_to_bytes!(key_attrs.core.bits) * 2)),
+pub fn psa_asymmetric_sign_output_size(
+    key_attributes: psa_key_attributes::KeyAttributes,
+) -> Result<usize> {
+    match key_attributes.key_type {
+        KeyType::RsaKeyPair => usize::try_from(bits_to_bytes!(key_attributes.key_bits))
+            .or(Err(ResponseStatus::PsaErrorNotSupported)),
+        KeyType::EccKeyPair { .. } => usize::try_from(bits_to_bytes!(key_attributes.key_bits) * 2)
+            .or(Err(ResponseStatus::PsaErrorNotSupported)),
         _ => Err(ResponseStatus::PsaErrorNotSupported),
     }
 }
 
+/// Compute the size of the output of an asymmetric encryption or decryption, given the Parsec
+/// key attributes of the key used.
+/// Implementing `PSA_ASYMMETRIC_ENCRYPT_OUTPUT_SIZE` and `PSA_ASYMMETRIC_DECRYPT_OUTPUT_SIZE` for
+/// RSA keys, as defined in `crypto_sizes.h` (Mbed Crypto).
+pub fn psa_asymmetric_crypt_output_size(
+    key_attributes: psa_key_attributes::KeyAttributes,
+) -> Result<usize> {
+    match key_attributes.key_type {
+        KeyType::RsaPublicKey | KeyType::RsaKeyPair => {
+            usize::try_from(bits_to_bytes!(key_attributes.key_bits))
+                .or(Err(ResponseStatus::PsaErrorNotSupported))
+        }
+        _ => Err(ResponseStatus::PsaErrorNotSupported),
+    }
+}
+
+/// Get the length of the authentication tag of an AEAD algorithm.
+/// Implementing `PSA_AEAD_TAG_LENGTH` as defined in `crypto_sizes.h` (Mbed Crypto).
+fn psa_aead_tag_length(alg: psa_algorithm_t) -> usize {
+    // This should never panic on 32 bits or more machines.
+    usize::try_from((alg & PSA_ALG_AEAD_TAG_LENGTH_MASK) >> PSA_AEAD_TAG_LENGTH_OFFSET)
+        .expect("Conversion to usize failed.")
+}
+
+/// Compute the size of the output of an AEAD encryption, given the algorithm and the length of
+/// the plaintext.
+/// Implementing `PSA_AEAD_ENCRYPT_OUTPUT_SIZE` as defined in `crypto_sizes.h` (Mbed Crypto).
+pub fn psa_aead_encrypt_output_size(alg: psa_algorithm_t, plaintext_length: usize) -> usize {
+    plaintext_length.saturating_add(psa_aead_tag_length(alg))
+}
+
+/// Compute the size of the output of an AEAD decryption, given the algorithm and the length of
+/// the ciphertext.
+/// Implementing `PSA_AEAD_DECRYPT_OUTPUT_SIZE` as defined in `crypto_sizes.h` (Mbed Crypto).
+pub fn psa_aead_decrypt_output_size(alg: psa_algorithm_t, ciphertext_length: usize) -> usize {
+    ciphertext_length.saturating_sub(psa_aead_tag_length(alg))
+}
+
+// Maximum size of a block of the supported block ciphers, `PSA_BLOCK_CIPHER_BLOCK_MAX_SIZE` in
+// `crypto_sizes.h` (Mbed Crypto).
+const PSA_BLOCK_CIPHER_BLOCK_MAX_SIZE: usize = 16;
+
+/// Encrypt or decrypt a message in one go with an unauthenticated cipher, using the IV given.
+/// The IV is not set if empty, for the modes that do not need one.
+///
+/// # Safety
+///
+/// Calling this function is only safe if:
+/// * the Mbed Crypto library has already been initialized
+/// * the key handle has been opened and is not closed before the end of this function
+pub unsafe fn psa_cipher_crypt(
+    key_handle: &KeyHandle,
+    alg: psa_algorithm_t,
+    iv: &[u8],
+    input: &[u8],
+    encrypt: bool,
+) -> Result<Vec<u8>> {
+    // An all-zero structure is the valid initial state of a cipher operation
+    // (`PSA_CIPHER_OPERATION_INIT`).
+    let mut operation: psa_cipher_operation_t = std::mem::zeroed();
+    let output_size = input.len().saturating_add(PSA_BLOCK_CIPHER_BLOCK_MAX_SIZE);
+    let mut output = vec![0u8; output_size];
+    let mut update_length = 0;
+    let mut finish_length = 0;
+
+    let mut status = if encrypt {
+        psa_crypto_binding::psa_cipher_encrypt_setup(&mut operation, key_handle.raw(), alg)
+    } else {
+        psa_crypto_binding::psa_cipher_decrypt_setup(&mut operation, key_handle.raw(), alg)
+    };
+    if status == PSA_SUCCESS && !iv.is_empty() {
+        status = psa_crypto_binding::psa_cipher_set_iv(&mut operation, iv.as_ptr(), iv.len());
+    }
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_cipher_update(
+            &mut operation,
+            input.as_ptr(),
+            input.len(),
+            output.as_mut_ptr(),
+            output_size,
+            &mut update_length,
+        );
+    }
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_cipher_finish(
+            &mut operation,
+            output[update_length..].as_mut_ptr(),
+            output_size - update_length,
+            &mut finish_length,
+        );
+    }
+
+    if status != PSA_SUCCESS {
+        error!("Cipher operation status: {}", status);
+        let _ = psa_crypto_binding::psa_cipher_abort(&mut operation);
+        Err(convert_status(status))
+    } else {
+        output.resize(update_length + finish_length, 0);
+        Ok(output)
+    }
+}
+
+// Maximum size of a MAC, `PSA_MAC_MAX_SIZE` in `crypto_sizes.h` (Mbed Crypto).
+const PSA_MAC_MAX_SIZE: usize = 64;
+
+/// Compute the MAC of a message in one go.
+///
+/// # Safety
+///
+/// Calling this function is only safe if:
+/// * the Mbed Crypto library has already been initialized
+/// * the key handle has been opened and is not closed before the end of this function
+pub unsafe fn psa_mac_compute(
+    key_handle: &KeyHandle,
+    alg: psa_algorithm_t,
+    input: &[u8],
+) -> Result<Vec<u8>> {
+    // An all-zero structure is the valid initial state of a MAC operation
+    // (`PSA_MAC_OPERATION_INIT`).
+    let mut operation: psa_mac_operation_t = std::mem::zeroed();
+    let mut mac = vec![0u8; PSA_MAC_MAX_SIZE];
+    let mut mac_length = 0;
+
+    let mut status = psa_crypto_binding::psa_mac_sign_setup(&mut operation, key_handle.raw(), alg);
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_mac_update(&mut operation, input.as_ptr(), input.len());
+    }
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_mac_sign_finish(
+            &mut operation,
+            mac.as_mut_ptr(),
+            PSA_MAC_MAX_SIZE,
+            &mut mac_length,
+        );
+    }
+
+    if status != PSA_SUCCESS {
+        error!("MAC compute status: {}", status);
+        let _ = psa_crypto_binding::psa_mac_abort(&mut operation);
+        Err(convert_status(status))
+    } else {
+        mac.resize(mac_length, 0);
+        Ok(mac)
+    }
+}
+
+/// Verify the MAC of a message in one go. The comparison is done in constant time by Mbed
+/// Crypto and a mismatch is reported as `PsaErrorInvalidSignature`.
+///
+/// # Safety
+///
+/// Calling this function is only safe if:
+/// * the Mbed Crypto library has already been initialized
+/// * the key handle has been opened and is not closed before the end of this function
+pub unsafe fn psa_mac_verify(
+    key_handle: &KeyHandle,
+    alg: psa_algorithm_t,
+    input: &[u8],
+    mac: &[u8],
+) -> Result<()> {
+    // An all-zero structure is the valid initial state of a MAC operation
+    // (`PSA_MAC_OPERATION_INIT`).
+    let mut operation: psa_mac_operation_t = std::mem::zeroed();
+
+    let mut status =
+        psa_crypto_binding::psa_mac_verify_setup(&mut operation, key_handle.raw(), alg);
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_mac_update(&mut operation, input.as_ptr(), input.len());
+    }
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_mac_verify_finish(&mut operation, mac.as_ptr(), mac.len());
+    }
+
+    if status != PSA_SUCCESS {
+        error!("MAC verify status: {}", status);
+        let _ = psa_crypto_binding::psa_mac_abort(&mut operation);
+        Err(convert_status(status))
+    } else {
+        Ok(())
+    }
+}
+
+// Maximum size of a hash, `PSA_HASH_MAX_SIZE` in `crypto_sizes.h` (Mbed Crypto).
+const PSA_HASH_MAX_SIZE: usize = 64;
+
+// Size of the chunks in which the input of a hash operation is given to Mbed Crypto.
+const HASH_CHUNK_SIZE: usize = 4096;
+
+/// Set up a hash operation and give it the whole input, in chunks.
+///
+/// # Safety
+///
+/// Calling this function is only safe if the Mbed Crypto library has already been initialized.
+unsafe fn psa_hash_setup_and_update(
+    operation: &mut psa_hash_operation_t,
+    alg: psa_algorithm_t,
+    input: &[u8],
+) -> psa_status_t {
+    let mut status = psa_crypto_binding::psa_hash_setup(operation, alg);
+    for chunk in input.chunks(HASH_CHUNK_SIZE) {
+        if status != PSA_SUCCESS {
+            break;
+        }
+        status = psa_crypto_binding::psa_hash_update(operation, chunk.as_ptr(), chunk.len());
+    }
+
+    status
+}
+
+/// Compute the hash of a message in one go. The message is given to Mbed Crypto in chunks.
+///
+/// # Safety
+///
+/// Calling this function is only safe if the Mbed Crypto library has already been initialized.
+pub unsafe fn psa_hash_compute(alg: psa_algorithm_t, input: &[u8]) -> Result<Vec<u8>> {
+    // An all-zero structure is the valid initial state of a hash operation
+    // (`PSA_HASH_OPERATION_INIT`).
+    let mut operation: psa_hash_operation_t = std::mem::zeroed();
+    let mut hash = vec![0u8; PSA_HASH_MAX_SIZE];
+    let mut hash_length = 0;
+
+    let mut status = psa_hash_setup_and_update(&mut operation, alg, input);
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_hash_finish(
+            &mut operation,
+            hash.as_mut_ptr(),
+            PSA_HASH_MAX_SIZE,
+            &mut hash_length,
+        );
+    }
+
+    if status != PSA_SUCCESS {
+        error!("Hash compute status: {}", status);
+        let _ = psa_crypto_binding::psa_hash_abort(&mut operation);
+        Err(convert_status(status))
+    } else {
+        hash.resize(hash_length, 0);
+        Ok(hash)
+    }
+}
+
+/// Compare the hash of a message with an expected value in one go. The comparison is done in
+/// constant time by Mbed Crypto and a mismatch is reported as `PsaErrorInvalidSignature`.
+///
+/// # Safety
+///
+/// Calling this function is only safe if the Mbed Crypto library has already been initialized.
+pub unsafe fn psa_hash_compare(alg: psa_algorithm_t, input: &[u8], hash: &[u8]) -> Result<()> {
+    // An all-zero structure is the valid initial state of a hash operation
+    // (`PSA_HASH_OPERATION_INIT`).
+    let mut operation: psa_hash_operation_t = std::mem::zeroed();
+
+    let mut status = psa_hash_setup_and_update(&mut operation, alg, input);
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_hash_verify(&mut operation, hash.as_ptr(), hash.len());
+    }
+
+    if status != PSA_SUCCESS {
+        error!("Hash compare status: {}", status);
+        let _ = psa_crypto_binding::psa_hash_abort(&mut operation);
+        Err(convert_status(status))
+    } else {
+        Ok(())
+    }
+}
+
+/// Compute the hash of a message with an algorithm of the SHA-3 family. The SHA-3 family is not
+/// implemented by Mbed Crypto 2.0.0 and is computed in software instead. Returns `None` for the
+/// other hash algorithms.
+pub fn sha3_hash(alg: Hash, input: &[u8]) -> Option<Vec<u8>> {
+    match alg {
+        Hash::Sha3_224 => Some(Sha3_224::digest(input).to_vec()),
+        Hash::Sha3_256 => Some(Sha3_256::digest(input).to_vec()),
+        Hash::Sha3_384 => Some(Sha3_384::digest(input).to_vec()),
+        Hash::Sha3_512 => Some(Sha3_512::digest(input).to_vec()),
+        _ => None,
+    }
+}
+
+/// Compare a hash with an expected value in constant time. A mismatch is reported as
+/// `PsaErrorInvalidSignature`, as `psa_hash_verify` does.
+pub fn compare_hashes(hash: &[u8], expected: &[u8]) -> Result<()> {
+    let difference = hash
+        .iter()
+        .zip(expected)
+        .fold(0, |difference, (byte, expected_byte)| {
+            difference | (byte ^ expected_byte)
+        });
+
+    if hash.len() == expected.len() && difference == 0 {
+        Ok(())
+    } else {
+        error!("The hash of the message does not match the hash given.");
+        Err(ResponseStatus::PsaErrorInvalidSignature)
+    }
+}
+
+/// Set up a key derivation operation and provide all its inputs.
+///
+/// For HKDF, the salt and info are used as the salt and info inputs. For the TLS 1.2 PRF and
+/// PSK-to-MS algorithms, they are used as the seed and label inputs.
+///
+/// # Safety
+///
+/// Calling this function is only safe if:
+/// * the Mbed Crypto library has already been initialized
+/// * the key handle has been opened and is not closed before the end of this function
+unsafe fn psa_key_derivation_setup(
+    operation: &mut psa_key_derivation_operation_t,
+    base_key_handle: &KeyHandle,
+    alg: psa_algorithm_t,
+    salt: &[u8],
+    info: &[u8],
+) -> psa_status_t {
+    let (salt_step, info_step) = if alg & !PSA_ALG_HASH_MASK == PSA_ALG_HKDF_BASE {
+        (PSA_KEY_DERIVATION_INPUT_SALT, PSA_KEY_DERIVATION_INPUT_INFO)
+    } else {
+        (
+            PSA_KEY_DERIVATION_INPUT_SEED,
+            PSA_KEY_DERIVATION_INPUT_LABEL,
+        )
+    };
+
+    let mut status = psa_crypto_binding::psa_key_derivation_setup(operation, alg);
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_key_derivation_input_bytes(
+            operation,
+            salt_step,
+            salt.as_ptr(),
+            salt.len(),
+        );
+    }
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_key_derivation_input_key(
+            operation,
+            PSA_KEY_DERIVATION_INPUT_SECRET,
+            base_key_handle.raw(),
+        );
+    }
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_key_derivation_input_bytes(
+            operation,
+            info_step,
+            info.as_ptr(),
+            info.len(),
+        );
+    }
+
+    status
+}
+
+/// Derive raw bytes from a base key in one go.
+///
+/// # Safety
+///
+/// Calling this function is only safe if:
+/// * the Mbed Crypto library has already been initialized
+/// * the key handle has been opened and is not closed before the end of this function
+pub unsafe fn psa_key_derivation_output_bytes(
+    base_key_handle: &KeyHandle,
+    alg: psa_algorithm_t,
+    salt: &[u8],
+    info: &[u8],
+    length: usize,
+) -> Result<Vec<u8>> {
+    // An all-zero structure is the valid initial state of a key derivation operation
+    // (`PSA_KEY_DERIVATION_OPERATION_INIT`).
+    let mut operation: psa_key_derivation_operation_t = std::mem::zeroed();
+    let mut output = vec![0u8; length];
+
+    let mut status = psa_key_derivation_setup(&mut operation, base_key_handle, alg, salt, info);
+    if status == PSA_SUCCESS {
+        status = psa_crypto_binding::psa_key_derivation_output_bytes(
+            &mut operation,
+            output.as_mut_ptr(),
+            length,
+        );
+    }
+    let _ = psa_crypto_binding::psa_key_derivation_abort(&mut operation);
+
+    if status != PSA_SUCCESS {
+        error!("Key derivation status: {}", status);
+        Err(convert_status(status))
+    } else {
+        Ok(output)
+    }
+}
+
+/// Size in bytes of the coordinates of a point on the curve of an Elliptic Curve key.
+fn psa_ecc_field_size(key_attrs: &psa_key_attributes_t) -> usize {
+    (usize::from(key_attrs.core.bits) + 7) / 8
+}
+
+/// Compute the shared secret of a raw key agreement between a private key and the public key
+/// of a peer. For ECDH, the shared secret is the x coordinate of the shared point.
+///
+/// # Safety
+///
+/// Calling this function is only safe if:
+/// * the Mbed Crypto library has already been initialized
+/// * the key handle has been opened and is not closed before the end of this function
+pub unsafe fn psa_raw_key_agreement(
+    private_key_handle: &KeyHandle,
+    alg: psa_algorithm_t,
+    peer_key: &[u8],
+    output_size: usize,
+) -> Result<Vec<u8>> {
+    let mut shared_secret = vec![0u8; output_size];
+    let mut shared_secret_length = 0;
+
+    let status = psa_crypto_binding::psa_raw_key_agreement(
+        alg,
+        private_key_handle.raw(),
+        peer_key.as_ptr(),
+        peer_key.len(),
+        shared_secret.as_mut_ptr(),
+        output_size,
+        &mut shared_secret_length,
+    );
+
+    if status != PSA_SUCCESS {
+        error!("Raw key agreement status: {}", status);
+        Err(convert_status(status))
+    } else {
+        shared_secret.resize(shared_secret_length, 0);
+        Ok(shared_secret)
+    }
+}
+
 /// Compute the size of the public key material to be exported, given the attributes of the key.
 /// Implementing `PSA_KEY_EXPORT_MAX_SIZE` for public keys only, as defined in `crypto_sizes.h` (Mbed Crypto).
 pub fn psa_export_public_key_size(key_attrs: &psa_key_attributes_t) -> Result<usize> {
@@ -231,6 +805,44 @@ pub fn psa_export_public_key_size(key_attrs: &psa_key_attributes_t) -> Result<us
         PSA_KEY_TYPE_RSA_PUBLIC_KEY | PSA_KEY_TYPE_RSA_KEYPAIR => Ok(usize::from(
             export_asn1_int_max_size!(key_attrs.core.bits) + 11,
         )),
+        // Uncompressed point format: 0x04 || x || y
+        key_type
+            if key_type & !(PSA_KEY_TYPE_ECC_CURVE_MASK | PSA_KEY_TYPE_CATEGORY_FLAG_PAIR)
+                == PSA_KEY_TYPE_ECC_PUBLIC_KEY_BASE =>
+        {
+            Ok(2 * psa_ecc_field_size(key_attrs) + 1)
+        }
+        _ => Err(ResponseStatus::PsaErrorNotSupported),
+    }
+}
+
+/// Compute the size of the key material to be exported, given the attributes of the key.
+/// Implementing `PSA_KEY_EXPORT_MAX_SIZE` for key pairs and symmetric keys, as defined in
+/// `crypto_sizes.h` (Mbed Crypto). The size of public keys is given by
+/// `psa_export_public_key_size`.
+pub fn psa_export_key_size(key_attrs: &psa_key_attributes_t) -> Result<usize> {
+    macro_rules! export_asn1_int_max_size {
+        ($size:expr) => {
+            ($size) / 8 + 5
+        };
+    };
+
+    let key_bits = usize::from(key_attrs.core.bits);
+    match key_attrs.core.type_ {
+        PSA_KEY_TYPE_RSA_KEYPAIR => Ok(9 * export_asn1_int_max_size!(key_bits / 2 + 1) + 14),
+        // The private value of the key pair, as a big-endian integer.
+        key_type if key_type & !PSA_KEY_TYPE_ECC_CURVE_MASK == PSA_KEY_TYPE_ECC_KEYPAIR_BASE => {
+            Ok((key_bits + 7) / 8)
+        }
+        key_type if key_type & PSA_KEY_TYPE_CATEGORY_MASK == PSA_KEY_TYPE_CATEGORY_PUBLIC_KEY => {
+            psa_export_public_key_size(key_attrs)
+        }
+        key_type
+            if key_type & PSA_KEY_TYPE_CATEGORY_MASK == PSA_KEY_TYPE_CATEGORY_SYMMETRIC
+                || key_type & PSA_KEY_TYPE_CATEGORY_MASK == PSA_KEY_TYPE_CATEGORY_RAW =>
+        {
+            Ok((key_bits + 7) / 8)
+        }
         _ => Err(ResponseStatus::PsaErrorNotSupported),
     }
 }
@@ -311,6 +923,45 @@ impl KeyHandle {
         }
     }
 
+    /// Derive a new key from a base key. The new key is created with the attributes given.
+    ///
+    /// # Safety
+    ///
+    /// Calling this function is only safe if:
+    /// * the Mbed Crypto library has already been initialized
+    /// * calls to open, generate, import and close are protected by the same mutex
+    /// * only PSA_KEY_SLOT_COUNT slots are used at any given time
+    /// * the base key handle has been opened and is not closed before the end of this function
+    pub unsafe fn derive(
+        attributes: &psa_key_attributes_t,
+        base_key_handle: &KeyHandle,
+        alg: psa_algorithm_t,
+        salt: &[u8],
+        info: &[u8],
+    ) -> Result<Self> {
+        // An all-zero structure is the valid initial state of a key derivation operation
+        // (`PSA_KEY_DERIVATION_OPERATION_INIT`).
+        let mut operation: psa_key_derivation_operation_t = std::mem::zeroed();
+        let mut key_handle: psa_key_handle_t = Default::default();
+
+        let mut status = psa_key_derivation_setup(&mut operation, base_key_handle, alg, salt, info);
+        if status == PSA_SUCCESS {
+            status = psa_crypto_binding::psa_key_derivation_output_key(
+                attributes,
+                &mut operation,
+                &mut key_handle,
+            );
+        }
+        let _ = psa_crypto_binding::psa_key_derivation_abort(&mut operation);
+
+        if status != PSA_SUCCESS {
+            error!("Derive key status: {}", status);
+            Err(convert_status(status))
+        } else {
+            Ok(KeyHandle(key_handle))
+        }
+    }
+
     /// Import a key in binary format.
     ///
     /// # Safety
@@ -335,6 +986,30 @@ impl KeyHandle {
         }
     }
 
+    /// Copy a key. The new key is created with the attributes given.
+    ///
+    /// # Safety
+    ///
+    /// Calling this function is only safe if:
+    /// * the Mbed Crypto library has already been initialized
+    /// * calls to open, generate, import and close are protected by the same mutex
+    /// * only PSA_KEY_SLOT_COUNT slots are used at any given time
+    /// * the source key handle has been opened and is not closed before the end of this function
+    pub unsafe fn copy(
+        attributes: &psa_key_attributes_t,
+        source_key_handle: &KeyHandle,
+    ) -> Result<Self> {
+        let mut key_handle: psa_key_handle_t = Default::default();
+        let status =
+            psa_crypto_binding::psa_copy_key(source_key_handle.raw(), attributes, &mut key_handle);
+        if status != PSA_SUCCESS {
+            error!("Copy key status: {}", status);
+            Err(convert_status(status))
+        } else {
+            Ok(KeyHandle(key_handle))
+        }
+    }
+
     /// Get the attributes associated with the key stored in this handle.
     ///
     /// # Safety
@@ -18,8 +18,10 @@
 //! are the real implementors of the operations that Parsec claims to support. They map to
 //! functionality in the underlying hardware which allows the PSA Crypto operations to be
 //! backed by a hardware root of trust.
+use crate::utils::Secret;
 use parsec_interface::requests::ProviderID;
 use serde::Deserialize;
+use std::path::PathBuf;
 
 pub mod core_provider;
 
@@ -37,6 +39,8 @@ pub mod tpm_provider;
 // to the one described in the Internally Tagged Enum representation
 // where "provider_type" is the tag field. For details see:
 // https://serde.rs/enum-representations.html
+// Secret fields can also be read from a file, an environment variable or a systemd credential
+// with the fields of the same name suffixed by "_file", "_env" or "_credential".
 #[serde(tag = "provider_type")]
 pub enum ProviderConfig {
     MbedCrypto {
@@ -45,18 +49,50 @@ pub enum ProviderConfig {
     Pkcs11 {
         key_id_manager: String,
         library_path: String,
-        slot_number: usize,
-        user_pin: Option<String>,
+        slot_number: Option<usize>,
+        token_label: Option<String>,
+        serial_number: Option<String>,
+        user_pin: Option<Secret>,
+        user_pin_file: Option<PathBuf>,
+        user_pin_env: Option<String>,
+        user_pin_credential: Option<String>,
+        session_pool_size: Option<usize>,
+        adopted_keys: Option<Vec<AdoptedKeyConfig>>,
     },
     Tpm {
         key_id_manager: String,
         tcti: String,
-        owner_hierarchy_auth: String,
+        owner_hierarchy_auth: Option<Secret>,
+        owner_hierarchy_auth_file: Option<PathBuf>,
+        owner_hierarchy_auth_env: Option<String>,
+        owner_hierarchy_auth_credential: Option<String>,
+        persistent_keys: Option<bool>,
     },
 }
 
 use self::ProviderConfig::{MbedCrypto, Pkcs11, Tpm};
 
+/// Configuration of a key already present in a PKCS 11 token which is adopted by the provider
+/// when it starts.
+///
+/// The key objects are found with their `CKA_ID` or, if it is not given, with their `CKA_LABEL`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AdoptedKeyConfig {
+    /// Name of the application the key is given to
+    pub app_name: String,
+    /// Name of the key for this application
+    pub key_name: String,
+    /// `CKA_ID` of the key objects, as an hexadecimal string
+    pub id: Option<String>,
+    /// `CKA_LABEL` of one of the key objects
+    pub label: Option<String>,
+    /// Algorithm permitted for the key
+    pub algorithm: Algorithm,
+    /// Whether the key objects are destroyed with the key. Defaults to `false`, only the mapping
+    /// of the key is then removed.
+    pub destroy_token_objects: Option<bool>,
+}
+
 impl ProviderConfig {
     pub fn key_id_manager(&self) -> &String {
         match *self {
@@ -81,11 +117,20 @@ impl ProviderConfig {
 }
 
 use crate::authenticators::ApplicationName;
+use crate::key_id_managers::{self, KeyTriple, ManageKeyIDs};
+use log::{error, info};
+use parsec_interface::operations::psa_algorithm::{Algorithm, AsymmetricSignature, Hash};
+use parsec_interface::operations::psa_key_attributes::{KeyAttributes, UsageFlags};
 use parsec_interface::operations::{
-    list_opcodes, list_providers, ping, psa_destroy_key, psa_export_public_key, psa_generate_key,
-    psa_import_key, psa_sign_hash, psa_verify_hash,
+    attest_key, list_opcodes, list_providers, ping, psa_aead_decrypt, psa_aead_encrypt,
+    psa_asymmetric_decrypt, psa_asymmetric_encrypt, psa_cipher_decrypt, psa_cipher_encrypt,
+    psa_copy_key, psa_destroy_key, psa_export_key, psa_export_public_key, psa_generate_key,
+    psa_generate_random, psa_get_key_attributes, psa_hash_compare, psa_hash_compute,
+    psa_import_key, psa_key_derivation, psa_mac_compute, psa_mac_verify, psa_raw_key_agreement,
+    psa_sign_hash, psa_sign_message, psa_verify_hash, psa_verify_message, seal_data, unseal_data,
 };
 use parsec_interface::requests::{ResponseStatus, Result};
+use std::sync::{Arc, RwLock};
 
 /// Provider interface for servicing client operations
 ///
@@ -97,6 +142,12 @@ pub trait Provide {
     /// The descriptions are gathered in the Core Provider and returned for a ListProviders operation.
     fn describe(&self) -> Result<list_providers::ProviderInfo>;
 
+    /// Return the key ID manager in which the current provider stores information about its keys,
+    /// or `None` if the provider does not manage keys.
+    fn key_id_store(&self) -> Option<&Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>> {
+        None
+    }
+
     /// List the providers running in the service.
     fn list_providers(&self, _op: list_providers::Operation) -> Result<list_providers::Result> {
         Err(ResponseStatus::PsaErrorNotSupported)
@@ -142,6 +193,15 @@ pub trait Provide {
         Err(ResponseStatus::PsaErrorNotSupported)
     }
 
+    /// Execute an ExportKey operation.
+    fn psa_export_key(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_export_key::Operation,
+    ) -> Result<psa_export_key::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
     /// Execute a DestroyKey operation.
     fn psa_destroy_key(
         &self,
@@ -169,4 +229,274 @@ pub trait Provide {
     ) -> Result<psa_verify_hash::Result> {
         Err(ResponseStatus::PsaErrorNotSupported)
     }
+
+    /// Execute an AsymmetricEncrypt operation.
+    fn psa_asymmetric_encrypt(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_asymmetric_encrypt::Operation,
+    ) -> Result<psa_asymmetric_encrypt::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute an AsymmetricDecrypt operation.
+    fn psa_asymmetric_decrypt(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_asymmetric_decrypt::Operation,
+    ) -> Result<psa_asymmetric_decrypt::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute an AeadEncrypt operation.
+    fn psa_aead_encrypt(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_aead_encrypt::Operation,
+    ) -> Result<psa_aead_encrypt::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute an AeadDecrypt operation.
+    fn psa_aead_decrypt(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_aead_decrypt::Operation,
+    ) -> Result<psa_aead_decrypt::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a CipherEncrypt operation.
+    fn psa_cipher_encrypt(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_cipher_encrypt::Operation,
+    ) -> Result<psa_cipher_encrypt::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a CipherDecrypt operation.
+    fn psa_cipher_decrypt(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_cipher_decrypt::Operation,
+    ) -> Result<psa_cipher_decrypt::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a MacCompute operation.
+    fn psa_mac_compute(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_mac_compute::Operation,
+    ) -> Result<psa_mac_compute::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a MacVerify operation.
+    fn psa_mac_verify(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_mac_verify::Operation,
+    ) -> Result<psa_mac_verify::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a HashCompute operation. This operation does not involve any key and is therefore
+    /// not linked to an application.
+    fn psa_hash_compute(
+        &self,
+        _op: psa_hash_compute::Operation,
+    ) -> Result<psa_hash_compute::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a HashCompare operation. This operation does not involve any key and is therefore
+    /// not linked to an application.
+    fn psa_hash_compare(
+        &self,
+        _op: psa_hash_compare::Operation,
+    ) -> Result<psa_hash_compare::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a KeyDerivation operation.
+    fn psa_key_derivation(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_key_derivation::Operation,
+    ) -> Result<psa_key_derivation::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a RawKeyAgreement operation.
+    fn psa_raw_key_agreement(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_raw_key_agreement::Operation,
+    ) -> Result<psa_raw_key_agreement::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a GenerateRandom operation. This operation does not involve any key and is
+    /// therefore not linked to an application.
+    fn psa_generate_random(
+        &self,
+        _op: psa_generate_random::Operation,
+    ) -> Result<psa_generate_random::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a CopyKey operation.
+    fn psa_copy_key(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_copy_key::Operation,
+    ) -> Result<psa_copy_key::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute an AttestKey operation.
+    fn attest_key(
+        &self,
+        _app_name: ApplicationName,
+        _op: attest_key::Operation,
+    ) -> Result<attest_key::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a SealData operation.
+    fn seal_data(
+        &self,
+        _app_name: ApplicationName,
+        _op: seal_data::Operation,
+    ) -> Result<seal_data::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute an UnsealData operation.
+    fn unseal_data(
+        &self,
+        _app_name: ApplicationName,
+        _op: unseal_data::Operation,
+    ) -> Result<unseal_data::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a GetKeyAttributes operation.
+    ///
+    /// The attributes are read from the key ID manager of the provider, which makes this
+    /// implementation common to all providers managing keys.
+    fn psa_get_key_attributes(
+        &self,
+        app_name: ApplicationName,
+        op: psa_get_key_attributes::Operation,
+    ) -> Result<psa_get_key_attributes::Result> {
+        let provider_id = self.describe()?.id;
+        info!("{:?} Provider - Get Key Attributes", provider_id);
+
+        let key_id_store = self
+            .key_id_store()
+            .ok_or(ResponseStatus::PsaErrorNotSupported)?;
+        let key_triple = KeyTriple::new(app_name, provider_id, op.key_name);
+        let store_handle = key_id_store.read().expect("Key store lock poisoned");
+
+        match store_handle.get(&key_triple) {
+            Ok(Some(key_info)) => Ok(psa_get_key_attributes::Result {
+                attributes: key_info.attributes,
+            }),
+            Ok(None) => Err(ResponseStatus::PsaErrorDoesNotExist),
+            Err(string) => Err(key_id_managers::to_response_status(string)),
+        }
+    }
+
+    /// Execute a SignMessage operation.
+    fn psa_sign_message(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_sign_message::Operation,
+    ) -> Result<psa_sign_message::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+
+    /// Execute a VerifyMessage operation.
+    fn psa_verify_message(
+        &self,
+        _app_name: ApplicationName,
+        _op: psa_verify_message::Operation,
+    ) -> Result<psa_verify_message::Result> {
+        Err(ResponseStatus::PsaErrorNotSupported)
+    }
+}
+
+/// Get the hash algorithm used by a signature algorithm to hash messages.
+///
+/// Signature algorithms which do not specify a hash algorithm can only be used on hashes: an
+/// error of `PsaErrorInvalidArgument` is returned for them.
+pub fn signature_hash_alg(alg: AsymmetricSignature) -> Result<Hash> {
+    match alg {
+        AsymmetricSignature::RsaPkcs1v15Sign { hash_alg }
+        | AsymmetricSignature::RsaPss { hash_alg }
+        | AsymmetricSignature::Ecdsa { hash_alg }
+        | AsymmetricSignature::DeterministicEcdsa { hash_alg } => Ok(hash_alg),
+        _ => {
+            error!("Signing a message requires a signature algorithm with a hash algorithm.");
+            Err(ResponseStatus::PsaErrorInvalidArgument)
+        }
+    }
+}
+
+/// Check that a key with the target attributes can be created as a copy of a key with the source
+/// attributes.
+///
+/// The source key needs to allow copies and the copy can only restrict its policy: it must have
+/// the same type, size and permitted algorithm and can not have usage flags which are not set on
+/// the source key.
+pub fn check_copy_policy(source: KeyAttributes, target: KeyAttributes) -> Result<()> {
+    if !source.key_policy.key_usage_flags.copy {
+        error!("The source key does not allow to be copied.");
+        return Err(ResponseStatus::PsaErrorNotPermitted);
+    }
+
+    if target.key_type != source.key_type || target.key_bits != source.key_bits {
+        error!("The type and size of a copied key can not be changed.");
+        return Err(ResponseStatus::PsaErrorInvalidArgument);
+    }
+
+    if target.key_policy.key_algorithm != source.key_policy.key_algorithm {
+        error!("The permitted algorithm of a copied key can not be changed.");
+        return Err(ResponseStatus::PsaErrorNotPermitted);
+    }
+
+    let source_flags = source.key_policy.key_usage_flags;
+    let target_flags = target.key_policy.key_usage_flags;
+    let is_subset = |source_flag: bool, target_flag: bool| source_flag || !target_flag;
+    let UsageFlags {
+        sign_hash,
+        verify_hash,
+        sign_message,
+        verify_message,
+        export,
+        encrypt,
+        decrypt,
+        cache,
+        copy,
+        derive,
+    } = target_flags;
+    if !(is_subset(source_flags.sign_hash, sign_hash)
+        && is_subset(source_flags.verify_hash, verify_hash)
+        && is_subset(source_flags.sign_message, sign_message)
+        && is_subset(source_flags.verify_message, verify_message)
+        && is_subset(source_flags.export, export)
+        && is_subset(source_flags.encrypt, encrypt)
+        && is_subset(source_flags.decrypt, decrypt)
+        && is_subset(source_flags.cache, cache)
+        && is_subset(source_flags.copy, copy)
+        && is_subset(source_flags.derive, derive))
+    {
+        error!("The usage flags of a copied key must be a subset of the ones of the source key.");
+        return Err(ResponseStatus::PsaErrorNotPermitted);
+    }
+
+    Ok(())
 }
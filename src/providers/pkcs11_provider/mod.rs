@@ -16,47 +16,76 @@
 //!
 //! This provider allows clients to access any PKCS 11 compliant device
 //! through the Parsec interface.
-use super::Provide;
+use super::{signature_hash_alg, AdoptedKeyConfig, Provide};
 use crate::authenticators::ApplicationName;
 use crate::key_id_managers;
 use crate::key_id_managers::{KeyInfo, KeyTriple, ManageKeyIDs};
+use crate::utils::Secret;
 use derivative::Derivative;
 use log::{error, info, warn};
 use parsec_interface::operations::list_providers::ProviderInfo;
 use parsec_interface::operations::psa_algorithm::*;
 use parsec_interface::operations::psa_key_attributes::*;
 use parsec_interface::operations::{
-    list_opcodes, psa_destroy_key, psa_export_public_key, psa_generate_key, psa_import_key,
-    psa_sign_hash, psa_verify_hash,
+    list_opcodes, psa_asymmetric_decrypt, psa_asymmetric_encrypt, psa_cipher_decrypt,
+    psa_cipher_encrypt, psa_destroy_key, psa_export_key, psa_export_public_key, psa_generate_key,
+    psa_generate_random, psa_import_key, psa_mac_compute, psa_mac_verify, psa_raw_key_agreement,
+    psa_sign_hash, psa_sign_message, psa_verify_hash, psa_verify_message,
 };
 use parsec_interface::requests::{Opcode, ProviderID, ResponseStatus, Result};
 use picky_asn1::wrapper::IntegerAsn1;
+use pkcs11::errors::Error as Pkcs11Error;
 use pkcs11::types::{
-    CKF_OS_LOCKING_OK, CKF_RW_SESSION, CKF_SERIAL_SESSION, CKR_OK, CKU_USER, CK_ATTRIBUTE,
-    CK_C_INITIALIZE_ARGS, CK_MECHANISM, CK_OBJECT_HANDLE, CK_SESSION_HANDLE, CK_SLOT_ID,
+    CKF_OS_LOCKING_OK, CKF_RW_SESSION, CKF_SERIAL_SESSION, CKR_OK, CKR_USER_ALREADY_LOGGED_IN,
+    CKU_USER, CK_AES_CTR_PARAMS, CK_ATTRIBUTE, CK_ATTRIBUTE_TYPE, CK_BBOOL, CK_C_INITIALIZE_ARGS,
+    CK_ECDH1_DERIVE_PARAMS, CK_MECHANISM, CK_MECHANISM_TYPE, CK_OBJECT_HANDLE,
+    CK_RSA_PKCS_OAEP_PARAMS, CK_RSA_PKCS_PSS_PARAMS, CK_SESSION_HANDLE, CK_SLOT_ID, CK_ULONG,
 };
 use pkcs11::Ctx;
 use serde::{Deserialize, Serialize};
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::io::{Error, ErrorKind};
 use std::mem;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
 use uuid::Uuid;
 
-type LocalIdStore = HashSet<[u8; 4]>;
+type LocalIdStore = HashSet<Vec<u8>>;
 
 mod utils;
 
-const SUPPORTED_OPCODES: [Opcode; 7] = [
+const SUPPORTED_OPCODES: [Opcode; 19] = [
     Opcode::PsaGenerateKey,
     Opcode::PsaDestroyKey,
     Opcode::PsaSignHash,
     Opcode::PsaVerifyHash,
     Opcode::PsaImportKey,
     Opcode::PsaExportPublicKey,
+    Opcode::PsaExportKey,
+    Opcode::PsaAsymmetricEncrypt,
+    Opcode::PsaAsymmetricDecrypt,
+    Opcode::PsaCipherEncrypt,
+    Opcode::PsaCipherDecrypt,
+    Opcode::PsaMacCompute,
+    Opcode::PsaMacVerify,
+    Opcode::PsaRawKeyAgreement,
+    Opcode::PsaGenerateRandom,
+    Opcode::PsaGetKeyAttributes,
+    Opcode::PsaSignMessage,
+    Opcode::PsaVerifyMessage,
     Opcode::ListOpcodes,
 ];
 
+// Size of an AES block, which is also the size of the IVs.
+const AES_BLOCK_SIZE: usize = 16;
+
+// Size of the chunks in which the input of a hash operation is given to the token. It keeps the
+// size of every call bounded whatever the size of the input.
+const DIGEST_CHUNK_SIZE: usize = 4096;
+
+// Default maximum number of sessions opened at the same time by the provider.
+const DEFAULT_SESSION_POOL_SIZE: usize = 16;
+
 // Public exponent value for all RSA keys.
 const PUBLIC_EXPONENT: [u8; 3] = [0x01, 0x00, 0x01];
 
@@ -73,19 +102,22 @@ pub struct Pkcs11Provider {
     // TODO: the local ID store is currently only used to prevent creating a key that does not
     // exist, it should also act as a cache for non-desctrucitve operations. Same for Mbed Crypto.
     local_ids: RwLock<LocalIdStore>,
-    // The authentication state is common to all sessions. A counter of logged in sessions is used
-    // to keep track of current logged in sessions, ignore logging in if the user is already
-    // logged in and only log out when no other session is.
-    // The mutex is both used to have interior mutability on the counter and to create a critical
-    // section inside login/logout functions. The clippy warning is ignored here to not have one
-    // Mutex<()> and an AtomicUsize which would make the code more complicated. Maybe a better
-    // way exists.
-    #[allow(clippy::mutex_atomic)]
-    logged_sessions_counter: Mutex<usize>,
+    // Key IDs of the keys adopted from the token whose objects are kept when the keys are
+    // destroyed.
+    preserved_key_ids: HashSet<Vec<u8>>,
+    // Sessions are opened and logged in when needed, up to session_pool_size of them, and are then
+    // kept open to be reused by the next operations. The condition variable is notified every time
+    // a session is given back to the pool or forgotten.
+    #[derivative(Debug = "ignore")]
+    session_pool: Mutex<SessionPool>,
+    #[derivative(Debug = "ignore")]
+    session_available: Condvar,
+    session_pool_size: usize,
     backend: Ctx,
     slot_number: CK_SLOT_ID,
-    // Some PKCS 11 devices do not need a pin, the None variant means that.
-    user_pin: Option<String>,
+    // Some PKCS 11 devices do not need a pin, the None variant means that. The pin is wiped from
+    // memory when the provider is dropped.
+    user_pin: Option<Secret>,
 }
 
 // The RSA Public Key data are DER encoded with the following representation:
@@ -99,30 +131,136 @@ struct RsaPublicKey {
     public_exponent: IntegerAsn1,
 }
 
+// The RSA Private Key data are DER encoded with the following representation:
+// RSAPrivateKey ::= SEQUENCE {
+//     version           INTEGER,  -- 0
+//     modulus           INTEGER,  -- n
+//     publicExponent    INTEGER,  -- e
+//     privateExponent   INTEGER,  -- d
+//     prime1            INTEGER,  -- p
+//     prime2            INTEGER,  -- q
+//     exponent1         INTEGER,  -- d mod (p-1)
+//     exponent2         INTEGER,  -- d mod (q-1)
+//     coefficient       INTEGER   -- (inverse of q) mod p
+// }
+#[derive(Serialize, Deserialize, Debug)]
+struct RsaPrivateKey {
+    version: IntegerAsn1,
+    modulus: IntegerAsn1,
+    public_exponent: IntegerAsn1,
+    private_exponent: IntegerAsn1,
+    prime1: IntegerAsn1,
+    prime2: IntegerAsn1,
+    exponent1: IntegerAsn1,
+    exponent2: IntegerAsn1,
+    coefficient: IntegerAsn1,
+}
+
 // For PKCS 11, a key pair consists of two independant public and private keys. Both will share the
 // same key ID.
 enum KeyPairType {
     PublicKey,
     PrivateKey,
+    SecretKey,
     Any,
 }
 
-// Representation of a PKCS 11 session.
+// Sessions opened by the provider which are not currently used by an operation.
+#[derive(Default)]
+struct SessionPool {
+    read_only: Vec<CK_SESSION_HANDLE>,
+    read_write: Vec<CK_SESSION_HANDLE>,
+    // Number of sessions currently opened, whether they are idle or used.
+    opened: usize,
+}
+
+impl SessionPool {
+    fn idle_sessions(&mut self, read_write: ReadWriteSession) -> &mut Vec<CK_SESSION_HANDLE> {
+        match read_write {
+            ReadWriteSession::ReadOnly => &mut self.read_only,
+            ReadWriteSession::ReadWrite => &mut self.read_write,
+        }
+    }
+}
+
+// Representation of a PKCS 11 session checked out of the session pool. It is given back to the
+// pool when dropped.
 struct Session<'a> {
     provider: &'a Pkcs11Provider,
     session_handle: CK_SESSION_HANDLE,
-    // This information is necessary to log out when dropped.
-    is_logged_in: bool,
+    read_write: ReadWriteSession,
+    // Set once the operation executed in the session succeeded. Otherwise an operation started with
+    // one of the C_*Init functions might still be active in the session, or the session might not
+    // be usable anymore, so it is closed instead of being given back to the pool.
+    clean: Cell<bool>,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Copy, Clone)]
 enum ReadWriteSession {
     ReadOnly,
     ReadWrite,
 }
 
+impl ReadWriteSession {
+    fn other(self) -> ReadWriteSession {
+        match self {
+            ReadWriteSession::ReadOnly => ReadWriteSession::ReadWrite,
+            ReadWriteSession::ReadWrite => ReadWriteSession::ReadOnly,
+        }
+    }
+}
+
 impl Session<'_> {
+    /// Checks out a session of the given type from the pool.
+    ///
+    /// An idle session is reused if there is one. Otherwise a new session is opened if the pool
+    /// is not full, replacing an idle session of the other type if needed. If all sessions are in
+    /// use, waits for one to be given back.
     fn new(provider: &Pkcs11Provider, read_write: ReadWriteSession) -> Result<Session> {
+        let mut pool = provider
+            .session_pool
+            .lock()
+            .expect("Session pool lock poisoned");
+
+        loop {
+            if let Some(session_handle) = pool.idle_sessions(read_write).pop() {
+                return Ok(Session {
+                    provider,
+                    session_handle,
+                    read_write,
+                    clean: Cell::new(false),
+                });
+            } else if pool.opened < provider.session_pool_size {
+                pool.opened += 1;
+                drop(pool);
+                return Session::open_session(provider, read_write);
+            } else if let Some(session_handle) = pool.idle_sessions(read_write.other()).pop() {
+                // The opened sessions count does not change as the new session replaces this one.
+                drop(pool);
+                Session::close_session(provider, session_handle);
+                return Session::open_session(provider, read_write);
+            } else {
+                pool = provider
+                    .session_available
+                    .wait(pool)
+                    .expect("Session pool lock poisoned");
+            }
+        }
+    }
+
+    fn session_handle(&self) -> CK_SESSION_HANDLE {
+        self.session_handle
+    }
+
+    // Marks the operation executed in the session as successful: the session is given back to
+    // the pool without being checked.
+    fn mark_clean(&self) {
+        self.clean.set(true);
+    }
+
+    // Opens and logs in a new session. The session must already be counted in the opened sessions
+    // of the pool, it is removed from them if the session can not be opened.
+    fn open_session(provider: &Pkcs11Provider, read_write: ReadWriteSession) -> Result<Session> {
         info!("Opening session on slot {}", provider.slot_number);
 
         let mut session_flags = CKF_SERIAL_SESSION;
@@ -130,72 +268,66 @@ impl Session<'_> {
             session_flags |= CKF_RW_SESSION;
         }
 
-        match provider
-            .backend
-            .open_session(provider.slot_number, session_flags, None, None)
-        {
-            Ok(session_handle) => {
-                let mut session = Session {
-                    provider,
-                    session_handle,
-                    is_logged_in: false,
-                };
-
+        let result =
+            match provider
+                .backend
+                .open_session(provider.slot_number, session_flags, None, None)
+            {
                 // The stress tests revealed bugs when sessions were concurrently running and some
                 // of them where logging in and out during their execution. These bugs seemed to
                 // disappear when *all* sessions are logged in by default.
                 // See https://github.com/opendnssec/SoftHSMv2/issues/509 for reference.
                 // This has security implications and should be disclosed.
-                session.login()?;
+                Ok(session_handle) => match Session::login(provider, session_handle) {
+                    Ok(()) => Ok(Session {
+                        provider,
+                        session_handle,
+                        read_write,
+                        clean: Cell::new(false),
+                    }),
+                    Err(e) => {
+                        Session::close_session(provider, session_handle);
+                        Err(e)
+                    }
+                },
+                Err(e) => {
+                    error!(
+                        "Error opening session for slot {}: {}.",
+                        provider.slot_number, e
+                    );
+                    Err(utils::to_response_status(e))
+                }
+            };
 
-                Ok(session)
-            }
-            Err(e) => {
-                error!(
-                    "Error opening session for slot {}: {}.",
-                    provider.slot_number, e
-                );
-                Err(utils::to_response_status(e))
-            }
+        if result.is_err() {
+            let mut pool = provider
+                .session_pool
+                .lock()
+                .expect("Session pool lock poisoned");
+            pool.opened -= 1;
+            provider.session_available.notify_one();
         }
-    }
 
-    fn session_handle(&self) -> CK_SESSION_HANDLE {
-        self.session_handle
+        result
     }
 
-    fn login(&mut self) -> Result<()> {
-        #[allow(clippy::mutex_atomic)]
-        let mut logged_sessions_counter = self
-            .provider
-            .logged_sessions_counter
-            .lock()
-            .expect("Error while locking mutex.");
-
-        if self.is_logged_in {
-            info!(
-                "This session ({}) has already requested authentication.",
-                self.session_handle
-            );
-            Ok(())
-        } else if *logged_sessions_counter > 0 {
-            info!(
-                "Logging in ignored as {} sessions are already requiring authentication.",
-                *logged_sessions_counter
-            );
-            *logged_sessions_counter += 1;
-            self.is_logged_in = true;
-            Ok(())
-        } else if let Some(user_pin) = self.provider.user_pin.as_ref() {
-            match self
-                .provider
+    // The authentication state is common to all sessions of the application: sessions are logged
+    // in when opened and stay logged in until they are closed.
+    fn login(provider: &Pkcs11Provider, session_handle: CK_SESSION_HANDLE) -> Result<()> {
+        if let Some(user_pin) = provider.user_pin.as_ref() {
+            match provider
                 .backend
-                .login(self.session_handle, CKU_USER, Some(user_pin))
+                .login(session_handle, CKU_USER, Some(user_pin.expose()))
             {
                 Ok(_) => {
-                    info!("Logging in session {}.", self.session_handle);
-                    *logged_sessions_counter += 1;
-                    self.is_logged_in = true;
+                    info!("Logging in session {}.", session_handle);
+                    Ok(())
+                }
+                Err(Pkcs11Error::Pkcs11(CKR_USER_ALREADY_LOGGED_IN)) => {
+                    info!(
+                        "Logging in ignored as the user is already logged in (session {}).",
+                        session_handle
+                    );
                     Ok(())
                 }
                 Err(e) => {
@@ -209,76 +341,62 @@ impl Session<'_> {
         }
     }
 
-    fn logout(&mut self) -> Result<()> {
-        #[allow(clippy::mutex_atomic)]
-        let mut logged_sessions_counter = self
-            .provider
-            .logged_sessions_counter
-            .lock()
-            .expect("Error while locking mutex.");
-
-        if !self.is_logged_in {
-            info!("Session {} has already logged out.", self.session_handle);
-            Ok(())
-        } else if *logged_sessions_counter == 0 {
-            info!("The user is already logged out, ignoring.");
-            Ok(())
-        } else if *logged_sessions_counter == 1 {
-            // Only this session requires authentication.
-            match self.provider.backend.logout(self.session_handle) {
-                Ok(_) => {
-                    info!("Logged out in session {}.", self.session_handle);
-                    *logged_sessions_counter -= 1;
-                    self.is_logged_in = false;
-                    Ok(())
-                }
-                Err(e) => {
-                    error!(
-                        "Failed to log out from session {} due to error {}. Continuing...",
-                        self.session_handle, e
-                    );
-                    Err(utils::to_response_status(e))
-                }
-            }
-        } else {
-            info!(
-                "{} sessions are still requiring authentication, not logging out.",
-                *logged_sessions_counter
-            );
-            *logged_sessions_counter -= 1;
-            self.is_logged_in = false;
-            Ok(())
+    fn close_session(provider: &Pkcs11Provider, session_handle: CK_SESSION_HANDLE) {
+        match provider.backend.close_session(session_handle) {
+            Ok(_) => info!("Session {} closed.", session_handle),
+            // Treat this as best effort.
+            Err(e) => error!(
+                "Failed to close session {} due to error {}. Continuing...",
+                session_handle, e
+            ),
         }
     }
 }
 
 impl Drop for Session<'_> {
     fn drop(&mut self) {
-        if self.logout().is_err() {
-            error!("Error while logging out. Continuing...");
+        let usable = self.clean.get();
+        if !usable {
+            Session::close_session(self.provider, self.session_handle);
         }
-        match self.provider.backend.close_session(self.session_handle) {
-            Ok(_) => info!("Session {} closed.", self.session_handle),
-            // Treat this as best effort.
-            Err(e) => error!(
-                "Failed to close session {} due to error {}. Continuing...",
-                self.session_handle, e
-            ),
+
+        let mut pool = self
+            .provider
+            .session_pool
+            .lock()
+            .expect("Session pool lock poisoned");
+        if usable {
+            pool.idle_sessions(self.read_write)
+                .push(self.session_handle);
+        } else {
+            pool.opened -= 1;
         }
+        self.provider.session_available.notify_one();
     }
 }
 
+/// Gets the key triple under which the adoption of a key from the token is recorded. The records
+/// are internal to the provider so that the clients can not remove them.
+fn adoption_record_triple(adopted_key: &AdoptedKeyConfig) -> KeyTriple {
+    // The length of the application name makes the record name unambiguous.
+    let record_name = format!(
+        "{}:{}:{}",
+        adopted_key.app_name.len(),
+        adopted_key.app_name,
+        adopted_key.key_name
+    );
+    KeyTriple::new_internal(ProviderID::Pkcs11, record_name)
+}
+
 /// Gets a key identifier from the Key ID Manager.
 fn get_key_id(
     key_triple: &KeyTriple,
     store_handle: &dyn ManageKeyIDs,
-) -> Result<([u8; 4], KeyAttributes)> {
+) -> Result<(Vec<u8>, KeyAttributes)> {
     match store_handle.get(key_triple) {
         Ok(Some(key_info)) => {
-            if key_info.id.len() == 4 {
-                let mut dst = [0; 4];
-                dst.copy_from_slice(&key_info.id);
-                Ok((dst, key_info.attributes))
+            if !key_info.id.is_empty() {
+                Ok((key_info.id.clone(), key_info.attributes))
             } else {
                 error!("Stored Key ID is not valid.");
                 Err(ResponseStatus::KeyIDManagerError)
@@ -296,7 +414,7 @@ fn create_key_id(
     local_ids_handle: &mut LocalIdStore,
 ) -> Result<[u8; 4]> {
     let mut key_id = rand::random::<[u8; 4]>();
-    while local_ids_handle.contains(&key_id) {
+    while local_ids_handle.contains(&key_id[..]) {
         key_id = rand::random::<[u8; 4]>();
     }
     let key_info = KeyInfo {
@@ -308,7 +426,7 @@ fn create_key_id(
             if insert_option.is_some() {
                 warn!("Overwriting Key triple mapping ({})", key_triple);
             }
-            let _ = local_ids_handle.insert(key_id);
+            let _ = local_ids_handle.insert(key_id.to_vec());
 
             Ok(key_id)
         }
@@ -318,13 +436,13 @@ fn create_key_id(
 
 fn remove_key_id(
     key_triple: &KeyTriple,
-    key_id: [u8; 4],
+    key_id: &[u8],
     store_handle: &mut dyn ManageKeyIDs,
     local_ids_handle: &mut LocalIdStore,
 ) -> Result<()> {
     match store_handle.remove(key_triple) {
         Ok(_) => {
-            let _ = local_ids_handle.remove(&key_id);
+            let _ = local_ids_handle.remove(key_id);
             Ok(())
         }
         Err(string) => Err(key_id_managers::to_response_status(string)),
@@ -338,26 +456,279 @@ fn key_id_exists(key_triple: &KeyTriple, store_handle: &dyn ManageKeyIDs) -> Res
     }
 }
 
+// Parameters of the RSA PSS mechanism matching the PSA definition of the algorithm with SHA-256:
+// MGF1 uses the same hash function and the salt is as long as the digest.
+static RSA_PSS_SHA256_PARAMS: CK_RSA_PKCS_PSS_PARAMS = CK_RSA_PKCS_PSS_PARAMS {
+    hashAlg: pkcs11::types::CKM_SHA256,
+    mgf: pkcs11::types::CKG_MGF1_SHA256,
+    sLen: 32,
+};
+
+// Get the PKCS 11 mechanism and the data to give to the signing and verifying operations for the
+// signature algorithm and digest given.
+fn sign_mechanism_and_data(
+    alg: AsymmetricSignature,
+    mut hash: Vec<u8>,
+) -> Result<(CK_MECHANISM, Vec<u8>)> {
+    let mut mech = CK_MECHANISM {
+        mechanism: 0,
+        pParameter: std::ptr::null_mut(),
+        ulParameterLen: 0,
+    };
+
+    match alg {
+        AsymmetricSignature::RsaPkcs1v15Sign {
+            hash_alg: Hash::Sha256,
+        } => {
+            // Build a valid ASN.1 DigestInfo DER-encoded structure by appending the hash to a
+            // DigestAlgorithmIdentifier value representing the SHA256 OID with no parameters.
+            // The OID used is: "2.16.840.1.101.3.4.2.1".
+            // It would be better to use the DigestInfo structure defined in this file but the
+            // AlgorithmIdentifier structure does not currently support the simple SHA256 OID.
+            // See Devolutions/picky-rs#19
+            let mut digest_info = vec![
+                0x30, 0x31, 0x30, 0x0D, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+                0x01, 0x05, 0x00, 0x04, 0x20,
+            ];
+            digest_info.append(&mut hash);
+            mech.mechanism = pkcs11::types::CKM_RSA_PKCS;
+            Ok((mech, digest_info))
+        }
+        // The RSA PSS mechanism does not hash the data, only the digest is given.
+        AsymmetricSignature::RsaPss {
+            hash_alg: Hash::Sha256,
+        } => {
+            let params: *const CK_RSA_PKCS_PSS_PARAMS = &RSA_PSS_SHA256_PARAMS;
+            mech.mechanism = pkcs11::types::CKM_RSA_PKCS_PSS;
+            mech.pParameter = params as pkcs11::types::CK_VOID_PTR;
+            mech.ulParameterLen = mem::size_of::<CK_RSA_PKCS_PSS_PARAMS>();
+            Ok((mech, hash))
+        }
+        // The ECDSA mechanism does not hash the data and its signature is the concatenation of
+        // the r and s values, which is the format expected by PSA.
+        AsymmetricSignature::Ecdsa {
+            hash_alg: Hash::Sha256,
+        } => {
+            mech.mechanism = pkcs11::types::CKM_ECDSA;
+            Ok((mech, hash))
+        }
+        _ => {
+            error!("The PKCS 11 provider currently only supports \"RSA PKCS#1 v1.5 signature with hashing\", RSA PSS and ECDSA algorithms with SHA-256 as hashing algorithm.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+// Get the RSA OAEP parameters matching the PSA definition of the algorithm with SHA-256, the
+// label given is used as the encoding parameter. The label needs to outlive the parameters.
+fn rsa_oaep_sha256_params(label: &[u8]) -> CK_RSA_PKCS_OAEP_PARAMS {
+    CK_RSA_PKCS_OAEP_PARAMS {
+        hashAlg: pkcs11::types::CKM_SHA256,
+        mgf: pkcs11::types::CKG_MGF1_SHA256,
+        source: pkcs11::types::CKZ_DATA_SPECIFIED,
+        pSourceData: if label.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            label.as_ptr() as pkcs11::types::CK_VOID_PTR
+        },
+        ulSourceDataLen: label.len(),
+    }
+}
+
+// Get the PKCS 11 mechanism to use for the encryption and decryption operations of the
+// algorithm given. The OAEP parameters need to outlive the mechanism.
+fn encrypt_mechanism(
+    alg: AsymmetricEncryption,
+    label: &[u8],
+    oaep_params: &CK_RSA_PKCS_OAEP_PARAMS,
+) -> Result<CK_MECHANISM> {
+    let mut mech = CK_MECHANISM {
+        mechanism: 0,
+        pParameter: std::ptr::null_mut(),
+        ulParameterLen: 0,
+    };
+
+    match alg {
+        AsymmetricEncryption::RsaPkcs1v15Crypt => {
+            if !label.is_empty() {
+                error!("A salt can not be used with the RSA PKCS#1 v1.5 encryption algorithm.");
+                return Err(ResponseStatus::PsaErrorInvalidArgument);
+            }
+            mech.mechanism = pkcs11::types::CKM_RSA_PKCS;
+            Ok(mech)
+        }
+        AsymmetricEncryption::RsaOaep {
+            hash_alg: Hash::Sha256,
+        } => {
+            let params: *const CK_RSA_PKCS_OAEP_PARAMS = oaep_params;
+            mech.mechanism = pkcs11::types::CKM_RSA_PKCS_OAEP;
+            mech.pParameter = params as pkcs11::types::CK_VOID_PTR;
+            mech.ulParameterLen = mem::size_of::<CK_RSA_PKCS_OAEP_PARAMS>();
+            Ok(mech)
+        }
+        _ => {
+            error!("The PKCS 11 provider currently only supports the RSA PKCS#1 v1.5 and RSA OAEP with SHA-256 encryption algorithms.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+// Get the PKCS 11 AES mechanism type matching the cipher algorithm given.
+fn cipher_mechanism_type(alg: Cipher) -> Result<CK_MECHANISM_TYPE> {
+    match alg {
+        Cipher::CbcPkcs7 => Ok(pkcs11::types::CKM_AES_CBC_PAD),
+        Cipher::CbcNoPadding => Ok(pkcs11::types::CKM_AES_CBC),
+        Cipher::Ctr => Ok(pkcs11::types::CKM_AES_CTR),
+        Cipher::EcbNoPadding => Ok(pkcs11::types::CKM_AES_ECB),
+        _ => {
+            error!(
+                "The PKCS 11 provider currently only supports the CBC, CTR and ECB cipher modes."
+            );
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+// Get the PKCS 11 mechanism type matching the MAC algorithm given.
+fn rsa_mechanism_type(alg: Algorithm) -> CK_MECHANISM_TYPE {
+    match alg {
+        Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPss { .. }) => {
+            pkcs11::types::CKM_RSA_PKCS_PSS
+        }
+        Algorithm::AsymmetricEncryption(AsymmetricEncryption::RsaOaep { .. }) => {
+            pkcs11::types::CKM_RSA_PKCS_OAEP
+        }
+        _ => pkcs11::types::CKM_RSA_PKCS,
+    }
+}
+
+fn mac_mechanism_type(alg: Mac) -> Result<CK_MECHANISM_TYPE> {
+    match alg {
+        Mac::FullLength(FullLengthMac::Hmac {
+            hash_alg: Hash::Sha256,
+        }) => Ok(pkcs11::types::CKM_SHA256_HMAC),
+        _ => {
+            error!("The PKCS 11 provider currently only supports full length HMAC with SHA-256 as MAC algorithm.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+fn digest_mechanism_type(alg: Hash) -> Result<CK_MECHANISM_TYPE> {
+    match alg {
+        Hash::Sha1 => Ok(pkcs11::types::CKM_SHA_1),
+        Hash::Sha224 => Ok(pkcs11::types::CKM_SHA224),
+        Hash::Sha256 => Ok(pkcs11::types::CKM_SHA256),
+        Hash::Sha384 => Ok(pkcs11::types::CKM_SHA384),
+        Hash::Sha512 => Ok(pkcs11::types::CKM_SHA512),
+        _ => {
+            error!("The PKCS 11 provider currently only supports SHA-1 and SHA-2 hash algorithms.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+// Get the values of the CKA_SENSITIVE and CKA_EXTRACTABLE attributes of a private or secret key
+// from its usage flags. Only keys which can be exported are not sensitive and extractable.
+fn sensitive_and_extractable(key_attributes: KeyAttributes) -> (CK_BBOOL, CK_BBOOL) {
+    if key_attributes.key_policy.key_usage_flags.export {
+        (pkcs11::types::CK_FALSE, pkcs11::types::CK_TRUE)
+    } else {
+        (pkcs11::types::CK_TRUE, pkcs11::types::CK_FALSE)
+    }
+}
+
+// Get the parameters of the ECDH mechanism, without key derivation function. The peer public key
+// needs to outlive the parameters.
+fn ecdh1_derive_params(peer_key: &[u8]) -> CK_ECDH1_DERIVE_PARAMS {
+    CK_ECDH1_DERIVE_PARAMS {
+        kdf: pkcs11::types::CKD_NULL,
+        ulSharedDataLen: 0,
+        pSharedData: std::ptr::null_mut(),
+        ulPublicDataLen: peer_key.len(),
+        pPublicData: peer_key.as_ptr() as pkcs11::types::CK_BYTE_PTR,
+    }
+}
+
+// Get the parameters of the AES CTR mechanism, the whole IV given is used as the counter block.
+fn aes_ctr_params(iv: &[u8]) -> CK_AES_CTR_PARAMS {
+    let mut params = CK_AES_CTR_PARAMS {
+        ulCounterBits: 128,
+        cb: [0; AES_BLOCK_SIZE],
+    };
+    if iv.len() == AES_BLOCK_SIZE {
+        params.cb.copy_from_slice(iv);
+    }
+
+    params
+}
+
+// Get the PKCS 11 mechanism to use for the cipher algorithm and IV given. The IV and the CTR
+// parameters need to outlive the mechanism.
+fn cipher_mechanism(
+    alg: Cipher,
+    iv: &[u8],
+    ctr_params: &CK_AES_CTR_PARAMS,
+) -> Result<CK_MECHANISM> {
+    let mut mech = CK_MECHANISM {
+        mechanism: cipher_mechanism_type(alg)?,
+        pParameter: std::ptr::null_mut(),
+        ulParameterLen: 0,
+    };
+
+    match alg {
+        Cipher::EcbNoPadding => {
+            if !iv.is_empty() {
+                error!("No IV should be given with the ECB mode.");
+                return Err(ResponseStatus::PsaErrorInvalidArgument);
+            }
+        }
+        _ => {
+            if iv.len() != AES_BLOCK_SIZE {
+                error!("The IV should be {} bytes long.", AES_BLOCK_SIZE);
+                return Err(ResponseStatus::PsaErrorInvalidArgument);
+            }
+            if alg == Cipher::Ctr {
+                let params: *const CK_AES_CTR_PARAMS = ctr_params;
+                mech.pParameter = params as pkcs11::types::CK_VOID_PTR;
+                mech.ulParameterLen = mem::size_of::<CK_AES_CTR_PARAMS>();
+            } else {
+                mech.pParameter = iv.as_ptr() as pkcs11::types::CK_VOID_PTR;
+                mech.ulParameterLen = iv.len();
+            }
+        }
+    }
+
+    Ok(mech)
+}
+
 impl Pkcs11Provider {
     /// Creates and initialise a new instance of Pkcs11Provider.
     /// Checks if there are not more keys stored in the Key ID Manager than in the PKCS 11 library
     /// and if there are, delete them. Adds Key IDs currently in use in the local IDs store.
+    /// Adopts the keys of the token given in the configuration.
     /// Returns `None` if the initialisation failed.
     fn new(
         key_id_store: Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>,
         backend: Ctx,
         slot_number: usize,
-        user_pin: Option<String>,
+        user_pin: Option<Secret>,
+        session_pool_size: usize,
+        adopted_keys: Vec<AdoptedKeyConfig>,
     ) -> Option<Pkcs11Provider> {
         #[allow(clippy::mutex_atomic)]
-        let pkcs11_provider = Pkcs11Provider {
+        let mut pkcs11_provider = Pkcs11Provider {
             key_id_store,
             local_ids: RwLock::new(HashSet::new()),
-            logged_sessions_counter: Mutex::new(0),
+            preserved_key_ids: HashSet::new(),
+            session_pool: Mutex::new(SessionPool::default()),
+            session_available: Condvar::new(),
+            session_pool_size,
             backend,
             slot_number,
             user_pin,
         };
+        let mut preserved_key_ids = HashSet::new();
         {
             // The local scope allows to drop store_handle and local_ids_handle in order to return
             // the pkcs11_provider.
@@ -379,6 +750,10 @@ impl Pkcs11Provider {
                         Session::new(&pkcs11_provider, ReadWriteSession::ReadOnly).ok()?;
 
                     for key_triple in key_triples.iter().cloned() {
+                        // The adoption records are kept even if the adopted key is destroyed.
+                        if key_triple.is_internal() {
+                            continue;
+                        }
                         let (key_id, _) = match get_key_id(key_triple, &*store_handle) {
                             Ok(key_id) => key_id,
                             Err(response_status) => {
@@ -388,7 +763,7 @@ impl Pkcs11Provider {
                         };
                         match pkcs11_provider.find_key(
                             session.session_handle(),
-                            key_id,
+                            &key_id,
                             KeyPairType::Any,
                         ) {
                             Ok(_) => {
@@ -423,307 +798,451 @@ impl Pkcs11Provider {
                     return None;
                 }
             }
-        }
 
-        Some(pkcs11_provider)
-    }
+            if !adopted_keys.is_empty() {
+                let session = Session::new(&pkcs11_provider, ReadWriteSession::ReadOnly).ok()?;
 
-    /// Find the PKCS 11 object handle corresponding to the key ID and the key type (public or
-    /// private key) given as parameters for the current session.
-    fn find_key(
-        &self,
-        session: CK_SESSION_HANDLE,
-        key_id: [u8; 4],
-        key_type: KeyPairType,
-    ) -> Result<CK_OBJECT_HANDLE> {
-        let mut template = vec![CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(&key_id)];
-        match key_type {
-            KeyPairType::PublicKey => template.push(
-                CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS)
-                    .with_ck_ulong(&pkcs11::types::CKO_PUBLIC_KEY),
-            ),
-            KeyPairType::PrivateKey => template.push(
-                CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS)
-                    .with_ck_ulong(&pkcs11::types::CKO_PRIVATE_KEY),
-            ),
-            KeyPairType::Any => (),
-        }
+                for adopted_key in adopted_keys {
+                    let key_triple = KeyTriple::new(
+                        ApplicationName::new(adopted_key.app_name.clone()),
+                        ProviderID::Pkcs11,
+                        adopted_key.key_name.clone(),
+                    );
+                    let preserve_token_objects =
+                        !adopted_key.destroy_token_objects.unwrap_or(false);
+
+                    // Keys are only adopted once: a key adopted when the provider started before
+                    // is not adopted again, even if it was destroyed since.
+                    match store_handle.get(&adoption_record_triple(&adopted_key)) {
+                        Ok(Some(record)) => {
+                            if preserve_token_objects {
+                                let _ = preserved_key_ids.insert(record.id.clone());
+                            }
+                            continue;
+                        }
+                        Ok(None) => (),
+                        Err(string) => {
+                            error!(
+                                "Key ID Manager error: {}, key {} is not adopted.",
+                                string, key_triple
+                            );
+                            continue;
+                        }
+                    }
 
-        if let Err(e) = self.backend.find_objects_init(session, &template) {
-            error!("Object enumeration init failed with {}", e);
-            Err(utils::to_response_status(e))
-        } else {
-            match self.backend.find_objects(session, 1) {
-                Ok(objects) => {
-                    if let Err(e) = self.backend.find_objects_final(session) {
-                        error!("Object enumeration final failed with {}", e);
-                        Err(utils::to_response_status(e))
-                    } else if objects.is_empty() {
-                        Err(ResponseStatus::PsaErrorDoesNotExist)
-                    } else {
-                        Ok(objects[0])
+                    match pkcs11_provider.adopt_key(
+                        session.session_handle(),
+                        &key_triple,
+                        &adopted_key,
+                        &mut *store_handle,
+                    ) {
+                        Ok(key_id) => {
+                            info!("Key {} adopted from the token.", key_triple);
+                            if preserve_token_objects {
+                                let _ = preserved_key_ids.insert(key_id.clone());
+                            }
+                            let _ = local_ids_handle.insert(key_id);
+                        }
+                        Err(e) => {
+                            error!(
+                                "Key {} could not be adopted ({}), continuing...",
+                                key_triple, e
+                            );
+                        }
                     }
                 }
-                Err(e) => {
-                    error!("Finding objects failed with {}", e);
-                    Err(utils::to_response_status(e))
-                }
             }
         }
-    }
-}
-
-impl Provide for Pkcs11Provider {
-    fn list_opcodes(&self, _op: list_opcodes::Operation) -> Result<list_opcodes::Result> {
-        Ok(list_opcodes::Result {
-            opcodes: SUPPORTED_OPCODES.iter().copied().collect(),
-        })
-    }
+        pkcs11_provider.preserved_key_ids = preserved_key_ids;
 
-    fn describe(&self) -> Result<ProviderInfo> {
-        Ok(ProviderInfo {
-            // Assigned UUID for this provider: 30e39502-eba6-4d60-a4af-c518b7f5e38f
-            uuid: Uuid::parse_str("30e39502-eba6-4d60-a4af-c518b7f5e38f")
-                .or(Err(ResponseStatus::InvalidEncoding))?,
-            description: String::from("PKCS #11 provider, interfacing with a PKCS #11 library."),
-            vendor: String::from("OASIS Standard."),
-            version_maj: 0,
-            version_min: 1,
-            version_rev: 0,
-            id: ProviderID::Pkcs11,
-        })
+        Some(pkcs11_provider)
     }
 
-    fn psa_generate_key(
+    /// Binds the token key objects described in the configuration to the key triple given, with
+    /// the attributes read from the objects. Returns the key ID of the objects.
+    fn adopt_key(
         &self,
-        app_name: ApplicationName,
-        op: psa_generate_key::Operation,
-    ) -> Result<psa_generate_key::Result> {
-        info!("Pkcs11 Provider - Create Key");
-
-        if op.attributes.key_type != KeyType::RsaKeyPair {
-            error!("The PKCS11 provider currently only supports creating RSA key pairs.");
+        session: CK_SESSION_HANDLE,
+        key_triple: &KeyTriple,
+        adopted_key: &AdoptedKeyConfig,
+        store_handle: &mut dyn ManageKeyIDs,
+    ) -> Result<Vec<u8>> {
+        let key_id = match (&adopted_key.id, &adopted_key.label) {
+            (Some(id), _) => utils::parse_hex(id).ok_or_else(|| {
+                error!("The key ID \"{}\" is not a valid hexadecimal string.", id);
+                ResponseStatus::PsaErrorInvalidArgument
+            })?,
+            (None, Some(label)) => {
+                let template = vec![CK_ATTRIBUTE::new(pkcs11::types::CKA_LABEL).with_string(label)];
+                let key = self.find_object(session, &template)?;
+                let mut values =
+                    self.get_byte_attributes(session, key, &[pkcs11::types::CKA_ID])?;
+                values.remove(0)
+            }
+            (None, None) => {
+                error!("An adopted key needs either an ID or a label.");
+                return Err(ResponseStatus::PsaErrorInvalidArgument);
+            }
+        };
+        if key_id.is_empty() {
+            error!("Only key objects with a CKA_ID attribute can be adopted.");
             return Err(ResponseStatus::PsaErrorNotSupported);
         }
 
-        let key_name = op.key_name;
-        let key_attributes = op.attributes;
-        // This should never panic on 32 bits or more machines.
-        let key_size = std::convert::TryFrom::try_from(op.attributes.key_bits).unwrap();
+        let key_attributes = self.token_key_attributes(session, &key_id, adopted_key.algorithm)?;
 
-        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
-        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
-        let mut local_ids_handle = self.local_ids.write().expect("Local ID lock poisoned");
-        if key_id_exists(&key_triple, &*store_handle)? {
+        if store_handle
+            .exists(key_triple)
+            .or_else(|e| Err(key_id_managers::to_response_status(e)))?
+        {
+            error!(
+                "A key named {} already exists, it is not replaced by the adopted key.",
+                key_triple
+            );
             return Err(ResponseStatus::PsaErrorAlreadyExists);
         }
-        let key_id = create_key_id(
-            key_triple.clone(),
-            key_attributes,
-            &mut *store_handle,
-            &mut local_ids_handle,
-        )?;
 
-        let mech = CK_MECHANISM {
-            mechanism: pkcs11::types::CKM_RSA_PKCS_KEY_PAIR_GEN,
-            pParameter: std::ptr::null_mut(),
-            ulParameterLen: 0,
+        let key_info = KeyInfo {
+            id: key_id.clone(),
+            attributes: key_attributes,
         };
+        let _ = store_handle
+            .insert(key_triple.clone(), key_info.clone())
+            .or_else(|e| Err(key_id_managers::to_response_status(e)))?;
+        if let Err(string) = store_handle.insert(adoption_record_triple(adopted_key), key_info) {
+            error!("Key ID Manager error: {}", string);
+            let _ = store_handle.remove(key_triple);
+            return Err(key_id_managers::to_response_status(string));
+        }
 
-        let mut priv_template: Vec<CK_ATTRIBUTE> = Vec::new();
-        let mut pub_template: Vec<CK_ATTRIBUTE> = Vec::new();
+        Ok(key_id)
+    }
 
-        priv_template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_SIGN).with_bool(&pkcs11::types::CK_TRUE));
-        priv_template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(&key_id));
-        priv_template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_TOKEN).with_bool(&pkcs11::types::CK_TRUE));
+    /// Reads the attributes of the key objects with the key ID given and converts them to the key
+    /// attributes of a key permitted to be used with the algorithm given.
+    fn token_key_attributes(
+        &self,
+        session: CK_SESSION_HANDLE,
+        key_id: &[u8],
+        algorithm: Algorithm,
+    ) -> Result<KeyAttributes> {
+        let optional_key = |key_type| match self.find_key(session, key_id, key_type) {
+            Ok(key) => Ok(Some(key)),
+            Err(ResponseStatus::PsaErrorDoesNotExist) => Ok(None),
+            Err(e) => Err(e),
+        };
+        let private_key = optional_key(KeyPairType::PrivateKey)?;
+        let public_key = optional_key(KeyPairType::PublicKey)?;
+        let secret_key = optional_key(KeyPairType::SecretKey)?;
+
+        // The object holding the secret part of the key, if there is one.
+        let key = match (private_key, secret_key, public_key) {
+            (Some(key), _, _) | (None, Some(key), _) | (None, None, Some(key)) => key,
+            (None, None, None) => {
+                error!("No key object found with this key ID.");
+                return Err(ResponseStatus::PsaErrorDoesNotExist);
+            }
+        };
 
-        pub_template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_VERIFY).with_bool(&pkcs11::types::CK_TRUE));
-        pub_template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(&key_id));
-        pub_template.push(
-            CK_ATTRIBUTE::new(pkcs11::types::CKA_PUBLIC_EXPONENT).with_bytes(&PUBLIC_EXPONENT),
-        );
-        pub_template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_MODULUS_BITS).with_ck_ulong(&key_size));
-        pub_template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_TOKEN).with_bool(&pkcs11::types::CK_TRUE));
-        pub_template.push(
-            CK_ATTRIBUTE::new(pkcs11::types::CKA_PRIVATE).with_bool(&pkcs11::types::CK_FALSE),
-        );
-        pub_template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ENCRYPT).with_bool(&pkcs11::types::CK_TRUE));
+        let key_type = self.get_ulong_attribute(session, key, pkcs11::types::CKA_KEY_TYPE)?;
+        let (key_type, key_bits) = match key_type {
+            pkcs11::types::CKK_RSA => {
+                let modulus = self
+                    .get_byte_attributes(session, key, &[pkcs11::types::CKA_MODULUS])?
+                    .remove(0);
+                let modulus_len = modulus.iter().skip_while(|byte| **byte == 0).count();
+                let key_type = if private_key.is_some() {
+                    KeyType::RsaKeyPair
+                } else {
+                    KeyType::RsaPublicKey
+                };
+                let key_bits: u32 = std::convert::TryFrom::try_from(modulus_len * 8)
+                    .or(Err(ResponseStatus::PsaErrorNotSupported))?;
+                (key_type, key_bits)
+            }
+            pkcs11::types::CKK_EC => {
+                let ec_params = self
+                    .get_byte_attributes(session, key, &[pkcs11::types::CKA_EC_PARAMS])?
+                    .remove(0);
+                let (curve_family, key_bits) = utils::ec_params_to_curve(&ec_params)?;
+                let key_type = if private_key.is_some() {
+                    KeyType::EccKeyPair { curve_family }
+                } else {
+                    KeyType::EccPublicKey { curve_family }
+                };
+                (key_type, key_bits)
+            }
+            pkcs11::types::CKK_AES => {
+                let value_len =
+                    self.get_ulong_attribute(session, key, pkcs11::types::CKA_VALUE_LEN)?;
+                (KeyType::Aes, value_len * 8)
+            }
+            pkcs11::types::CKK_GENERIC_SECRET => {
+                let value_len =
+                    self.get_ulong_attribute(session, key, pkcs11::types::CKA_VALUE_LEN)?;
+                (KeyType::Hmac, value_len * 8)
+            }
+            key_type => {
+                error!("Key objects of type {} can not be adopted.", key_type);
+                return Err(ResponseStatus::PsaErrorNotSupported);
+            }
+        };
 
-        let session = Session::new(self, ReadWriteSession::ReadWrite).or_else(|err| {
-            error!("Error creating a new session: {}.", err);
-            remove_key_id(
-                &key_triple,
-                key_id,
-                &mut *store_handle,
-                &mut local_ids_handle,
-            )?;
-            Err(err)
-        })?;
+        // Usage of the secret part of the key: the private or secret key object.
+        let (sign, decrypt, derive, export) = if private_key.is_some() || secret_key.is_some() {
+            let export = self.get_bool_attribute(session, key, pkcs11::types::CKA_EXTRACTABLE)?
+                && !self.get_bool_attribute(session, key, pkcs11::types::CKA_SENSITIVE)?;
+            (
+                self.get_bool_attribute(session, key, pkcs11::types::CKA_SIGN)?,
+                self.get_bool_attribute(session, key, pkcs11::types::CKA_DECRYPT)?,
+                self.get_bool_attribute(session, key, pkcs11::types::CKA_DERIVE)?,
+                export,
+            )
+        } else {
+            (false, false, false, false)
+        };
+        // Usage of the public part of the key: the public or secret key object.
+        let (verify, encrypt) = match public_key.or(secret_key) {
+            Some(key) => (
+                self.get_bool_attribute(session, key, pkcs11::types::CKA_VERIFY)?,
+                self.get_bool_attribute(session, key, pkcs11::types::CKA_ENCRYPT)?,
+            ),
+            None => (false, false),
+        };
 
-        info!(
-            "Generating RSA key pair in session {}",
-            session.session_handle()
-        );
+        let key_attributes = KeyAttributes {
+            key_type,
+            key_bits,
+            key_policy: KeyPolicy {
+                key_usage_flags: UsageFlags {
+                    sign_hash: sign,
+                    verify_hash: verify,
+                    sign_message: sign,
+                    verify_message: verify,
+                    export,
+                    encrypt,
+                    decrypt,
+                    cache: false,
+                    copy: false,
+                    derive,
+                },
+                key_algorithm: algorithm,
+            },
+        };
+        key_attributes.compatible_with_alg(algorithm)?;
 
-        match self.backend.generate_key_pair(
-            session.session_handle(),
-            &mech,
-            &pub_template,
-            &priv_template,
-        ) {
-            Ok(_key) => Ok(psa_generate_key::Result {}),
-            Err(e) => {
-                error!("Generate Key Pair operation failed with {}", e);
-                remove_key_id(
-                    &key_triple,
-                    key_id,
-                    &mut *store_handle,
-                    &mut local_ids_handle,
-                )?;
-                Err(utils::to_response_status(e))
+        Ok(key_attributes)
+    }
+
+    /// Read a `CK_BBOOL` attribute of an object.
+    fn get_bool_attribute(
+        &self,
+        session: CK_SESSION_HANDLE,
+        object: CK_OBJECT_HANDLE,
+        attr_type: CK_ATTRIBUTE_TYPE,
+    ) -> Result<bool> {
+        let value = self
+            .get_byte_attributes(session, object, &[attr_type])?
+            .remove(0);
+        match value.as_slice() {
+            [byte] => Ok(*byte != pkcs11::types::CK_FALSE),
+            _ => {
+                error!("Attribute {} is not a boolean.", attr_type);
+                Err(ResponseStatus::PsaErrorCommunicationFailure)
             }
         }
     }
 
-    fn psa_import_key(
+    /// Read a `CK_ULONG` attribute of an object.
+    fn get_ulong_attribute(
         &self,
-        app_name: ApplicationName,
-        op: psa_import_key::Operation,
-    ) -> Result<psa_import_key::Result> {
-        info!("Pkcs11 Provider - Import Key");
+        session: CK_SESSION_HANDLE,
+        object: CK_OBJECT_HANDLE,
+        attr_type: CK_ATTRIBUTE_TYPE,
+    ) -> Result<CK_ULONG> {
+        let value = self
+            .get_byte_attributes(session, object, &[attr_type])?
+            .remove(0);
+        if value.len() == mem::size_of::<CK_ULONG>() {
+            let mut bytes = [0; mem::size_of::<CK_ULONG>()];
+            bytes.copy_from_slice(&value);
+            Ok(CK_ULONG::from_ne_bytes(bytes))
+        } else {
+            error!("Attribute {} is not an unsigned long.", attr_type);
+            Err(ResponseStatus::PsaErrorCommunicationFailure)
+        }
+    }
 
-        if op.attributes.key_type != KeyType::RsaPublicKey {
-            error!("The PKCS 11 provider currently only supports importing RSA public key.");
+    /// Sign a hash with the private key of the given ID, in the session given.
+    fn sign_hash_with_key(
+        &self,
+        session: CK_SESSION_HANDLE,
+        key_id: &[u8],
+        alg: AsymmetricSignature,
+        hash: Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        if hash.len() != 32 {
+            error!("The PKCS11 provider currently only supports 256 bits long digests.");
             return Err(ResponseStatus::PsaErrorNotSupported);
         }
 
-        let key_name = op.key_name;
-        let key_attributes = op.attributes;
-        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
-        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
-        let mut local_ids_handle = self.local_ids.write().expect("Local ID lock poisoned");
-        if key_id_exists(&key_triple, &*store_handle)? {
-            return Err(ResponseStatus::PsaErrorAlreadyExists);
-        }
-        let key_id = create_key_id(
-            key_triple.clone(),
-            key_attributes,
-            &mut *store_handle,
-            &mut local_ids_handle,
-        )?;
+        let (mech, data) = sign_mechanism_and_data(alg, hash)?;
 
-        let mut template: Vec<CK_ATTRIBUTE> = Vec::new();
+        let key = self.find_key(session, key_id, KeyPairType::PrivateKey)?;
+        info!("Located signing key.");
 
-        let public_key: RsaPublicKey = picky_asn1_der::from_bytes(&op.data).or_else(|e| {
-            error!("Failed to parse RsaPublicKey data ({}).", e);
-            Err(ResponseStatus::PsaErrorInvalidArgument)
-        })?;
+        match self.backend.sign_init(session, &mech, key) {
+            Ok(_) => {
+                info!("Signing operation initialized.");
 
-        if public_key.modulus.is_negative() || public_key.public_exponent.is_negative() {
-            error!("Only positive modulus and public exponent are supported.");
-            return Err(ResponseStatus::PsaErrorInvalidArgument);
+                match self.backend.sign(session, &data) {
+                    Ok(signature) => Ok(signature),
+                    Err(e) => {
+                        error!("Failed to execute signing operation. Error: {}", e);
+                        Err(utils::to_response_status(e))
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to initialize signing operation. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
         }
+    }
 
-        let modulus_object = &public_key.modulus.as_unsigned_bytes_be();
-        let exponent_object = &public_key.public_exponent.as_unsigned_bytes_be();
-
-        template.push(
-            CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS)
-                .with_ck_ulong(&pkcs11::types::CKO_PUBLIC_KEY),
-        );
-        template.push(
-            CK_ATTRIBUTE::new(pkcs11::types::CKA_KEY_TYPE).with_ck_ulong(&pkcs11::types::CKK_RSA),
-        );
-        template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_TOKEN).with_bool(&pkcs11::types::CK_TRUE));
-        template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_MODULUS).with_bytes(modulus_object));
-        template.push(
-            CK_ATTRIBUTE::new(pkcs11::types::CKA_PUBLIC_EXPONENT).with_bytes(exponent_object),
-        );
-        template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_VERIFY).with_bool(&pkcs11::types::CK_TRUE));
-        template
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ENCRYPT).with_bool(&pkcs11::types::CK_TRUE));
-        template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(&key_id));
-        template.push(
-            CK_ATTRIBUTE::new(pkcs11::types::CKA_PRIVATE).with_bool(&pkcs11::types::CK_FALSE),
-        );
+    /// Verify the signature of a hash with the public key of the given ID, in the session given.
+    fn verify_hash_with_key(
+        &self,
+        session: CK_SESSION_HANDLE,
+        key_id: &[u8],
+        alg: AsymmetricSignature,
+        hash: Vec<u8>,
+        signature: &[u8],
+    ) -> Result<()> {
+        if hash.len() != 32 {
+            error!("The PKCS11 provider currently only supports 256 bits long digests.");
+            return Err(ResponseStatus::PsaErrorNotSupported);
+        }
 
-        // Restrict to RSA.
-        let allowed_mechanisms = [pkcs11::types::CKM_RSA_PKCS];
-        // The attribute contains a pointer to the allowed_mechanism array and its size as
-        // ulValueLen.
-        let mut allowed_mechanisms_attribute =
-            CK_ATTRIBUTE::new(pkcs11::types::CKA_ALLOWED_MECHANISMS);
-        allowed_mechanisms_attribute.ulValueLen = mem::size_of_val(&allowed_mechanisms);
-        allowed_mechanisms_attribute.pValue = &allowed_mechanisms
-            as *const pkcs11::types::CK_MECHANISM_TYPE
-            as pkcs11::types::CK_VOID_PTR;
-        template.push(allowed_mechanisms_attribute);
+        // Verify without hashing.
+        let (mech, data) = sign_mechanism_and_data(alg, hash)?;
 
-        let session = Session::new(self, ReadWriteSession::ReadWrite).or_else(|err| {
-            error!("Error creating a new session: {}.", err);
-            remove_key_id(
-                &key_triple,
-                key_id,
-                &mut *store_handle,
-                &mut local_ids_handle,
-            )?;
-            Err(err)
-        })?;
+        let key = self.find_key(session, key_id, KeyPairType::PublicKey)?;
+        info!("Located public key.");
 
-        info!(
-            "Importing RSA public key in session {}",
-            session.session_handle()
-        );
+        match self.backend.verify_init(session, &mech, key) {
+            Ok(_) => {
+                info!("Verify operation initialized.");
 
-        match self
-            .backend
-            .create_object(session.session_handle(), &template)
-        {
-            Ok(_key) => Ok(psa_import_key::Result {}),
+                match self.backend.verify(session, &data, signature) {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(utils::to_response_status(e)),
+                }
+            }
             Err(e) => {
-                error!("Import operation failed with {}", e);
-                remove_key_id(
-                    &key_triple,
-                    key_id,
-                    &mut *store_handle,
-                    &mut local_ids_handle,
-                )?;
+                error!("Failed to initialize verifying operation. Error: {}", e);
                 Err(utils::to_response_status(e))
             }
         }
     }
 
-    fn psa_export_public_key(
+    /// Hash a message with the token, in the session given.
+    fn digest(&self, session: CK_SESSION_HANDLE, alg: Hash, message: &[u8]) -> Result<Vec<u8>> {
+        let mech = CK_MECHANISM {
+            mechanism: digest_mechanism_type(alg)?,
+            pParameter: std::ptr::null_mut(),
+            ulParameterLen: 0,
+        };
+
+        self.backend.digest_init(session, &mech).or_else(|e| {
+            error!("Failed to initialize hashing operation. Error: {}", e);
+            Err(utils::to_response_status(e))
+        })?;
+        info!("Hashing operation initialized.");
+
+        // The message is given in chunks so that large messages do not need to be passed to the
+        // token in one go. A failed call terminates the hashing operation, the session can then be
+        // used for other operations.
+        for chunk in message.chunks(DIGEST_CHUNK_SIZE) {
+            self.backend.digest_update(session, chunk).or_else(|e| {
+                error!("Failed to execute hashing operation. Error: {}", e);
+                Err(utils::to_response_status(e))
+            })?;
+        }
+
+        self.backend.digest_final(session).or_else(|e| {
+            error!("Failed to finish hashing operation. Error: {}", e);
+            Err(utils::to_response_status(e))
+        })
+    }
+
+    /// Find the PKCS 11 object handle corresponding to the key ID and the key type (public,
+    /// private or secret key) given as parameters for the current session.
+    fn find_key(
         &self,
-        app_name: ApplicationName,
-        op: psa_export_public_key::Operation,
-    ) -> Result<psa_export_public_key::Result> {
-        info!("Pkcs11 Provider - Export Public Key");
+        session: CK_SESSION_HANDLE,
+        key_id: &[u8],
+        key_type: KeyPairType,
+    ) -> Result<CK_OBJECT_HANDLE> {
+        let mut template = vec![CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(key_id)];
+        match key_type {
+            KeyPairType::PublicKey => template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS)
+                    .with_ck_ulong(&pkcs11::types::CKO_PUBLIC_KEY),
+            ),
+            KeyPairType::PrivateKey => template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS)
+                    .with_ck_ulong(&pkcs11::types::CKO_PRIVATE_KEY),
+            ),
+            KeyPairType::SecretKey => template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS)
+                    .with_ck_ulong(&pkcs11::types::CKO_SECRET_KEY),
+            ),
+            KeyPairType::Any => (),
+        }
 
-        let key_name = op.key_name;
-        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
-        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
-        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+        self.find_object(session, &template)
+    }
 
-        key_attributes.can_export()?;
+    /// Find the first PKCS 11 object handle matching the template given for the current session.
+    fn find_object(
+        &self,
+        session: CK_SESSION_HANDLE,
+        template: &[CK_ATTRIBUTE],
+    ) -> Result<CK_OBJECT_HANDLE> {
+        if let Err(e) = self.backend.find_objects_init(session, template) {
+            error!("Object enumeration init failed with {}", e);
+            return Err(utils::to_response_status(e));
+        }
 
-        let session = Session::new(self, ReadWriteSession::ReadOnly)?;
-        info!(
-            "Export RSA public key in session {}",
-            session.session_handle()
-        );
+        // The enumeration is always finished, even if finding the objects failed, so that the
+        // session can be used for other operations.
+        let find_result = self.backend.find_objects(session, 1);
+        let final_result = self.backend.find_objects_final(session);
 
-        let key = self.find_key(session.session_handle(), key_id, KeyPairType::PublicKey)?;
-        info!("Located key for export.");
+        let objects = find_result.or_else(|e| {
+            error!("Finding objects failed with {}", e);
+            Err(utils::to_response_status(e))
+        })?;
+        final_result.or_else(|e| {
+            error!("Object enumeration final failed with {}", e);
+            Err(utils::to_response_status(e))
+        })?;
+
+        if objects.is_empty() {
+            Err(ResponseStatus::PsaErrorDoesNotExist)
+        } else {
+            Ok(objects[0])
+        }
+    }
 
+    /// Read the modulus and public exponent of a RSA public key object and serialise them as a
+    /// DER-encoded `RSAPublicKey` structure.
+    fn export_rsa_public_key(
+        &self,
+        session: CK_SESSION_HANDLE,
+        key: CK_OBJECT_HANDLE,
+    ) -> Result<Vec<u8>> {
         let mut size_attrs: Vec<CK_ATTRIBUTE> = Vec::new();
         size_attrs.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_MODULUS));
         size_attrs.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_PUBLIC_EXPONENT));
@@ -732,7 +1251,7 @@ impl Provide for Pkcs11Provider {
         let (modulus_len, public_exponent_len) =
             match self
                 .backend
-                .get_attribute_value(session.session_handle(), key, &mut size_attrs)
+                .get_attribute_value(session, key, &mut size_attrs)
             {
                 Ok((rv, attrs)) => {
                     if rv != CKR_OK {
@@ -753,268 +1272,1461 @@ impl Provide for Pkcs11Provider {
         modulus.resize(modulus_len, 0);
         public_exponent.resize(public_exponent_len, 0);
 
-        let mut extract_attrs: Vec<CK_ATTRIBUTE> = Vec::new();
-        extract_attrs
-            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_MODULUS).with_bytes(modulus.as_mut_slice()));
-        extract_attrs.push(
-            CK_ATTRIBUTE::new(pkcs11::types::CKA_PUBLIC_EXPONENT)
-                .with_bytes(public_exponent.as_mut_slice()),
-        );
+        let mut extract_attrs: Vec<CK_ATTRIBUTE> = Vec::new();
+        extract_attrs
+            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_MODULUS).with_bytes(modulus.as_mut_slice()));
+        extract_attrs.push(
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_PUBLIC_EXPONENT)
+                .with_bytes(public_exponent.as_mut_slice()),
+        );
+
+        match self
+            .backend
+            .get_attribute_value(session, key, &mut extract_attrs)
+        {
+            Ok(res) => {
+                let (rv, attrs) = res;
+                if rv != CKR_OK {
+                    error!("Error when extracting attribute: {}.", rv);
+                    Err(utils::rv_to_response_status(rv))
+                } else {
+                    let modulus = attrs[0].get_bytes();
+                    let public_exponent = attrs[1].get_bytes();
+
+                    // To produce a valid ASN.1 RSAPublicKey structure, 0x00 is put in front of the positive
+                    // integer if highest significant bit is one, to differentiate it from a negative number.
+                    let modulus = IntegerAsn1::from_unsigned_bytes_be(modulus);
+                    let public_exponent = IntegerAsn1::from_unsigned_bytes_be(public_exponent);
+
+                    let key = RsaPublicKey {
+                        modulus,
+                        public_exponent,
+                    };
+                    picky_asn1_der::to_vec(&key).or_else(|err| {
+                        error!("Could not serialise key elements: {}.", err);
+                        Err(ResponseStatus::PsaErrorCommunicationFailure)
+                    })
+                }
+            }
+            Err(e) => {
+                error!("Failed to read attributes from public key. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
+        }
+    }
+
+    /// Read the point of an Elliptic Curve public key object and return it in the uncompressed
+    /// format expected by PSA.
+    fn export_ec_public_key(
+        &self,
+        session: CK_SESSION_HANDLE,
+        key: CK_OBJECT_HANDLE,
+    ) -> Result<Vec<u8>> {
+        let mut size_attrs = vec![CK_ATTRIBUTE::new(pkcs11::types::CKA_EC_POINT)];
+
+        // Get the length of the attribute to retrieve.
+        let ec_point_len = match self
+            .backend
+            .get_attribute_value(session, key, &mut size_attrs)
+        {
+            Ok((rv, attrs)) => {
+                if rv != CKR_OK {
+                    error!("Error when extracting attribute: {}.", rv);
+                    Err(utils::rv_to_response_status(rv))
+                } else {
+                    Ok(attrs[0].ulValueLen)
+                }
+            }
+            Err(e) => {
+                error!("Failed to read attributes from public key. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
+        }?;
+
+        let mut ec_point: Vec<pkcs11::types::CK_BYTE> = vec![0; ec_point_len];
+        let mut extract_attrs = vec![
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_EC_POINT).with_bytes(ec_point.as_mut_slice())
+        ];
+
+        match self
+            .backend
+            .get_attribute_value(session, key, &mut extract_attrs)
+        {
+            Ok((rv, attrs)) => {
+                if rv != CKR_OK {
+                    error!("Error when extracting attribute: {}.", rv);
+                    Err(utils::rv_to_response_status(rv))
+                } else {
+                    utils::der_to_ec_point(attrs[0].get_bytes())
+                }
+            }
+            Err(e) => {
+                error!("Failed to read attributes from public key. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
+        }
+    }
+    /// Read the value of byte array attributes of an object. The token will refuse to reveal
+    /// sensitive attributes of objects that are not extractable.
+    fn get_byte_attributes(
+        &self,
+        session: CK_SESSION_HANDLE,
+        key: CK_OBJECT_HANDLE,
+        attr_types: &[CK_ATTRIBUTE_TYPE],
+    ) -> Result<Vec<Vec<u8>>> {
+        let mut size_attrs: Vec<CK_ATTRIBUTE> = attr_types
+            .iter()
+            .map(|attr_type| CK_ATTRIBUTE::new(*attr_type))
+            .collect();
+
+        // Get the length of the attributes to retrieve.
+        let lengths: Vec<usize> =
+            match self
+                .backend
+                .get_attribute_value(session, key, &mut size_attrs)
+            {
+                Ok((rv, attrs)) => {
+                    if rv != CKR_OK {
+                        error!("Error when extracting attribute: {}.", rv);
+                        Err(utils::rv_to_response_status(rv))
+                    } else {
+                        Ok(attrs.iter().map(|attr| attr.ulValueLen).collect())
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to read attributes from key. Error: {}", e);
+                    Err(utils::to_response_status(e))
+                }
+            }?;
+
+        let mut values: Vec<Vec<pkcs11::types::CK_BYTE>> =
+            lengths.iter().map(|length| vec![0; *length]).collect();
+        let mut extract_attrs: Vec<CK_ATTRIBUTE> = attr_types
+            .iter()
+            .zip(values.iter_mut())
+            .map(|(attr_type, value)| {
+                CK_ATTRIBUTE::new(*attr_type).with_bytes(value.as_mut_slice())
+            })
+            .collect();
+
+        match self
+            .backend
+            .get_attribute_value(session, key, &mut extract_attrs)
+        {
+            Ok((rv, attrs)) => {
+                if rv != CKR_OK {
+                    error!("Error when extracting attribute: {}.", rv);
+                    Err(utils::rv_to_response_status(rv))
+                } else {
+                    Ok(attrs.iter().map(|attr| attr.get_bytes()).collect())
+                }
+            }
+            Err(e) => {
+                error!("Failed to read attributes from key. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
+        }
+    }
+
+    /// Read the components of a RSA private key object and serialise them as a DER-encoded
+    /// `RSAPrivateKey` structure.
+    fn export_rsa_private_key(
+        &self,
+        session: CK_SESSION_HANDLE,
+        key: CK_OBJECT_HANDLE,
+    ) -> Result<Vec<u8>> {
+        let mut values = self
+            .get_byte_attributes(
+                session,
+                key,
+                &[
+                    pkcs11::types::CKA_MODULUS,
+                    pkcs11::types::CKA_PUBLIC_EXPONENT,
+                    pkcs11::types::CKA_PRIVATE_EXPONENT,
+                    pkcs11::types::CKA_PRIME_1,
+                    pkcs11::types::CKA_PRIME_2,
+                    pkcs11::types::CKA_EXPONENT_1,
+                    pkcs11::types::CKA_EXPONENT_2,
+                    pkcs11::types::CKA_COEFFICIENT,
+                ],
+            )?
+            .into_iter()
+            .map(IntegerAsn1::from_unsigned_bytes_be);
+
+        // The values are taken in the same order as they were requested.
+        let mut next = || {
+            values
+                .next()
+                .ok_or(ResponseStatus::PsaErrorCommunicationFailure)
+        };
+        let key = RsaPrivateKey {
+            version: IntegerAsn1::from_unsigned_bytes_be(vec![0]),
+            modulus: next()?,
+            public_exponent: next()?,
+            private_exponent: next()?,
+            prime1: next()?,
+            prime2: next()?,
+            exponent1: next()?,
+            exponent2: next()?,
+            coefficient: next()?,
+        };
+
+        picky_asn1_der::to_vec(&key).or_else(|err| {
+            error!("Could not serialise key elements: {}.", err);
+            Err(ResponseStatus::PsaErrorCommunicationFailure)
+        })
+    }
+}
+
+impl Provide for Pkcs11Provider {
+    fn list_opcodes(&self, _op: list_opcodes::Operation) -> Result<list_opcodes::Result> {
+        Ok(list_opcodes::Result {
+            opcodes: SUPPORTED_OPCODES.iter().copied().collect(),
+        })
+    }
+
+    fn describe(&self) -> Result<ProviderInfo> {
+        Ok(ProviderInfo {
+            // Assigned UUID for this provider: 30e39502-eba6-4d60-a4af-c518b7f5e38f
+            uuid: Uuid::parse_str("30e39502-eba6-4d60-a4af-c518b7f5e38f")
+                .or(Err(ResponseStatus::InvalidEncoding))?,
+            description: String::from("PKCS #11 provider, interfacing with a PKCS #11 library."),
+            vendor: String::from("OASIS Standard."),
+            version_maj: 0,
+            version_min: 1,
+            version_rev: 0,
+            id: ProviderID::Pkcs11,
+        })
+    }
+
+    fn key_id_store(&self) -> Option<&Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>> {
+        Some(&self.key_id_store)
+    }
+
+    fn psa_generate_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_generate_key::Operation,
+    ) -> Result<psa_generate_key::Result> {
+        info!("Pkcs11 Provider - Create Key");
+
+        match op.attributes.key_type {
+            KeyType::RsaKeyPair | KeyType::EccKeyPair { .. } | KeyType::Aes | KeyType::Hmac => (),
+            _ => {
+                error!("The PKCS11 provider currently only supports creating RSA and Elliptic Curve key pairs, AES and HMAC keys.");
+                return Err(ResponseStatus::PsaErrorNotSupported);
+            }
+        }
+
+        let key_name = op.key_name;
+        let key_attributes = op.attributes;
+        // This should never panic on 32 bits or more machines.
+        let key_size = std::convert::TryFrom::try_from(op.attributes.key_bits).unwrap();
+        let ec_params = match key_attributes.key_type {
+            KeyType::EccKeyPair { .. } => utils::ec_params(key_attributes)?,
+            _ => Vec::new(),
+        };
+
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
+        let mut local_ids_handle = self.local_ids.write().expect("Local ID lock poisoned");
+        if key_id_exists(&key_triple, &*store_handle)? {
+            return Err(ResponseStatus::PsaErrorAlreadyExists);
+        }
+        let key_id = create_key_id(
+            key_triple.clone(),
+            key_attributes,
+            &mut *store_handle,
+            &mut local_ids_handle,
+        )?;
+
+        // For secret keys, only the private template is used.
+        let is_secret_key = match key_attributes.key_type {
+            KeyType::Aes | KeyType::Hmac => true,
+            _ => false,
+        };
+        let mut priv_template: Vec<CK_ATTRIBUTE> = Vec::new();
+        let mut pub_template: Vec<CK_ATTRIBUTE> = Vec::new();
+        let value_len = key_size / 8;
+
+        priv_template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(&key_id));
+        priv_template
+            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_TOKEN).with_bool(&pkcs11::types::CK_TRUE));
+        // The value of exportable keys needs to be readable from the token.
+        let (sensitive, extractable) = sensitive_and_extractable(key_attributes);
+        priv_template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_SENSITIVE).with_bool(&sensitive));
+        priv_template
+            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_EXTRACTABLE).with_bool(&extractable));
+
+        if !is_secret_key {
+            priv_template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_SIGN).with_bool(&pkcs11::types::CK_TRUE),
+            );
+
+            pub_template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_VERIFY).with_bool(&pkcs11::types::CK_TRUE),
+            );
+            pub_template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(&key_id));
+            pub_template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_TOKEN).with_bool(&pkcs11::types::CK_TRUE),
+            );
+            pub_template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_PRIVATE).with_bool(&pkcs11::types::CK_FALSE),
+            );
+        }
+
+        let mechanism = match key_attributes.key_type {
+            KeyType::Aes => {
+                priv_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_VALUE_LEN).with_ck_ulong(&value_len),
+                );
+                priv_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_ENCRYPT)
+                        .with_bool(&pkcs11::types::CK_TRUE),
+                );
+                priv_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_DECRYPT)
+                        .with_bool(&pkcs11::types::CK_TRUE),
+                );
+                pkcs11::types::CKM_AES_KEY_GEN
+            }
+            KeyType::Hmac => {
+                priv_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_VALUE_LEN).with_ck_ulong(&value_len),
+                );
+                priv_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_SIGN).with_bool(&pkcs11::types::CK_TRUE),
+                );
+                priv_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_VERIFY).with_bool(&pkcs11::types::CK_TRUE),
+                );
+                pkcs11::types::CKM_GENERIC_SECRET_KEY_GEN
+            }
+            KeyType::RsaKeyPair => {
+                pub_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_PUBLIC_EXPONENT)
+                        .with_bytes(&PUBLIC_EXPONENT),
+                );
+                pub_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_MODULUS_BITS).with_ck_ulong(&key_size),
+                );
+                pub_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_ENCRYPT)
+                        .with_bool(&pkcs11::types::CK_TRUE),
+                );
+                priv_template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_DECRYPT)
+                        .with_bool(&pkcs11::types::CK_TRUE),
+                );
+                pkcs11::types::CKM_RSA_PKCS_KEY_PAIR_GEN
+            }
+            _ => {
+                pub_template
+                    .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_EC_PARAMS).with_bytes(&ec_params));
+                if key_attributes.key_policy.key_usage_flags.derive {
+                    priv_template.push(
+                        CK_ATTRIBUTE::new(pkcs11::types::CKA_DERIVE)
+                            .with_bool(&pkcs11::types::CK_TRUE),
+                    );
+                }
+                pkcs11::types::CKM_EC_KEY_PAIR_GEN
+            }
+        };
+
+        let mech = CK_MECHANISM {
+            mechanism,
+            pParameter: std::ptr::null_mut(),
+            ulParameterLen: 0,
+        };
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite).or_else(|err| {
+            error!("Error creating a new session: {}.", err);
+            remove_key_id(
+                &key_triple,
+                &key_id,
+                &mut *store_handle,
+                &mut local_ids_handle,
+            )?;
+            Err(err)
+        })?;
+
+        info!("Generating key in session {}", session.session_handle());
+
+        let generate_result = if is_secret_key {
+            self.backend
+                .generate_key(session.session_handle(), &mech, &priv_template)
+                .map(|_key| ())
+        } else {
+            self.backend
+                .generate_key_pair(
+                    session.session_handle(),
+                    &mech,
+                    &pub_template,
+                    &priv_template,
+                )
+                .map(|_keys| ())
+        };
+
+        match generate_result {
+            Ok(()) => {
+                session.mark_clean();
+                Ok(psa_generate_key::Result {})
+            }
+            Err(e) => {
+                error!("Generate Key operation failed with {}", e);
+                remove_key_id(
+                    &key_triple,
+                    &key_id,
+                    &mut *store_handle,
+                    &mut local_ids_handle,
+                )?;
+                Err(utils::to_response_status(e))
+            }
+        }
+    }
+
+    fn psa_import_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_import_key::Operation,
+    ) -> Result<psa_import_key::Result> {
+        info!("Pkcs11 Provider - Import Key");
+
+        let key_name = op.key_name;
+        let key_attributes = op.attributes;
+
+        // The values pointed to by the template attributes need to outlive it.
+        let (key_class, key_type, allowed_mechanism, key_values) = match key_attributes.key_type {
+            KeyType::RsaPublicKey => {
+                let public_key: RsaPublicKey =
+                    picky_asn1_der::from_bytes(&op.data).or_else(|e| {
+                        error!("Failed to parse RsaPublicKey data ({}).", e);
+                        Err(ResponseStatus::PsaErrorInvalidArgument)
+                    })?;
+
+                if public_key.modulus.is_negative() || public_key.public_exponent.is_negative() {
+                    error!("Only positive modulus and public exponent are supported.");
+                    return Err(ResponseStatus::PsaErrorInvalidArgument);
+                }
+
+                (
+                    pkcs11::types::CKO_PUBLIC_KEY,
+                    pkcs11::types::CKK_RSA,
+                    rsa_mechanism_type(key_attributes.key_policy.key_algorithm),
+                    vec![
+                        (
+                            pkcs11::types::CKA_MODULUS,
+                            public_key.modulus.as_unsigned_bytes_be().to_vec(),
+                        ),
+                        (
+                            pkcs11::types::CKA_PUBLIC_EXPONENT,
+                            public_key.public_exponent.as_unsigned_bytes_be().to_vec(),
+                        ),
+                    ],
+                )
+            }
+            KeyType::RsaKeyPair => {
+                let private_key: RsaPrivateKey =
+                    picky_asn1_der::from_bytes(&op.data).or_else(|e| {
+                        error!("Failed to parse RsaPrivateKey data ({}).", e);
+                        Err(ResponseStatus::PsaErrorInvalidArgument)
+                    })?;
+
+                let values = vec![
+                    (pkcs11::types::CKA_MODULUS, private_key.modulus),
+                    (
+                        pkcs11::types::CKA_PUBLIC_EXPONENT,
+                        private_key.public_exponent,
+                    ),
+                    (
+                        pkcs11::types::CKA_PRIVATE_EXPONENT,
+                        private_key.private_exponent,
+                    ),
+                    (pkcs11::types::CKA_PRIME_1, private_key.prime1),
+                    (pkcs11::types::CKA_PRIME_2, private_key.prime2),
+                    (pkcs11::types::CKA_EXPONENT_1, private_key.exponent1),
+                    (pkcs11::types::CKA_EXPONENT_2, private_key.exponent2),
+                    (pkcs11::types::CKA_COEFFICIENT, private_key.coefficient),
+                ];
+
+                if values.iter().any(|(_, value)| value.is_negative()) {
+                    error!("Only positive RSA key components are supported.");
+                    return Err(ResponseStatus::PsaErrorInvalidArgument);
+                }
+
+                (
+                    pkcs11::types::CKO_PRIVATE_KEY,
+                    pkcs11::types::CKK_RSA,
+                    rsa_mechanism_type(key_attributes.key_policy.key_algorithm),
+                    values
+                        .into_iter()
+                        .map(|(attribute_type, value)| {
+                            (attribute_type, value.as_unsigned_bytes_be().to_vec())
+                        })
+                        .collect(),
+                )
+            }
+            KeyType::EccPublicKey { .. } => (
+                pkcs11::types::CKO_PUBLIC_KEY,
+                pkcs11::types::CKK_EC,
+                pkcs11::types::CKM_ECDSA,
+                vec![
+                    (
+                        pkcs11::types::CKA_EC_PARAMS,
+                        utils::ec_params(key_attributes)?,
+                    ),
+                    (
+                        pkcs11::types::CKA_EC_POINT,
+                        utils::ec_point_to_der(op.data)?,
+                    ),
+                ],
+            ),
+            KeyType::Aes => {
+                let allowed_mechanism = match key_attributes.key_policy.key_algorithm {
+                    Algorithm::Cipher(cipher) => cipher_mechanism_type(cipher)?,
+                    _ => {
+                        error!("AES keys can only be used with cipher algorithms in the PKCS 11 provider.");
+                        return Err(ResponseStatus::PsaErrorNotSupported);
+                    }
+                };
+
+                (
+                    pkcs11::types::CKO_SECRET_KEY,
+                    pkcs11::types::CKK_AES,
+                    allowed_mechanism,
+                    vec![(pkcs11::types::CKA_VALUE, op.data)],
+                )
+            }
+            KeyType::Hmac => {
+                let allowed_mechanism = match key_attributes.key_policy.key_algorithm {
+                    Algorithm::Mac(mac) => mac_mechanism_type(mac)?,
+                    _ => {
+                        error!("HMAC keys can only be used with MAC algorithms in the PKCS 11 provider.");
+                        return Err(ResponseStatus::PsaErrorNotSupported);
+                    }
+                };
+
+                (
+                    pkcs11::types::CKO_SECRET_KEY,
+                    pkcs11::types::CKK_GENERIC_SECRET,
+                    allowed_mechanism,
+                    vec![(pkcs11::types::CKA_VALUE, op.data)],
+                )
+            }
+            _ => {
+                error!("The PKCS 11 provider currently only supports importing RSA key pairs, RSA and Elliptic Curve public keys, AES and HMAC keys.");
+                return Err(ResponseStatus::PsaErrorNotSupported);
+            }
+        };
+
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
+        let mut local_ids_handle = self.local_ids.write().expect("Local ID lock poisoned");
+        if key_id_exists(&key_triple, &*store_handle)? {
+            return Err(ResponseStatus::PsaErrorAlreadyExists);
+        }
+        let key_id = create_key_id(
+            key_triple.clone(),
+            key_attributes,
+            &mut *store_handle,
+            &mut local_ids_handle,
+        )?;
+
+        let (sensitive, extractable) = sensitive_and_extractable(key_attributes);
+        let mut template: Vec<CK_ATTRIBUTE> = Vec::new();
+
+        template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS).with_ck_ulong(&key_class));
+        template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_KEY_TYPE).with_ck_ulong(&key_type));
+        template
+            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_TOKEN).with_bool(&pkcs11::types::CK_TRUE));
+        for (attribute_type, value) in key_values.iter() {
+            template.push(CK_ATTRIBUTE::new(*attribute_type).with_bytes(value));
+        }
+        if key_class == pkcs11::types::CKO_SECRET_KEY {
+            if key_type == pkcs11::types::CKK_AES {
+                template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_ENCRYPT)
+                        .with_bool(&pkcs11::types::CK_TRUE),
+                );
+                template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_DECRYPT)
+                        .with_bool(&pkcs11::types::CK_TRUE),
+                );
+            } else {
+                template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_SIGN).with_bool(&pkcs11::types::CK_TRUE),
+                );
+                template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_VERIFY).with_bool(&pkcs11::types::CK_TRUE),
+                );
+            }
+            template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_SENSITIVE).with_bool(&sensitive));
+            template
+                .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_EXTRACTABLE).with_bool(&extractable));
+        } else if key_class == pkcs11::types::CKO_PRIVATE_KEY {
+            template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_SIGN).with_bool(&pkcs11::types::CK_TRUE),
+            );
+            template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_DECRYPT).with_bool(&pkcs11::types::CK_TRUE),
+            );
+            template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_PRIVATE).with_bool(&pkcs11::types::CK_TRUE),
+            );
+            template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_SENSITIVE).with_bool(&sensitive));
+            template
+                .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_EXTRACTABLE).with_bool(&extractable));
+        } else {
+            template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_VERIFY).with_bool(&pkcs11::types::CK_TRUE),
+            );
+            if key_type == pkcs11::types::CKK_RSA {
+                template.push(
+                    CK_ATTRIBUTE::new(pkcs11::types::CKA_ENCRYPT)
+                        .with_bool(&pkcs11::types::CK_TRUE),
+                );
+            }
+            template.push(
+                CK_ATTRIBUTE::new(pkcs11::types::CKA_PRIVATE).with_bool(&pkcs11::types::CK_FALSE),
+            );
+        }
+        template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(&key_id));
+
+        // Restrict to the mechanism matching the key type.
+        let allowed_mechanisms = [allowed_mechanism];
+        // The attribute contains a pointer to the allowed_mechanism array and its size as
+        // ulValueLen.
+        let mut allowed_mechanisms_attribute =
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_ALLOWED_MECHANISMS);
+        allowed_mechanisms_attribute.ulValueLen = mem::size_of_val(&allowed_mechanisms);
+        allowed_mechanisms_attribute.pValue = &allowed_mechanisms
+            as *const pkcs11::types::CK_MECHANISM_TYPE
+            as pkcs11::types::CK_VOID_PTR;
+        template.push(allowed_mechanisms_attribute);
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite).or_else(|err| {
+            error!("Error creating a new session: {}.", err);
+            remove_key_id(
+                &key_triple,
+                &key_id,
+                &mut *store_handle,
+                &mut local_ids_handle,
+            )?;
+            Err(err)
+        })?;
+
+        info!("Importing key in session {}", session.session_handle());
+
+        let key = match self
+            .backend
+            .create_object(session.session_handle(), &template)
+        {
+            Ok(key) => key,
+            Err(e) => {
+                error!("Import operation failed with {}", e);
+                remove_key_id(
+                    &key_triple,
+                    &key_id,
+                    &mut *store_handle,
+                    &mut local_ids_handle,
+                )?;
+                return Err(utils::to_response_status(e));
+            }
+        };
+
+        if key_class != pkcs11::types::CKO_PRIVATE_KEY {
+            session.mark_clean();
+            return Ok(psa_import_key::Result {});
+        }
+
+        // The public part of an imported key pair is stored as a separate object, sharing the
+        // same key ID as the private one.
+        let mut pub_template: Vec<CK_ATTRIBUTE> = Vec::new();
+        pub_template.push(
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS)
+                .with_ck_ulong(&pkcs11::types::CKO_PUBLIC_KEY),
+        );
+        pub_template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_KEY_TYPE).with_ck_ulong(&key_type));
+        pub_template
+            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_TOKEN).with_bool(&pkcs11::types::CK_TRUE));
+        for (attribute_type, value) in key_values.iter().filter(|(attribute_type, _)| {
+            *attribute_type == pkcs11::types::CKA_MODULUS
+                || *attribute_type == pkcs11::types::CKA_PUBLIC_EXPONENT
+        }) {
+            pub_template.push(CK_ATTRIBUTE::new(*attribute_type).with_bytes(value));
+        }
+        pub_template
+            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_VERIFY).with_bool(&pkcs11::types::CK_TRUE));
+        pub_template
+            .push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ENCRYPT).with_bool(&pkcs11::types::CK_TRUE));
+        pub_template.push(
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_PRIVATE).with_bool(&pkcs11::types::CK_FALSE),
+        );
+        pub_template.push(CK_ATTRIBUTE::new(pkcs11::types::CKA_ID).with_bytes(&key_id));
+        pub_template.push(allowed_mechanisms_attribute);
+
+        match self
+            .backend
+            .create_object(session.session_handle(), &pub_template)
+        {
+            Ok(_pub_key) => {
+                session.mark_clean();
+                Ok(psa_import_key::Result {})
+            }
+            Err(e) => {
+                error!("Import operation of the public key failed with {}", e);
+                if let Err(destroy_error) =
+                    self.backend.destroy_object(session.session_handle(), key)
+                {
+                    warn!(
+                        "Failed to destroy the imported private key object. Error: {}",
+                        destroy_error
+                    );
+                }
+                remove_key_id(
+                    &key_triple,
+                    &key_id,
+                    &mut *store_handle,
+                    &mut local_ids_handle,
+                )?;
+                Err(utils::to_response_status(e))
+            }
+        }
+    }
+
+    fn psa_export_public_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_export_public_key::Operation,
+    ) -> Result<psa_export_public_key::Result> {
+        info!("Pkcs11 Provider - Export Public Key");
+
+        let key_name = op.key_name;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_export()?;
+
+        let session = Session::new(self, ReadWriteSession::ReadOnly)?;
+        info!("Export public key in session {}", session.session_handle());
+
+        let key = self.find_key(session.session_handle(), &key_id, KeyPairType::PublicKey)?;
+        info!("Located key for export.");
+
+        let data = match key_attributes.key_type {
+            KeyType::EccKeyPair { .. } | KeyType::EccPublicKey { .. } => {
+                self.export_ec_public_key(session.session_handle(), key)?
+            }
+            _ => self.export_rsa_public_key(session.session_handle(), key)?,
+        };
+
+        session.mark_clean();
+        Ok(psa_export_public_key::Result { data })
+    }
+
+    fn psa_export_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_export_key::Operation,
+    ) -> Result<psa_export_key::Result> {
+        info!("Pkcs11 Provider - Export Key");
+
+        let key_name = op.key_name;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_export()?;
+
+        let session = Session::new(self, ReadWriteSession::ReadOnly)?;
+        info!("Export key in session {}", session.session_handle());
+
+        let data = match key_attributes.key_type {
+            KeyType::RsaPublicKey => {
+                let key =
+                    self.find_key(session.session_handle(), &key_id, KeyPairType::PublicKey)?;
+                self.export_rsa_public_key(session.session_handle(), key)?
+            }
+            KeyType::EccPublicKey { .. } => {
+                let key =
+                    self.find_key(session.session_handle(), &key_id, KeyPairType::PublicKey)?;
+                self.export_ec_public_key(session.session_handle(), key)?
+            }
+            KeyType::RsaKeyPair => {
+                let key =
+                    self.find_key(session.session_handle(), &key_id, KeyPairType::PrivateKey)?;
+                self.export_rsa_private_key(session.session_handle(), key)?
+            }
+            KeyType::EccKeyPair { .. } => {
+                let key =
+                    self.find_key(session.session_handle(), &key_id, KeyPairType::PrivateKey)?;
+                let mut values = self.get_byte_attributes(
+                    session.session_handle(),
+                    key,
+                    &[pkcs11::types::CKA_VALUE],
+                )?;
+                let private_value = values.remove(0);
+                // PSA expects the private value to be exactly as long as the size of the curve.
+                let value_len = (key_attributes.key_bits + 7) / 8;
+                if private_value.len() > value_len {
+                    error!("The private value is larger than the curve size.");
+                    return Err(ResponseStatus::PsaErrorCommunicationFailure);
+                }
+                let mut data = vec![0; value_len - private_value.len()];
+                data.extend_from_slice(&private_value);
+                data
+            }
+            KeyType::Aes | KeyType::Hmac => {
+                let key =
+                    self.find_key(session.session_handle(), &key_id, KeyPairType::SecretKey)?;
+                let mut values = self.get_byte_attributes(
+                    session.session_handle(),
+                    key,
+                    &[pkcs11::types::CKA_VALUE],
+                )?;
+                values.remove(0)
+            }
+            _ => {
+                error!("The PKCS11 provider does not support exporting this key type.");
+                return Err(ResponseStatus::PsaErrorNotSupported);
+            }
+        };
+        info!("Exported key.");
+
+        session.mark_clean();
+        Ok(psa_export_key::Result { data })
+    }
+
+    fn psa_destroy_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_destroy_key::Operation,
+    ) -> Result<psa_destroy_key::Result> {
+        info!("Pkcs11 Provider - Destroy Key");
+
+        let key_name = op.key_name;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
+        let mut local_ids_handle = self.local_ids.write().expect("Local ID lock poisoned");
+        let (key_id, _) = get_key_id(&key_triple, &*store_handle)?;
+
+        if self.preserved_key_ids.contains(&key_id) {
+            info!("The key was adopted from the token, only its mapping is removed.");
+            remove_key_id(
+                &key_triple,
+                &key_id,
+                &mut *store_handle,
+                &mut local_ids_handle,
+            )?;
+            return Ok(psa_destroy_key::Result {});
+        }
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!(
+            "Deleting RSA keypair in session {}",
+            session.session_handle()
+        );
+
+        match self.find_key(session.session_handle(), &key_id, KeyPairType::Any) {
+            Ok(key) => {
+                match self.backend.destroy_object(session.session_handle(), key) {
+                    Ok(_) => info!("Private part of the key destroyed successfully."),
+                    Err(e) => {
+                        error!("Failed to destroy private part of the key. Error: {}", e);
+                        return Err(utils::to_response_status(e));
+                    }
+                };
+            }
+            Err(e) => {
+                error!("Error destroying key: {}", e);
+                return Err(e);
+            }
+        };
+
+        // Second key is optional.
+        match self.find_key(session.session_handle(), &key_id, KeyPairType::Any) {
+            Ok(key) => {
+                match self.backend.destroy_object(session.session_handle(), key) {
+                    Ok(_) => info!("Private part of the key destroyed successfully."),
+                    Err(e) => {
+                        error!("Failed to destroy private part of the key. Error: {}", e);
+                        return Err(utils::to_response_status(e));
+                    }
+                };
+            }
+            // A second key is optional.
+            Err(ResponseStatus::PsaErrorDoesNotExist) => (),
+            Err(e) => {
+                error!("Error destroying key: {}", e);
+                return Err(e);
+            }
+        };
+        session.mark_clean();
+
+        remove_key_id(
+            &key_triple,
+            &key_id,
+            &mut *store_handle,
+            &mut local_ids_handle,
+        )?;
+
+        Ok(psa_destroy_key::Result {})
+    }
+
+    fn psa_sign_hash(
+        &self,
+        app_name: ApplicationName,
+        op: psa_sign_hash::Operation,
+    ) -> Result<psa_sign_hash::Result> {
+        info!("Pkcs11 Provider - Asym Sign");
+
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, op.key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_sign_hash()?;
+        key_attributes.permits_alg(op.alg.into())?;
+        key_attributes.compatible_with_alg(op.alg.into())?;
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!("Asymmetric sign in session {}", session.session_handle());
+
+        let signature =
+            self.sign_hash_with_key(session.session_handle(), &key_id, op.alg, op.hash)?;
+
+        session.mark_clean();
+        Ok(psa_sign_hash::Result { signature })
+    }
+
+    fn psa_verify_hash(
+        &self,
+        app_name: ApplicationName,
+        op: psa_verify_hash::Operation,
+    ) -> Result<psa_verify_hash::Result> {
+        info!("Pkcs11 Provider - Asym Verify");
+
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, op.key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_verify_hash()?;
+        key_attributes.permits_alg(op.alg.into())?;
+        key_attributes.compatible_with_alg(op.alg.into())?;
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!("Asymmetric verify in session {}", session.session_handle());
+
+        self.verify_hash_with_key(
+            session.session_handle(),
+            &key_id,
+            op.alg,
+            op.hash,
+            &op.signature,
+        )?;
+
+        session.mark_clean();
+        Ok(psa_verify_hash::Result {})
+    }
+
+    fn psa_sign_message(
+        &self,
+        app_name: ApplicationName,
+        op: psa_sign_message::Operation,
+    ) -> Result<psa_sign_message::Result> {
+        info!("Pkcs11 Provider - Sign Message");
+
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, op.key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_sign_message()?;
+        key_attributes.permits_alg(op.alg.into())?;
+        key_attributes.compatible_with_alg(op.alg.into())?;
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!("Sign message in session {}", session.session_handle());
+
+        let hash = self.digest(
+            session.session_handle(),
+            signature_hash_alg(op.alg)?,
+            &op.message,
+        )?;
+        let signature = self.sign_hash_with_key(session.session_handle(), &key_id, op.alg, hash)?;
+
+        session.mark_clean();
+        Ok(psa_sign_message::Result { signature })
+    }
+
+    fn psa_verify_message(
+        &self,
+        app_name: ApplicationName,
+        op: psa_verify_message::Operation,
+    ) -> Result<psa_verify_message::Result> {
+        info!("Pkcs11 Provider - Verify Message");
+
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, op.key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_verify_message()?;
+        key_attributes.permits_alg(op.alg.into())?;
+        key_attributes.compatible_with_alg(op.alg.into())?;
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!("Verify message in session {}", session.session_handle());
+
+        let hash = self.digest(
+            session.session_handle(),
+            signature_hash_alg(op.alg)?,
+            &op.message,
+        )?;
+        self.verify_hash_with_key(
+            session.session_handle(),
+            &key_id,
+            op.alg,
+            hash,
+            &op.signature,
+        )?;
+
+        session.mark_clean();
+        Ok(psa_verify_message::Result {})
+    }
+
+    fn psa_asymmetric_encrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_asymmetric_encrypt::Operation,
+    ) -> Result<psa_asymmetric_encrypt::Result> {
+        info!("Pkcs11 Provider - Asym Encrypt");
+
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let plaintext = op.plaintext;
+        let label = op.salt.unwrap_or_default();
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_encrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let oaep_params = rsa_oaep_sha256_params(&label);
+        let mech = encrypt_mechanism(alg, &label, &oaep_params)?;
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!("Asymmetric encrypt in session {}", session.session_handle());
+
+        let key = self.find_key(session.session_handle(), &key_id, KeyPairType::PublicKey)?;
+        info!("Located public key.");
+
+        match self
+            .backend
+            .encrypt_init(session.session_handle(), &mech, key)
+        {
+            Ok(_) => {
+                info!("Encrypt operation initialized.");
+
+                match self.backend.encrypt(session.session_handle(), &plaintext) {
+                    Ok(ciphertext) => {
+                        session.mark_clean();
+                        Ok(psa_asymmetric_encrypt::Result { ciphertext })
+                    }
+                    Err(e) => {
+                        error!("Failed to execute encrypting operation. Error: {}", e);
+                        Err(utils::to_response_status(e))
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to initialize encrypting operation. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
+        }
+    }
+
+    fn psa_asymmetric_decrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_asymmetric_decrypt::Operation,
+    ) -> Result<psa_asymmetric_decrypt::Result> {
+        info!("Pkcs11 Provider - Asym Decrypt");
+
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let ciphertext = op.ciphertext;
+        let label = op.salt.unwrap_or_default();
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_decrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let oaep_params = rsa_oaep_sha256_params(&label);
+        let mech = encrypt_mechanism(alg, &label, &oaep_params)?;
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!("Asymmetric decrypt in session {}", session.session_handle());
+
+        let key = self.find_key(session.session_handle(), &key_id, KeyPairType::PrivateKey)?;
+        info!("Located decrypting key.");
+
+        match self
+            .backend
+            .decrypt_init(session.session_handle(), &mech, key)
+        {
+            Ok(_) => {
+                info!("Decrypt operation initialized.");
+
+                match self.backend.decrypt(session.session_handle(), &ciphertext) {
+                    Ok(plaintext) => {
+                        session.mark_clean();
+                        Ok(psa_asymmetric_decrypt::Result { plaintext })
+                    }
+                    Err(e) => {
+                        error!("Failed to execute decrypting operation. Error: {}", e);
+                        Err(utils::to_response_status(e))
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to initialize decrypting operation. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
+        }
+    }
+
+    fn psa_cipher_encrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_cipher_encrypt::Operation,
+    ) -> Result<psa_cipher_encrypt::Result> {
+        info!("Pkcs11 Provider - Cipher Encrypt");
+
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let iv = op.iv;
+        let plaintext = op.plaintext;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_encrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let ctr_params = aes_ctr_params(&iv);
+        let mech = cipher_mechanism(alg, &iv, &ctr_params)?;
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!("Cipher encrypt in session {}", session.session_handle());
+
+        let key = self.find_key(session.session_handle(), &key_id, KeyPairType::SecretKey)?;
+        info!("Located secret key.");
 
         match self
             .backend
-            .get_attribute_value(session.session_handle(), key, &mut extract_attrs)
+            .encrypt_init(session.session_handle(), &mech, key)
         {
-            Ok(res) => {
-                let (rv, attrs) = res;
-                if rv != CKR_OK {
-                    error!("Error when extracting attribute: {}.", rv);
-                    Err(utils::rv_to_response_status(rv))
-                } else {
-                    let modulus = attrs[0].get_bytes();
-                    let public_exponent = attrs[1].get_bytes();
-
-                    // To produce a valid ASN.1 RSAPublicKey structure, 0x00 is put in front of the positive
-                    // integer if highest significant bit is one, to differentiate it from a negative number.
-                    let modulus = IntegerAsn1::from_unsigned_bytes_be(modulus);
-                    let public_exponent = IntegerAsn1::from_unsigned_bytes_be(public_exponent);
+            Ok(_) => {
+                info!("Encrypt operation initialized.");
 
-                    let key = RsaPublicKey {
-                        modulus,
-                        public_exponent,
-                    };
-                    let data = picky_asn1_der::to_vec(&key).or_else(|err| {
-                        error!("Could not serialise key elements: {}.", err);
-                        Err(ResponseStatus::PsaErrorCommunicationFailure)
-                    })?;
-                    Ok(psa_export_public_key::Result { data })
+                match self.backend.encrypt(session.session_handle(), &plaintext) {
+                    Ok(ciphertext) => {
+                        session.mark_clean();
+                        Ok(psa_cipher_encrypt::Result { ciphertext })
+                    }
+                    Err(e) => {
+                        error!("Failed to execute encrypting operation. Error: {}", e);
+                        Err(utils::to_response_status(e))
+                    }
                 }
             }
             Err(e) => {
-                error!("Failed to read attributes from public key. Error: {}", e);
+                error!("Failed to initialize encrypting operation. Error: {}", e);
                 Err(utils::to_response_status(e))
             }
         }
     }
 
-    fn psa_destroy_key(
+    fn psa_cipher_decrypt(
         &self,
         app_name: ApplicationName,
-        op: psa_destroy_key::Operation,
-    ) -> Result<psa_destroy_key::Result> {
-        info!("Pkcs11 Provider - Destroy Key");
+        op: psa_cipher_decrypt::Operation,
+    ) -> Result<psa_cipher_decrypt::Result> {
+        info!("Pkcs11 Provider - Cipher Decrypt");
 
         let key_name = op.key_name;
+        let alg = op.alg;
+        let iv = op.iv;
+        let ciphertext = op.ciphertext;
         let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
-        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
-        let mut local_ids_handle = self.local_ids.write().expect("Local ID lock poisoned");
-        let (key_id, _) = get_key_id(&key_triple, &*store_handle)?;
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_decrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let ctr_params = aes_ctr_params(&iv);
+        let mech = cipher_mechanism(alg, &iv, &ctr_params)?;
 
         let session = Session::new(self, ReadWriteSession::ReadWrite)?;
-        info!(
-            "Deleting RSA keypair in session {}",
-            session.session_handle()
-        );
+        info!("Cipher decrypt in session {}", session.session_handle());
 
-        match self.find_key(session.session_handle(), key_id, KeyPairType::Any) {
-            Ok(key) => {
-                match self.backend.destroy_object(session.session_handle(), key) {
-                    Ok(_) => info!("Private part of the key destroyed successfully."),
-                    Err(e) => {
-                        error!("Failed to destroy private part of the key. Error: {}", e);
-                        return Err(utils::to_response_status(e));
-                    }
-                };
-            }
-            Err(e) => {
-                error!("Error destroying key: {}", e);
-                return Err(e);
-            }
-        };
+        let key = self.find_key(session.session_handle(), &key_id, KeyPairType::SecretKey)?;
+        info!("Located secret key.");
 
-        // Second key is optional.
-        match self.find_key(session.session_handle(), key_id, KeyPairType::Any) {
-            Ok(key) => {
-                match self.backend.destroy_object(session.session_handle(), key) {
-                    Ok(_) => info!("Private part of the key destroyed successfully."),
+        match self
+            .backend
+            .decrypt_init(session.session_handle(), &mech, key)
+        {
+            Ok(_) => {
+                info!("Decrypt operation initialized.");
+
+                match self.backend.decrypt(session.session_handle(), &ciphertext) {
+                    Ok(plaintext) => {
+                        session.mark_clean();
+                        Ok(psa_cipher_decrypt::Result { plaintext })
+                    }
                     Err(e) => {
-                        error!("Failed to destroy private part of the key. Error: {}", e);
-                        return Err(utils::to_response_status(e));
+                        error!("Failed to execute decrypting operation. Error: {}", e);
+                        Err(utils::to_response_status(e))
                     }
-                };
+                }
             }
-            // A second key is optional.
-            Err(ResponseStatus::PsaErrorDoesNotExist) => (),
             Err(e) => {
-                error!("Error destroying key: {}", e);
-                return Err(e);
+                error!("Failed to initialize decrypting operation. Error: {}", e);
+                Err(utils::to_response_status(e))
             }
-        };
-
-        remove_key_id(
-            &key_triple,
-            key_id,
-            &mut *store_handle,
-            &mut local_ids_handle,
-        )?;
-
-        Ok(psa_destroy_key::Result {})
+        }
     }
 
-    fn psa_sign_hash(
+    fn psa_mac_compute(
         &self,
         app_name: ApplicationName,
-        op: psa_sign_hash::Operation,
-    ) -> Result<psa_sign_hash::Result> {
-        info!("Pkcs11 Provider - Asym Sign");
+        op: psa_mac_compute::Operation,
+    ) -> Result<psa_mac_compute::Result> {
+        info!("Pkcs11 Provider - MAC Compute");
 
         let key_name = op.key_name;
         let alg = op.alg;
-        let mut hash = op.hash;
+        let input = op.input;
         let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
         let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
         let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
 
-        key_attributes.can_sign_hash()?;
+        key_attributes.can_sign_message()?;
         key_attributes.permits_alg(alg.into())?;
         key_attributes.compatible_with_alg(alg.into())?;
 
-        match alg {
-            AsymmetricSignature::RsaPkcs1v15Sign {
-                hash_alg: Hash::Sha256,
-            } => (),
-            _ => {
-                error!(
-                    "The PKCS 11 provider currently only supports \"RSA PKCS#1 v1.5 signature with hashing\" algorithm with SHA-256 as hashing algorithm for the PsaSignHash operation.");
-                return Err(ResponseStatus::PsaErrorNotSupported);
-            }
-        }
-
         let mech = CK_MECHANISM {
-            mechanism: pkcs11::types::CKM_RSA_PKCS,
+            mechanism: mac_mechanism_type(alg)?,
             pParameter: std::ptr::null_mut(),
             ulParameterLen: 0,
         };
 
-        if hash.len() != 32 {
-            error!("The PKCS11 provider currently only supports 256 bits long digests.");
-            return Err(ResponseStatus::PsaErrorNotSupported);
-        }
-
         let session = Session::new(self, ReadWriteSession::ReadWrite)?;
-        info!("Asymmetric sign in session {}", session.session_handle());
+        info!("MAC compute in session {}", session.session_handle());
 
-        let key = self.find_key(session.session_handle(), key_id, KeyPairType::PrivateKey)?;
-        info!("Located signing key.");
+        let key = self.find_key(session.session_handle(), &key_id, KeyPairType::SecretKey)?;
+        info!("Located MAC key.");
 
         match self.backend.sign_init(session.session_handle(), &mech, key) {
             Ok(_) => {
-                info!("Signing operation initialized.");
-
-                // Build a valid ASN.1 DigestInfo DER-encoded structure by appending the hash to a
-                // DigestAlgorithmIdentifier value representing the SHA256 OID with no parameters.
-                // The OID used is: "2.16.840.1.101.3.4.2.1".
-                // It would be better to use the DigestInfo structure defined in this file but the
-                // AlgorithmIdentifier structure does not currently support the simple SHA256 OID.
-                // See Devolutions/picky-rs#19
-                let mut digest_info = vec![
-                    0x30, 0x31, 0x30, 0x0D, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04,
-                    0x02, 0x01, 0x05, 0x00, 0x04, 0x20,
-                ];
-                digest_info.append(&mut hash);
+                info!("MAC operation initialized.");
 
-                match self.backend.sign(session.session_handle(), &digest_info) {
-                    Ok(signature) => Ok(psa_sign_hash::Result { signature }),
+                match self.backend.sign(session.session_handle(), &input) {
+                    Ok(mac) => {
+                        session.mark_clean();
+                        Ok(psa_mac_compute::Result { mac })
+                    }
                     Err(e) => {
-                        error!("Failed to execute signing operation. Error: {}", e);
+                        error!("Failed to execute MAC operation. Error: {}", e);
                         Err(utils::to_response_status(e))
                     }
                 }
             }
             Err(e) => {
-                error!("Failed to initialize signing operation. Error: {}", e);
+                error!("Failed to initialize MAC operation. Error: {}", e);
                 Err(utils::to_response_status(e))
             }
         }
     }
 
-    fn psa_verify_hash(
+    fn psa_mac_verify(
         &self,
         app_name: ApplicationName,
-        op: psa_verify_hash::Operation,
-    ) -> Result<psa_verify_hash::Result> {
-        info!("Pkcs11 Provider - Asym Verify");
+        op: psa_mac_verify::Operation,
+    ) -> Result<psa_mac_verify::Result> {
+        info!("Pkcs11 Provider - MAC Verify");
 
         let key_name = op.key_name;
         let alg = op.alg;
-        let mut hash = op.hash;
-        let signature = op.signature;
+        let input = op.input;
+        let mac = op.mac;
         let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
         let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
         let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
 
-        key_attributes.can_verify_hash()?;
+        key_attributes.can_verify_message()?;
         key_attributes.permits_alg(alg.into())?;
         key_attributes.compatible_with_alg(alg.into())?;
 
-        match alg {
-            AsymmetricSignature::RsaPkcs1v15Sign {
-                hash_alg: Hash::Sha256,
-            } => (),
-            _ => {
-                error!(
-                    "The PKCS 11 provider currently only supports \"RSA PKCS#1 v1.5 signature with hashing\" algorithm with SHA-256 as hashing algorithm for the PsaVerifyHash operation.");
-                return Err(ResponseStatus::PsaErrorNotSupported);
-            }
-        }
-
         let mech = CK_MECHANISM {
-            // Verify without hashing.
-            mechanism: pkcs11::types::CKM_RSA_PKCS,
+            mechanism: mac_mechanism_type(alg)?,
             pParameter: std::ptr::null_mut(),
             ulParameterLen: 0,
         };
 
-        if hash.len() != 32 {
-            error!("The PKCS11 provider currently only supports 256 bits long digests.");
-            return Err(ResponseStatus::PsaErrorNotSupported);
-        }
-
         let session = Session::new(self, ReadWriteSession::ReadWrite)?;
-        info!("Asymmetric verify in session {}", session.session_handle());
+        info!("MAC verify in session {}", session.session_handle());
 
-        let key = self.find_key(session.session_handle(), key_id, KeyPairType::PublicKey)?;
-        info!("Located public key.");
+        let key = self.find_key(session.session_handle(), &key_id, KeyPairType::SecretKey)?;
+        info!("Located MAC key.");
 
+        // The comparison is done by the token with C_Verify, a mismatch is returned as
+        // CKR_SIGNATURE_INVALID which is converted to PsaErrorInvalidSignature.
         match self
             .backend
             .verify_init(session.session_handle(), &mech, key)
         {
             Ok(_) => {
-                info!("Verify operation initialized.");
-
-                // Build a valid ASN.1 DigestInfo DER-encoded structure by appending the hash to a
-                // DigestAlgorithmIdentifier value representing the SHA256 OID with no parameters.
-                // The OID used is: "2.16.840.1.101.3.4.2.1".
-                // It would be better to use the DigestInfo structure defined in this file but the
-                // AlgorithmIdentifier structure does not currently support the simple SHA256 OID.
-                // See Devolutions/picky-rs#19
-                let mut digest_info = vec![
-                    0x30, 0x31, 0x30, 0x0D, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04,
-                    0x02, 0x01, 0x05, 0x00, 0x04, 0x20,
-                ];
-                digest_info.append(&mut hash);
+                info!("MAC verify operation initialized.");
 
-                match self
-                    .backend
-                    .verify(session.session_handle(), &digest_info, &signature)
-                {
-                    Ok(_) => Ok(psa_verify_hash::Result {}),
+                match self.backend.verify(session.session_handle(), &input, &mac) {
+                    Ok(_) => {
+                        session.mark_clean();
+                        Ok(psa_mac_verify::Result {})
+                    }
                     Err(e) => Err(utils::to_response_status(e)),
                 }
             }
             Err(e) => {
-                error!("Failed to initialize verifying operation. Error: {}", e);
+                error!("Failed to initialize MAC verify operation. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
+        }
+    }
+
+    fn psa_raw_key_agreement(
+        &self,
+        app_name: ApplicationName,
+        op: psa_raw_key_agreement::Operation,
+    ) -> Result<psa_raw_key_agreement::Result> {
+        info!("Pkcs11 Provider - Raw Key Agreement");
+
+        let key_name = op.private_key_name;
+        let alg = op.alg;
+        let peer_key = op.peer_key;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Pkcs11, key_name);
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let (key_id, key_attributes) = get_key_id(&key_triple, &*store_handle)?;
+
+        key_attributes.can_derive_from()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        // The shared secret is the x coordinate of the shared point.
+        let secret_len = ((key_attributes.key_bits + 7) / 8) as pkcs11::types::CK_ULONG;
+
+        let params = ecdh1_derive_params(&peer_key);
+        let params_ptr: *const CK_ECDH1_DERIVE_PARAMS = &params;
+        let mech = CK_MECHANISM {
+            mechanism: pkcs11::types::CKM_ECDH1_DERIVE,
+            pParameter: params_ptr as pkcs11::types::CK_VOID_PTR,
+            ulParameterLen: std::mem::size_of::<CK_ECDH1_DERIVE_PARAMS>(),
+        };
+
+        // The shared secret is derived as a temporary session object from which the value is
+        // read before destroying it.
+        let template = vec![
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_CLASS)
+                .with_ck_ulong(&pkcs11::types::CKO_SECRET_KEY),
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_KEY_TYPE)
+                .with_ck_ulong(&pkcs11::types::CKK_GENERIC_SECRET),
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_VALUE_LEN).with_ck_ulong(&secret_len),
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_TOKEN).with_bool(&pkcs11::types::CK_FALSE),
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_SENSITIVE).with_bool(&pkcs11::types::CK_FALSE),
+            CK_ATTRIBUTE::new(pkcs11::types::CKA_EXTRACTABLE).with_bool(&pkcs11::types::CK_TRUE),
+        ];
+
+        let session = Session::new(self, ReadWriteSession::ReadWrite)?;
+        info!("Raw key agreement in session {}", session.session_handle());
+
+        let key = self.find_key(session.session_handle(), &key_id, KeyPairType::PrivateKey)?;
+        info!("Located private key.");
+
+        let secret_key = self
+            .backend
+            .derive_key(session.session_handle(), &mech, key, &template)
+            .or_else(|e| {
+                error!("Failed to derive the shared secret. Error: {}", e);
+                Err(utils::to_response_status(e))
+            })?;
+
+        let mut shared_secret: Vec<pkcs11::types::CK_BYTE> = vec![0; secret_len];
+        let mut extract_attrs =
+            vec![CK_ATTRIBUTE::new(pkcs11::types::CKA_VALUE)
+                .with_bytes(shared_secret.as_mut_slice())];
+
+        let extract_result = match self.backend.get_attribute_value(
+            session.session_handle(),
+            secret_key,
+            &mut extract_attrs,
+        ) {
+            Ok((rv, attrs)) => {
+                if rv != CKR_OK {
+                    error!("Error when extracting attribute: {}.", rv);
+                    Err(utils::rv_to_response_status(rv))
+                } else {
+                    Ok(attrs[0].get_bytes())
+                }
+            }
+            Err(e) => {
+                error!("Failed to read the shared secret. Error: {}", e);
+                Err(utils::to_response_status(e))
+            }
+        };
+
+        if let Err(e) = self
+            .backend
+            .destroy_object(session.session_handle(), secret_key)
+        {
+            warn!("Failed to destroy the shared secret object. Error: {}", e);
+        }
+
+        let shared_secret = extract_result?;
+
+        session.mark_clean();
+        Ok(psa_raw_key_agreement::Result { shared_secret })
+    }
+
+    fn psa_generate_random(
+        &self,
+        op: psa_generate_random::Operation,
+    ) -> Result<psa_generate_random::Result> {
+        info!("Pkcs11 Provider - Generate Random");
+
+        let session = Session::new(self, ReadWriteSession::ReadOnly)?;
+        info!(
+            "Generating random bytes in session {}",
+            session.session_handle()
+        );
+
+        match self
+            .backend
+            .generate_random(session.session_handle(), op.size)
+        {
+            Ok(random_bytes) => {
+                session.mark_clean();
+                Ok(psa_generate_random::Result { random_bytes })
+            }
+            Err(e) => {
+                error!("Failed to generate random bytes. Error: {}", e);
                 Err(utils::to_response_status(e))
             }
         }
@@ -1029,6 +2741,78 @@ impl Drop for Pkcs11Provider {
     }
 }
 
+/// Converts a blank padded string field of a PKCS 11 structure.
+fn padded_field_to_string(field: &[u8]) -> String {
+    String::from_utf8_lossy(field).trim_end().to_string()
+}
+
+/// Finds the slot containing the token whose label and serial number match the ones given.
+///
+/// Only the fields given are compared. Exactly one token must match.
+fn find_slot(
+    backend: &Ctx,
+    token_label: Option<&str>,
+    serial_number: Option<&str>,
+) -> std::io::Result<CK_SLOT_ID> {
+    let slots = backend.get_slot_list(true).or_else(|e| {
+        error!("Error getting the list of PKCS 11 slots ({}).", e);
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            "error getting the list of PKCS 11 slots",
+        ))
+    })?;
+
+    let mut matching_slots = Vec::new();
+    for slot in slots {
+        let token_info = match backend.get_token_info(slot) {
+            Ok(token_info) => token_info,
+            Err(e) => {
+                warn!(
+                    "Error getting the information of the token in slot {} ({}), ignoring it.",
+                    slot, e
+                );
+                continue;
+            }
+        };
+        let label = padded_field_to_string(&token_info.label);
+        let serial = padded_field_to_string(&token_info.serialNumber);
+
+        if token_label.map_or(true, |token_label| token_label == label)
+            && serial_number.map_or(true, |serial_number| serial_number == serial)
+        {
+            info!(
+                "Found token \'{}\' with serial number \'{}\' in slot {}.",
+                label, serial, slot
+            );
+            matching_slots.push(slot);
+        }
+    }
+
+    match matching_slots.as_slice() {
+        [slot] => Ok(*slot),
+        [] => {
+            error!(
+                "No PKCS 11 token found with label {:?} and serial number {:?}.",
+                token_label, serial_number
+            );
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "no matching PKCS 11 token",
+            ))
+        }
+        _ => {
+            error!(
+                "Several PKCS 11 tokens found with label {:?} and serial number {:?} (in slots {:?}). Use a more specific configuration.",
+                token_label, serial_number, matching_slots
+            );
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                "several matching PKCS 11 tokens",
+            ))
+        }
+    }
+}
+
 /// Builder for Pkcs11Provider
 #[derive(Default, Derivative)]
 #[derivative(Debug)]
@@ -1037,7 +2821,11 @@ pub struct Pkcs11ProviderBuilder {
     key_id_store: Option<Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>>,
     pkcs11_library_path: Option<String>,
     slot_number: Option<usize>,
-    user_pin: Option<String>,
+    token_label: Option<String>,
+    serial_number: Option<String>,
+    user_pin: Option<Secret>,
+    session_pool_size: Option<usize>,
+    adopted_keys: Option<Vec<AdoptedKeyConfig>>,
 }
 
 impl Pkcs11ProviderBuilder {
@@ -1046,7 +2834,11 @@ impl Pkcs11ProviderBuilder {
             key_id_store: None,
             pkcs11_library_path: None,
             slot_number: None,
+            token_label: None,
+            serial_number: None,
             user_pin: None,
+            session_pool_size: None,
+            adopted_keys: None,
         }
     }
 
@@ -1068,18 +2860,48 @@ impl Pkcs11ProviderBuilder {
         self
     }
 
-    pub fn with_slot_number(mut self, slot_number: usize) -> Pkcs11ProviderBuilder {
-        self.slot_number = Some(slot_number);
+    pub fn with_slot_number(mut self, slot_number: Option<usize>) -> Pkcs11ProviderBuilder {
+        self.slot_number = slot_number;
+
+        self
+    }
+
+    pub fn with_token_label(mut self, token_label: Option<String>) -> Pkcs11ProviderBuilder {
+        self.token_label = token_label;
+
+        self
+    }
+
+    pub fn with_serial_number(mut self, serial_number: Option<String>) -> Pkcs11ProviderBuilder {
+        self.serial_number = serial_number;
 
         self
     }
 
-    pub fn with_user_pin(mut self, user_pin: Option<String>) -> Pkcs11ProviderBuilder {
+    pub fn with_user_pin(mut self, user_pin: Option<Secret>) -> Pkcs11ProviderBuilder {
         self.user_pin = user_pin;
 
         self
     }
 
+    pub fn with_session_pool_size(
+        mut self,
+        session_pool_size: Option<usize>,
+    ) -> Pkcs11ProviderBuilder {
+        self.session_pool_size = session_pool_size;
+
+        self
+    }
+
+    pub fn with_adopted_keys(
+        mut self,
+        adopted_keys: Option<Vec<AdoptedKeyConfig>>,
+    ) -> Pkcs11ProviderBuilder {
+        self.adopted_keys = adopted_keys;
+
+        self
+    }
+
     pub fn build(self) -> std::io::Result<Pkcs11Provider> {
         let library_path = self
             .pkcs11_library_path
@@ -1088,9 +2910,14 @@ impl Pkcs11ProviderBuilder {
             "Building a PKCS 11 provider with library \'{}\'",
             library_path
         );
-        let slot_number = self
-            .slot_number
-            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing slot number"))?;
+        let session_pool_size = self.session_pool_size.unwrap_or(DEFAULT_SESSION_POOL_SIZE);
+        if session_pool_size == 0 {
+            error!("The session pool of the PKCS 11 provider needs at least one session.");
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "invalid session pool size",
+            ));
+        }
         let mut backend = Ctx::new(library_path).or_else(|e| {
             error!("Error creating a PKCS 11 context ({}).", e);
             Err(Error::new(
@@ -1112,12 +2939,33 @@ impl Pkcs11ProviderBuilder {
                 "PKCS 11 backend initializing failed",
             ))
         })?;
+        let slot_number = match (self.slot_number, &self.token_label, &self.serial_number) {
+            (Some(slot_number), None, None) => slot_number,
+            (Some(_), _, _) => {
+                error!("The slot number can not be given with a token label or serial number.");
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "slot number given with token label or serial number",
+                ));
+            }
+            (None, None, None) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "missing slot number, token label or serial number",
+                ))
+            }
+            (None, token_label, serial_number) => {
+                find_slot(&backend, token_label.as_deref(), serial_number.as_deref())?
+            }
+        };
         Ok(Pkcs11Provider::new(
             self.key_id_store
                 .ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing key ID store"))?,
             backend,
             slot_number,
             self.user_pin,
+            session_pool_size,
+            self.adopted_keys.unwrap_or_default(),
         )
         .ok_or_else(|| Error::new(ErrorKind::InvalidData, "PKCS 11 initialization failed"))?)
     }
@@ -14,10 +14,40 @@
 // limitations under the License.
 
 use log::error;
-use parsec_interface::requests::ResponseStatus;
+use parsec_interface::operations::psa_key_attributes::{EccFamily, KeyAttributes, KeyType};
+use parsec_interface::requests::{ResponseStatus, Result};
+use picky_asn1::wrapper::OctetStringAsn1;
 use pkcs11::errors::Error;
 use pkcs11::types::*;
 
+// DER encodings of the OIDs of the NIST curves (named curves in the ECParameters choice).
+// secp192r1: 1.2.840.10045.3.1.1
+const SECP192R1_OID: [u8; 10] = [0x06, 0x08, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x01];
+// secp224r1: 1.3.132.0.33
+const SECP224R1_OID: [u8; 7] = [0x06, 0x05, 0x2B, 0x81, 0x04, 0x00, 0x21];
+// secp256r1: 1.2.840.10045.3.1.7
+const SECP256R1_OID: [u8; 10] = [0x06, 0x08, 0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
+// secp384r1: 1.3.132.0.34
+const SECP384R1_OID: [u8; 7] = [0x06, 0x05, 0x2B, 0x81, 0x04, 0x00, 0x22];
+// secp521r1: 1.3.132.0.35
+const SECP521R1_OID: [u8; 7] = [0x06, 0x05, 0x2B, 0x81, 0x04, 0x00, 0x23];
+
+// DER encodings of the OIDs of the other curves of the keys which can be adopted from a token.
+// secp256k1: 1.3.132.0.10
+const SECP256K1_OID: [u8; 7] = [0x06, 0x05, 0x2B, 0x81, 0x04, 0x00, 0x0A];
+// brainpoolP256r1: 1.3.36.3.3.2.8.1.1.7
+const BRAINPOOLP256R1_OID: [u8; 11] = [
+    0x06, 0x09, 0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x07,
+];
+// brainpoolP384r1: 1.3.36.3.3.2.8.1.1.11
+const BRAINPOOLP384R1_OID: [u8; 11] = [
+    0x06, 0x09, 0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0B,
+];
+// brainpoolP512r1: 1.3.36.3.3.2.8.1.1.13
+const BRAINPOOLP512R1_OID: [u8; 11] = [
+    0x06, 0x09, 0x2B, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0D,
+];
+
 /// Convert the PKCS 11 library specific error values to ResponseStatus values that are returned on
 /// the wire protocol
 ///
@@ -63,3 +93,101 @@ pub fn rv_to_response_status(rv: CK_RV) -> ResponseStatus {
         }
     }
 }
+
+/// Get the DER-encoded value of the `CKA_EC_PARAMS` attribute for the curve of the key.
+///
+/// # Errors
+///
+/// Only the NIST curves (SECP R1 family) are supported. Returns `PsaErrorNotSupported` otherwise.
+pub fn ec_params(key_attributes: KeyAttributes) -> Result<Vec<u8>> {
+    match key_attributes.key_type {
+        KeyType::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        }
+        | KeyType::EccPublicKey {
+            curve_family: EccFamily::SecpR1,
+        } => match key_attributes.key_bits {
+            192 => Ok(SECP192R1_OID.to_vec()),
+            224 => Ok(SECP224R1_OID.to_vec()),
+            256 => Ok(SECP256R1_OID.to_vec()),
+            384 => Ok(SECP384R1_OID.to_vec()),
+            521 => Ok(SECP521R1_OID.to_vec()),
+            bits => {
+                error!("The SECP R1 curve with {} bits is not supported.", bits);
+                Err(ResponseStatus::PsaErrorNotSupported)
+            }
+        },
+        _ => {
+            error!("The PKCS 11 provider only supports curves of the SECP R1 family.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+/// Get the curve family and the size in bits of the curve whose OID is the DER-encoded value of a
+/// `CKA_EC_PARAMS` attribute.
+///
+/// # Errors
+///
+/// Only the NIST curves (SECP R1 family), secp256k1 and the Brainpool curves of the P R1 family
+/// with 256, 384 and 512 bits are recognized. Returns `PsaErrorNotSupported` otherwise, for
+/// example for explicit curve parameters.
+pub fn ec_params_to_curve(ec_params: &[u8]) -> Result<(EccFamily, u32)> {
+    let curves: [(&[u8], EccFamily, u32); 9] = [
+        (&SECP192R1_OID, EccFamily::SecpR1, 192),
+        (&SECP224R1_OID, EccFamily::SecpR1, 224),
+        (&SECP256R1_OID, EccFamily::SecpR1, 256),
+        (&SECP384R1_OID, EccFamily::SecpR1, 384),
+        (&SECP521R1_OID, EccFamily::SecpR1, 521),
+        (&SECP256K1_OID, EccFamily::SecpK1, 256),
+        (&BRAINPOOLP256R1_OID, EccFamily::BrainpoolPR1, 256),
+        (&BRAINPOOLP384R1_OID, EccFamily::BrainpoolPR1, 384),
+        (&BRAINPOOLP512R1_OID, EccFamily::BrainpoolPR1, 512),
+    ];
+
+    curves
+        .iter()
+        .find(|(oid, _, _)| *oid == ec_params)
+        .map(|(_, curve_family, bits)| (*curve_family, *bits))
+        .ok_or_else(|| {
+            error!("The curve of the key is not supported by the PKCS 11 provider.");
+            ResponseStatus::PsaErrorNotSupported
+        })
+}
+
+/// Parse a string of hexadecimal digits, as used in the configuration for key identifiers.
+pub fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() || hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect()
+}
+
+/// Convert an uncompressed Elliptic Curve point, as given by PSA, to the DER-encoded
+/// `OCTET STRING` value of the `CKA_EC_POINT` attribute.
+pub fn ec_point_to_der(ec_point: Vec<u8>) -> Result<Vec<u8>> {
+    if ec_point.first() != Some(&0x04) {
+        error!("Elliptic Curve public keys must be given as an uncompressed point.");
+        return Err(ResponseStatus::PsaErrorInvalidArgument);
+    }
+    picky_asn1_der::to_vec(&OctetStringAsn1(ec_point)).or_else(|e| {
+        error!("Could not serialise the EC point ({}).", e);
+        Err(ResponseStatus::PsaErrorInvalidArgument)
+    })
+}
+
+/// Convert the DER-encoded value of the `CKA_EC_POINT` attribute to the uncompressed point
+/// format used by PSA.
+pub fn der_to_ec_point(der: &[u8]) -> Result<Vec<u8>> {
+    let ec_point: OctetStringAsn1 = picky_asn1_der::from_bytes(der).or_else(|e| {
+        error!("Failed to parse the EC point ({}).", e);
+        Err(ResponseStatus::PsaErrorCommunicationFailure)
+    })?;
+    Ok(ec_point.0)
+}
@@ -16,38 +16,56 @@
 //!
 //! Provider allowing clients to use hardware or software TPM 2.0 implementations
 //! for their Parsec operations.
-use super::Provide;
+use super::{check_copy_policy, signature_hash_alg, Provide};
 use crate::authenticators::ApplicationName;
 use crate::key_id_managers;
 use crate::key_id_managers::{KeyInfo, KeyTriple, ManageKeyIDs};
+use crate::utils::Secret;
 use derivative::Derivative;
-use log::{error, info};
+use log::{error, info, warn};
 use parsec_interface::operations::list_providers::ProviderInfo;
-use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_algorithm::{Algorithm, AsymmetricSignature, Hash};
 use parsec_interface::operations::psa_key_attributes::*;
 use parsec_interface::operations::{
-    list_opcodes, psa_destroy_key, psa_export_public_key, psa_generate_key, psa_import_key,
-    psa_sign_hash, psa_verify_hash,
+    attest_key, list_opcodes, psa_asymmetric_decrypt, psa_asymmetric_encrypt, psa_copy_key,
+    psa_destroy_key, psa_export_key, psa_export_public_key, psa_generate_key, psa_generate_random,
+    psa_import_key, psa_mac_compute, psa_mac_verify, psa_raw_key_agreement, psa_sign_hash,
+    psa_sign_message, psa_verify_hash, psa_verify_message, seal_data, unseal_data,
 };
 use parsec_interface::requests::{Opcode, ProviderID, ResponseStatus, Result};
-use picky_asn1::wrapper::IntegerAsn1;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::io::ErrorKind;
 use std::sync::{Arc, Mutex, RwLock};
-use tss_esapi::{
-    constants::TPM2_ALG_SHA256, utils::AsymSchemeUnion, utils::Signature, utils::TpmsContext, Tcti,
-};
+use tss_esapi::response_code::Tss2ResponseCodeKind;
+use tss_esapi::tss2_esys::TPM2_HANDLE;
+use tss_esapi::{utils::TpmsContext, Tcti, TransientObjectContext};
 use uuid::Uuid;
 
 mod utils;
 
-const SUPPORTED_OPCODES: [Opcode; 7] = [
+const SUPPORTED_OPCODES: [Opcode; 21] = [
     Opcode::PsaGenerateKey,
     Opcode::PsaDestroyKey,
     Opcode::PsaSignHash,
     Opcode::PsaVerifyHash,
     Opcode::PsaImportKey,
     Opcode::PsaExportPublicKey,
+    Opcode::PsaExportKey,
+    Opcode::PsaAsymmetricEncrypt,
+    Opcode::PsaAsymmetricDecrypt,
+    Opcode::PsaMacCompute,
+    Opcode::PsaMacVerify,
+    Opcode::PsaRawKeyAgreement,
+    Opcode::PsaGenerateRandom,
+    Opcode::PsaGetKeyAttributes,
+    Opcode::PsaCopyKey,
+    Opcode::PsaSignMessage,
+    Opcode::PsaVerifyMessage,
+    Opcode::AttestKey,
+    Opcode::SealData,
+    Opcode::UnsealData,
     Opcode::ListOpcodes,
 ];
 
@@ -67,45 +85,169 @@ pub struct TpmProvider {
     // structure that is shared between threads and because two threads are not allowed the same
     // ESAPI context simultaneously.
     esapi_context: Mutex<tss_esapi::TransientObjectContext>,
-    // The Key ID Manager stores the key context and its associated authValue (a PasswordContext
-    // structure).
+    // The Key ID Manager stores a StoredKey for each key: the key context and its associated
+    // authValue or, for persistent keys, the persistent handle of the key and its authValue.
     #[derivative(Debug = "ignore")]
     key_id_store: Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>,
+    // Persistent handles used by the keys of the provider, when keys are made persistent instead
+    // of being stored as saved contexts. The Mutex is always locked after the ESAPI Context one.
+    persistent_handles: Option<Mutex<HashSet<TPM2_HANDLE>>>,
 }
 
-// Public exponent value for all RSA keys.
-const PUBLIC_EXPONENT: [u8; 3] = [0x01, 0x00, 0x01];
 const AUTH_VAL_LEN: usize = 32;
+// Maximum size of the data that can be given to the TPM2_HMAC command (MAX_DIGEST_BUFFER).
+const MAX_HMAC_INPUT_LEN: usize = 1024;
+// Number of random bytes requested to the TPM2_GetRandom command at once. The TPM returns at most
+// the size of its largest digest for each call.
+const GET_RANDOM_CHUNK_LEN: usize = 32;
+
+// Range of persistent handles of the owner hierarchy in which keys are made persistent.
+const PERSISTENT_HANDLE_FIRST: TPM2_HANDLE = 0x8102_0000;
+const PERSISTENT_HANDLE_COUNT: TPM2_HANDLE = 0x100;
+
+// Name of the attestation key in the Key ID Manager. It is stored in the namespace of the keys used
+// internally by the service so that no client can read, replace or destroy it.
+const ATTESTATION_KEY_NAME: &str = "tpm-attestation-key";
+// Size of the attestation key and maximum size of the nonce given by the caller (the size of the
+// largest digest supported).
+const ATTESTATION_KEY_SIZE: u32 = 2048;
+const MAX_NONCE_LEN: usize = 64;
+// Number of PCRs of the SHA-256 bank that can be quoted or used in a policy.
+const PCR_COUNT: u8 = 24;
+// Maximum size of the data that can be sealed in a keyed-hash object (MAX_SYM_DATA).
+const MAX_SEALED_DATA_LEN: usize = 128;
+
+// Saved context of a key object and its authValue.
+#[derive(Serialize, Deserialize)]
+struct PasswordContext {
+    context: TpmsContext,
+    auth_value: Vec<u8>,
+}
 
-// The RSA Public Key data are DER encoded with the following representation:
-// RSAPublicKey ::= SEQUENCE {
-//     modulus            INTEGER,  -- n
-//     publicExponent     INTEGER   -- e
-// }
-#[derive(Serialize, Deserialize, Debug)]
-struct RsaPublicKey {
-    modulus: IntegerAsn1,
-    public_exponent: IntegerAsn1,
+// Persistent handle of a key object made persistent in the TPM and its authValue.
+#[derive(Serialize, Deserialize)]
+struct PersistentPasswordContext {
+    persistent_handle: TPM2_HANDLE,
+    auth_value: Vec<u8>,
 }
 
-// The PasswordContext is what is stored by the Key ID Manager.
+// Saved context of a sealed data object, its authValue and the PCR selection its policy is bound
+// to, if any.
 #[derive(Serialize, Deserialize)]
-struct PasswordContext {
+struct SealedDataContext {
     context: TpmsContext,
     auth_value: Vec<u8>,
+    pcr_selection: Option<Vec<u8>>,
+}
+
+// What is stored by the Key ID Manager for each key. The variant tells how the key object is kept,
+// whatever the current configuration of the provider.
+#[derive(Serialize, Deserialize)]
+enum StoredKey {
+    // The saved context of the key object is loaded in the TPM for each operation.
+    Transient(PasswordContext),
+    // The key object is persistent in the TPM.
+    Persistent(PersistentPasswordContext),
+    // The saved context of a sealed data object, which can only be used by UnsealData.
+    Sealed(SealedDataContext),
+}
+
+fn is_public_key(key_attributes: KeyAttributes) -> bool {
+    match key_attributes.key_type {
+        KeyType::RsaPublicKey | KeyType::EccPublicKey { .. } | KeyType::DhPublicKey { .. } => true,
+        _ => false,
+    }
+}
+
+// Public keys are loaded in the NULL hierarchy and can not be made persistent, they are always
+// stored as saved contexts. Sealed data objects are always stored as saved contexts, with their
+// PCR selection.
+fn can_be_persistent(key_attributes: KeyAttributes) -> bool {
+    !is_public_key(key_attributes) && key_attributes.key_type != KeyType::RawData
+}
+
+// Attributes stored in the Key ID Manager for the attestation key. The key is an RSA restricted
+// signing key which can only sign data produced by the TPM itself.
+fn attestation_key_attributes() -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: ATTESTATION_KEY_SIZE,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: true,
+                sign_message: false,
+                verify_message: true,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPkcs1v15Sign {
+                hash_alg: Hash::Sha256,
+            }),
+        },
+    }
+}
+
+// Attributes stored in the Key ID Manager for the sealed data objects. The data can only be read
+// back with the UnsealData operation.
+fn sealed_data_attributes(data_len: usize) -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::RawData,
+        key_bits: u32::try_from(data_len * 8).expect("Conversion to u32 failed."),
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::None,
+        },
+    }
+}
+
+fn attestation_key_triple() -> KeyTriple {
+    KeyTriple::new_internal(ProviderID::Tpm, String::from(ATTESTATION_KEY_NAME))
+}
+
+// Checks that a PCR selection is not empty and only contains indexes of existing PCRs.
+fn check_pcr_selection(pcr_selection: &[u8]) -> Result<()> {
+    if pcr_selection.is_empty() || pcr_selection.iter().any(|pcr| *pcr >= PCR_COUNT) {
+        error!(
+            "The PCR selection must contain PCR indexes smaller than {}.",
+            PCR_COUNT
+        );
+        Err(ResponseStatus::PsaErrorInvalidArgument)
+    } else {
+        Ok(())
+    }
+}
+
+fn is_persistent_handle(handle: TPM2_HANDLE) -> bool {
+    handle >= PERSISTENT_HANDLE_FIRST && handle - PERSISTENT_HANDLE_FIRST < PERSISTENT_HANDLE_COUNT
 }
 
-// Inserts a new mapping in the Key ID manager that stores the PasswordContext.
-fn insert_password_context(
+// Inserts a new mapping in the Key ID manager that stores the saved context of a key.
+fn insert_stored_key(
     store_handle: &mut dyn ManageKeyIDs,
     key_triple: KeyTriple,
-    password_context: PasswordContext,
+    stored_key: StoredKey,
     key_attributes: KeyAttributes,
 ) -> Result<()> {
     let error_storing = |e| Err(key_id_managers::to_response_status(e));
 
     let key_info = KeyInfo {
-        id: bincode::serialize(&password_context)?,
+        id: bincode::serialize(&stored_key)?,
         attributes: key_attributes,
     };
 
@@ -121,13 +263,10 @@ fn insert_password_context(
     }
 }
 
-// Gets a PasswordContext mapping to the KeyTriple given.
-fn get_password_context(
-    store_handle: &dyn ManageKeyIDs,
-    key_triple: KeyTriple,
-) -> Result<(PasswordContext, KeyAttributes)> {
-    let key_info = store_handle
-        .get(&key_triple)
+// Gets the KeyInfo mapping to the KeyTriple given.
+fn get_key_info(store_handle: &dyn ManageKeyIDs, key_triple: &KeyTriple) -> Result<KeyInfo> {
+    Ok(store_handle
+        .get(key_triple)
         .or_else(|e| Err(key_id_managers::to_response_status(e)))?
         .ok_or_else(|| {
             error!(
@@ -135,21 +274,326 @@ fn get_password_context(
                 key_triple
             );
             ResponseStatus::PsaErrorDoesNotExist
+        })?
+        .clone())
+}
+
+// Reads the StoredKey of a KeyInfo.
+//
+// Keys stored by the previous versions of the provider, before persistent keys were added, are
+// stored as a bare PasswordContext and read as transient keys. As the bincode format does not tell
+// the types apart, the whole data needs to be read for it to be valid.
+fn get_stored_key(key_info: &KeyInfo) -> Result<StoredKey> {
+    let whole_data_read = |size: bincode::Result<u64>| match size {
+        Ok(size) => size == key_info.id.len() as u64,
+        Err(_) => false,
+    };
+
+    match bincode::deserialize::<StoredKey>(&key_info.id) {
+        Ok(stored_key) if whole_data_read(bincode::serialized_size(&stored_key)) => {
+            if let StoredKey::Persistent(persistent_password_context) = &stored_key {
+                if !is_persistent_handle(persistent_password_context.persistent_handle) {
+                    error!(
+                        "The persistent handle {:#x} stored is not in the range of the provider.",
+                        persistent_password_context.persistent_handle
+                    );
+                    return Err(ResponseStatus::KeyIDManagerError);
+                }
+            }
+            return Ok(stored_key);
+        }
+        _ => (),
+    }
+
+    match bincode::deserialize::<PasswordContext>(&key_info.id) {
+        Ok(password_context) if whole_data_read(bincode::serialized_size(&password_context)) => {
+            Ok(StoredKey::Transient(password_context))
+        }
+        _ => {
+            error!("The data stored for the key is not valid.");
+            Err(ResponseStatus::KeyIDManagerError)
+        }
+    }
+}
+
+// Signs a hash with the key of the context given. The usage policy of the key needs to be checked
+// by the caller.
+fn sign_hash(
+    esapi_context: &mut TransientObjectContext,
+    password_context: PasswordContext,
+    key_attributes: KeyAttributes,
+    alg: AsymmetricSignature,
+    hash: &[u8],
+) -> Result<Vec<u8>> {
+    // Checks that the algorithm can be used by the TPM.
+    let _ = utils::convert_asym_scheme_to_tpm(alg.into())?;
+    utils::check_digest_size(alg, hash)?;
+
+    let signature = esapi_context
+        .sign(password_context.context, &password_context.auth_value, hash)
+        .or_else(|e| {
+            error!("Error signing: {}.", e);
+            Err(utils::to_response_status(e))
         })?;
-    Ok((bincode::deserialize(&key_info.id)?, key_info.attributes))
+
+    utils::signature_data_to_bytes(signature.signature, key_attributes)
+}
+
+// Verifies the signature of a hash with the key of the context given. The usage policy of the key
+// needs to be checked by the caller.
+fn verify_hash(
+    esapi_context: &mut TransientObjectContext,
+    password_context: PasswordContext,
+    key_attributes: KeyAttributes,
+    alg: AsymmetricSignature,
+    hash: &[u8],
+    signature: Vec<u8>,
+) -> Result<()> {
+    utils::check_digest_size(alg, hash)?;
+    let signature = utils::parsec_to_tpm_signature(signature, key_attributes, alg)?;
+
+    let _ = esapi_context
+        .verify_signature(password_context.context, hash, signature)
+        .or_else(|e| Err(utils::to_response_status(e)))?;
+
+    Ok(())
 }
 
 impl TpmProvider {
     // Creates and initialise a new instance of TpmProvider.
+    // When persistent keys are enabled, the persistent handles of the keys in the Key ID Manager,
+    // including the attestation key, are marked as used.
     fn new(
         key_id_store: Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>,
         esapi_context: tss_esapi::TransientObjectContext,
+        persistent_keys: bool,
     ) -> Option<TpmProvider> {
+        let persistent_handles = if persistent_keys {
+            let store_handle = key_id_store.read().expect("Key store lock poisoned");
+            let mut persistent_handles = HashSet::new();
+            let key_triples = match store_handle.get_all(ProviderID::Tpm) {
+                Ok(key_triples) => key_triples,
+                Err(string) => {
+                    error!("Key ID Manager error: {}", string);
+                    return None;
+                }
+            };
+            for key_triple in key_triples {
+                let stored_key = match get_key_info(&*store_handle, key_triple)
+                    .and_then(|key_info| get_stored_key(&key_info))
+                {
+                    Ok(stored_key) => stored_key,
+                    Err(e) => {
+                        warn!(
+                            "Key {} can not be read ({}), skipping it. It can not be used until it is destroyed.",
+                            key_triple, e
+                        );
+                        continue;
+                    }
+                };
+                if let StoredKey::Persistent(persistent_password_context) = stored_key {
+                    let _ =
+                        persistent_handles.insert(persistent_password_context.persistent_handle);
+                }
+            }
+            Some(Mutex::new(persistent_handles))
+        } else {
+            None
+        };
+
         Some(TpmProvider {
             esapi_context: Mutex::new(esapi_context),
             key_id_store,
+            persistent_handles,
         })
     }
+
+    // Gets the PasswordContext of the key mapping to the KeyTriple given. For persistent keys,
+    // the context is read from the persistent handle.
+    fn get_password_context(
+        &self,
+        esapi_context: &mut TransientObjectContext,
+        store_handle: &dyn ManageKeyIDs,
+        key_triple: KeyTriple,
+    ) -> Result<(PasswordContext, KeyAttributes)> {
+        let key_info = get_key_info(store_handle, &key_triple)?;
+
+        match get_stored_key(&key_info)? {
+            StoredKey::Transient(password_context) => Ok((password_context, key_info.attributes)),
+            StoredKey::Sealed(sealed_data_context) => Ok((
+                PasswordContext {
+                    context: sealed_data_context.context,
+                    auth_value: sealed_data_context.auth_value,
+                },
+                key_info.attributes,
+            )),
+            StoredKey::Persistent(persistent_password_context) => {
+                let context = esapi_context
+                    .load_persistent(persistent_password_context.persistent_handle)
+                    .or_else(|e| {
+                        error!(
+                            "Error reading the persistent key at handle {:#x}: {}.",
+                            persistent_password_context.persistent_handle, e
+                        );
+                        Err(utils::to_response_status(e))
+                    })?;
+                Ok((
+                    PasswordContext {
+                        context,
+                        auth_value: persistent_password_context.auth_value,
+                    },
+                    key_info.attributes,
+                ))
+            }
+        }
+    }
+
+    // Inserts a new key in the Key ID Manager. If persistent keys are enabled, the key object is
+    // first made persistent at a free persistent handle.
+    fn insert_key(
+        &self,
+        esapi_context: &mut TransientObjectContext,
+        store_handle: &mut dyn ManageKeyIDs,
+        key_triple: KeyTriple,
+        password_context: PasswordContext,
+        key_attributes: KeyAttributes,
+    ) -> Result<()> {
+        let persistent_handles = match &self.persistent_handles {
+            Some(persistent_handles) if can_be_persistent(key_attributes) => persistent_handles,
+            _ => {
+                return insert_stored_key(
+                    store_handle,
+                    key_triple,
+                    StoredKey::Transient(password_context),
+                    key_attributes,
+                )
+            }
+        };
+
+        if store_handle
+            .exists(&key_triple)
+            .or_else(|e| Err(key_id_managers::to_response_status(e)))?
+        {
+            error!(
+                "Inserting a mapping in the Key ID Manager that would overwrite an existing one."
+            );
+            return Err(ResponseStatus::PsaErrorAlreadyExists);
+        }
+
+        let mut persistent_handles = persistent_handles
+            .lock()
+            .expect("Persistent handles lock poisoned");
+        let persistent_handle = self.make_persistent(
+            esapi_context,
+            &mut persistent_handles,
+            password_context.context,
+        )?;
+
+        let key_info = KeyInfo {
+            id: bincode::serialize(&StoredKey::Persistent(PersistentPasswordContext {
+                persistent_handle,
+                auth_value: password_context.auth_value,
+            }))?,
+            attributes: key_attributes,
+        };
+        if let Err(e) = store_handle.insert(key_triple, key_info) {
+            // The key is removed from the TPM as it can not be used without its mapping.
+            if let Err(e) = esapi_context.evict_persistent(persistent_handle) {
+                error!(
+                    "Error evicting the persistent key at handle {:#x}: {}.",
+                    persistent_handle, e
+                );
+            }
+            let _ = persistent_handles.remove(&persistent_handle);
+            return Err(key_id_managers::to_response_status(e));
+        }
+
+        Ok(())
+    }
+
+    // Makes the key object persistent at the first persistent handle of the range which is free,
+    // both for the provider and in the TPM.
+    fn make_persistent(
+        &self,
+        esapi_context: &mut TransientObjectContext,
+        persistent_handles: &mut HashSet<TPM2_HANDLE>,
+        context: TpmsContext,
+    ) -> Result<TPM2_HANDLE> {
+        for persistent_handle in
+            PERSISTENT_HANDLE_FIRST..PERSISTENT_HANDLE_FIRST + PERSISTENT_HANDLE_COUNT
+        {
+            if persistent_handles.contains(&persistent_handle) {
+                continue;
+            }
+            match esapi_context.make_persistent(context.clone(), persistent_handle) {
+                Ok(()) => {
+                    let _ = persistent_handles.insert(persistent_handle);
+                    return Ok(persistent_handle);
+                }
+                Err(e) => {
+                    if let Some(Tss2ResponseCodeKind::NvDefined) = utils::error_kind(&e) {
+                        warn!(
+                            "Persistent handle {:#x} is used by another application, skipping it.",
+                            persistent_handle
+                        );
+                        let _ = persistent_handles.insert(persistent_handle);
+                    } else {
+                        error!("Error making a key persistent: {}.", e);
+                        return Err(utils::to_response_status(e));
+                    }
+                }
+            }
+        }
+
+        error!(
+            "All the {} persistent handles starting at {:#x} are in use.",
+            PERSISTENT_HANDLE_COUNT, PERSISTENT_HANDLE_FIRST
+        );
+        Err(ResponseStatus::PsaErrorInsufficientStorage)
+    }
+
+    // Gets the PasswordContext of the attestation key. The key is created and stored in the Key
+    // ID Manager the first time it is needed. The mapping of an existing key is checked to be the
+    // one of a restricted signing key created by the provider.
+    fn get_attestation_key(
+        &self,
+        esapi_context: &mut TransientObjectContext,
+        store_handle: &mut dyn ManageKeyIDs,
+    ) -> Result<PasswordContext> {
+        let key_triple = attestation_key_triple();
+
+        if !store_handle
+            .exists(&key_triple)
+            .or_else(|e| Err(key_id_managers::to_response_status(e)))?
+        {
+            info!("Creating the attestation key of the TPM provider.");
+            let (key_context, auth_value) = esapi_context
+                .create_attestation_key(AUTH_VAL_LEN)
+                .or_else(|e| {
+                    error!("Error creating the attestation key: {}.", e);
+                    Err(utils::to_response_status(e))
+                })?;
+
+            self.insert_key(
+                esapi_context,
+                store_handle,
+                key_triple.clone(),
+                PasswordContext {
+                    context: key_context,
+                    auth_value,
+                },
+                attestation_key_attributes(),
+            )?;
+        }
+
+        let (password_context, key_attributes) =
+            self.get_password_context(esapi_context, &*store_handle, key_triple)?;
+        if key_attributes != attestation_key_attributes() {
+            error!("The attestation key stored in the Key ID Manager was not created by the TPM provider.");
+            return Err(ResponseStatus::KeyIDManagerError);
+        }
+        Ok(password_context)
+    }
 }
 
 impl Provide for TpmProvider {
@@ -172,22 +616,27 @@ impl Provide for TpmProvider {
         })
     }
 
+    fn key_id_store(&self) -> Option<&Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>> {
+        Some(&self.key_id_store)
+    }
+
     fn psa_generate_key(
         &self,
         app_name: ApplicationName,
         op: psa_generate_key::Operation,
     ) -> Result<psa_generate_key::Result> {
-        if op.attributes.key_type != KeyType::RsaKeyPair {
-            error!("The TPM provider currently only supports creating RSA key pairs.");
-            return Err(ResponseStatus::PsaErrorNotSupported);
+        match op.attributes.key_type {
+            KeyType::RsaKeyPair | KeyType::EccKeyPair { .. } | KeyType::Hmac => (),
+            _ => {
+                error!("The TPM provider currently only supports creating RSA and Elliptic Curve key pairs and HMAC keys.");
+                return Err(ResponseStatus::PsaErrorNotSupported);
+            }
         }
 
         let key_name = op.key_name;
         let attributes = op.attributes;
         let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
-        // This should never panic on 32 bits or more machines.
-        let key_size = std::convert::TryFrom::try_from(op.attributes.key_bits)
-            .expect("Conversion to usize failed.");
+        let key_params = utils::parsec_to_tpm_params(attributes)?;
 
         let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
         let mut esapi_context = self
@@ -195,14 +644,16 @@ impl Provide for TpmProvider {
             .lock()
             .expect("ESAPI Context lock poisoned");
 
+        // The key template (signing or decryption key) is derived from the key parameters.
         let (key_context, auth_value) = esapi_context
-            .create_rsa_signing_key(key_size, AUTH_VAL_LEN)
+            .create_key(key_params, AUTH_VAL_LEN)
             .or_else(|e| {
-                error!("Error creating a RSA signing key: {}.", e);
+                error!("Error creating a key: {}.", e);
                 Err(utils::to_response_status(e))
             })?;
 
-        insert_password_context(
+        self.insert_key(
+            &mut esapi_context,
             &mut *store_handle,
             key_triple,
             PasswordContext {
@@ -220,15 +671,18 @@ impl Provide for TpmProvider {
         app_name: ApplicationName,
         op: psa_import_key::Operation,
     ) -> Result<psa_import_key::Result> {
-        if op.attributes.key_type != KeyType::RsaPublicKey {
-            error!("The TPM provider currently only supports importing RSA public key.");
-            return Err(ResponseStatus::PsaErrorNotSupported);
+        match op.attributes.key_type {
+            KeyType::RsaKeyPair | KeyType::RsaPublicKey | KeyType::EccPublicKey { .. } => (),
+            _ => {
+                error!("The TPM provider currently only supports importing RSA key pairs and RSA and Elliptic Curve public keys.");
+                return Err(ResponseStatus::PsaErrorNotSupported);
+            }
         }
 
         let key_name = op.key_name;
         let attributes = op.attributes;
         let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
-        let key_data = op.data;
+        let key_params = utils::parsec_to_tpm_params(attributes)?;
 
         let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
         let mut esapi_context = self
@@ -236,45 +690,43 @@ impl Provide for TpmProvider {
             .lock()
             .expect("ESAPI Context lock poisoned");
 
-        let public_key: RsaPublicKey = picky_asn1_der::from_bytes(&key_data).or_else(|err| {
-            error!("Could not deserialise key elements: {}.", err);
-            Err(ResponseStatus::PsaErrorInvalidArgument)
-        })?;
-
-        if public_key.modulus.is_negative() || public_key.public_exponent.is_negative() {
-            error!("Only positive modulus and public exponent are supported.");
-            return Err(ResponseStatus::PsaErrorInvalidArgument);
-        }
+        let password_context = if let KeyType::RsaKeyPair = attributes.key_type {
+            let (public_key, private_key) = utils::bytes_to_rsa_key_pair(op.data, attributes)?;
 
-        if public_key.public_exponent.as_unsigned_bytes_be() != PUBLIC_EXPONENT {
-            error!("The TPM Provider only supports 0x101 as public exponent for RSA public keys, {:?} given.", public_key.public_exponent.as_unsigned_bytes_be());
-            return Err(ResponseStatus::PsaErrorNotSupported);
-        }
-        let key_data = public_key.modulus.as_unsigned_bytes_be();
+            // The key pair is imported under the Storage Root Key, as generated keys are, and
+            // protected by a new random auth value.
+            let (key_context, auth_value) = esapi_context
+                .import_key(key_params, public_key, &private_key, AUTH_VAL_LEN)
+                .or_else(|e| {
+                    error!("Error importing a key pair: {}.", e);
+                    Err(utils::to_response_status(e))
+                })?;
 
-        let len = key_data.len();
-        if len != 128 && len != 256 {
-            error!(
-                "The TPM provider only supports 1024 and 2048 bits RSA public keys ({} bits given).",
-                len * 8
-            );
-            return Err(ResponseStatus::PsaErrorNotSupported);
-        }
+            PasswordContext {
+                context: key_context,
+                auth_value,
+            }
+        } else {
+            let public_key = utils::bytes_to_pub_key(op.data, attributes)?;
 
-        let pub_key_context = esapi_context
-            .load_external_rsa_public_key(&key_data)
-            .or_else(|e| {
-                error!("Error creating a RSA signing key: {}.", e);
-                Err(utils::to_response_status(e))
-            })?;
+            let pub_key_context = esapi_context
+                .load_external_public_key(public_key, key_params)
+                .or_else(|e| {
+                    error!("Error loading an external public key: {}.", e);
+                    Err(utils::to_response_status(e))
+                })?;
 
-        insert_password_context(
-            &mut *store_handle,
-            key_triple,
             PasswordContext {
                 context: pub_key_context,
                 auth_value: Vec::new(),
-            },
+            }
+        };
+
+        self.insert_key(
+            &mut esapi_context,
+            &mut *store_handle,
+            key_triple,
+            password_context,
             attributes,
         )?;
 
@@ -295,7 +747,8 @@ impl Provide for TpmProvider {
             .lock()
             .expect("ESAPI Context lock poisoned");
 
-        let (password_context, key_attributes) = get_password_context(&*store_handle, key_triple)?;
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
 
         key_attributes.can_export()?;
 
@@ -306,18 +759,50 @@ impl Provide for TpmProvider {
                 Err(utils::to_response_status(e))
             })?;
 
-        let key = RsaPublicKey {
-            // To produce a valid ASN.1 RSAPublicKey structure, 0x00 is put in front of the positive
-            // modulus if highest significant bit is one, to differentiate it from a negative number.
-            modulus: IntegerAsn1::from_unsigned_bytes_be(pub_key_data),
-            public_exponent: IntegerAsn1::from_signed_bytes_be(PUBLIC_EXPONENT.to_vec()),
-        };
-        let key_data = picky_asn1_der::to_vec(&key).or_else(|err| {
-            error!("Could not serialise key elements: {}.", err);
-            Err(ResponseStatus::PsaErrorCommunicationFailure)
-        })?;
+        Ok(psa_export_public_key::Result {
+            data: utils::pub_key_to_bytes(pub_key_data, key_attributes)?,
+        })
+    }
+
+    fn psa_export_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_export_key::Operation,
+    ) -> Result<psa_export_key::Result> {
+        let key_name = op.key_name;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
 
-        Ok(psa_export_public_key::Result { data: key_data })
+        key_attributes.can_export()?;
+
+        match key_attributes.key_type {
+            KeyType::RsaPublicKey | KeyType::EccPublicKey { .. } => {
+                let pub_key_data = esapi_context
+                    .read_public_key(password_context.context)
+                    .or_else(|e| {
+                        error!("Error reading a public key: {}.", e);
+                        Err(utils::to_response_status(e))
+                    })?;
+
+                Ok(psa_export_key::Result {
+                    data: utils::pub_key_to_bytes(pub_key_data, key_attributes)?,
+                })
+            }
+            _ => {
+                // Keys are created under the storage hierarchy without a duplication policy:
+                // their sensitive part can never leave the TPM.
+                error!("The key can not be exported as it has no duplication policy.");
+                Err(ResponseStatus::PsaErrorNotPermitted)
+            }
+        }
     }
 
     fn psa_destroy_key(
@@ -329,20 +814,55 @@ impl Provide for TpmProvider {
         let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
         let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
 
-        let error_closure = |e| Err(key_id_managers::to_response_status(e));
-        if store_handle
+        let key_info = get_key_info(&*store_handle, &key_triple)?;
+        // A key whose data can not be read can still be destroyed: only its mapping is removed.
+        let stored_key = get_stored_key(&key_info).ok();
+
+        // The mapping is removed before the persistent object is evicted. If evicting fails, the
+        // mapping is put back so that the key stays usable and destroying it can be tried again.
+        let _ = store_handle
             .remove(&key_triple)
-            .or_else(error_closure)?
-            .is_none()
-        {
-            error!(
-                "Key triple \"{}\" does not exist in the Key ID Manager.",
-                key_triple
-            );
-            Err(ResponseStatus::PsaErrorDoesNotExist)
-        } else {
-            Ok(psa_destroy_key::Result {})
+            .or_else(|e| Err(key_id_managers::to_response_status(e)))?;
+
+        if let Some(StoredKey::Persistent(persistent_password_context)) = stored_key {
+            let persistent_handle = persistent_password_context.persistent_handle;
+            let mut esapi_context = self
+                .esapi_context
+                .lock()
+                .expect("ESAPI Context lock poisoned");
+
+            if let Err(e) = esapi_context.evict_persistent(persistent_handle) {
+                if let Some(Tss2ResponseCodeKind::Handle) = utils::error_kind(&e) {
+                    // The key object is not in the TPM anymore, for example after it was
+                    // cleared.
+                    warn!(
+                        "No persistent key at handle {:#x}, removing the mapping only.",
+                        persistent_handle
+                    );
+                } else {
+                    error!(
+                        "Error evicting the persistent key at handle {:#x}: {}.",
+                        persistent_handle, e
+                    );
+                    if let Err(string) = store_handle.insert(key_triple, key_info) {
+                        error!(
+                            "Key ID Manager error: {}, the persistent key at handle {:#x} is not mapped to any key anymore.",
+                            string, persistent_handle
+                        );
+                    }
+                    return Err(utils::to_response_status(e));
+                }
+            }
+
+            if let Some(persistent_handles) = &self.persistent_handles {
+                let _ = persistent_handles
+                    .lock()
+                    .expect("Persistent handles lock poisoned")
+                    .remove(&persistent_handle);
+            }
         }
+
+        Ok(psa_destroy_key::Result {})
     }
 
     fn psa_sign_hash(
@@ -367,36 +887,21 @@ impl Provide for TpmProvider {
             return Err(ResponseStatus::PsaErrorNotSupported);
         }
 
-        let (password_context, key_attributes) = get_password_context(&*store_handle, key_triple)?;
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
 
         key_attributes.can_sign_hash()?;
         key_attributes.permits_alg(alg.into())?;
         key_attributes.compatible_with_alg(alg.into())?;
 
-        match alg {
-            AsymmetricSignature::RsaPkcs1v15Sign {
-                hash_alg: Hash::Sha256,
-            } => (),
-            _ => {
-                error!(
-                    "The TPM provider currently only supports \"RSA PKCS#1 v1.5 signature with hashing\" algorithm with SHA-256 as hashing algorithm for the PsaSignHash operation.");
-                return Err(ResponseStatus::PsaErrorNotSupported);
-            }
-        }
-
-        let signature = esapi_context
-            .sign(
-                password_context.context,
-                &password_context.auth_value,
-                &hash,
-            )
-            .or_else(|e| {
-                error!("Error signing: {}.", e);
-                Err(utils::to_response_status(e))
-            })?;
-
         Ok(psa_sign_hash::Result {
-            signature: signature.signature,
+            signature: sign_hash(
+                &mut esapi_context,
+                password_context,
+                key_attributes,
+                alg,
+                &hash,
+            )?,
         })
     }
 
@@ -423,77 +928,667 @@ impl Provide for TpmProvider {
             return Err(ResponseStatus::PsaErrorNotSupported);
         }
 
-        let signature = Signature {
-            scheme: AsymSchemeUnion::RSASSA(TPM2_ALG_SHA256),
-            signature,
-        };
-
-        let (password_context, key_attributes) = get_password_context(&*store_handle, key_triple)?;
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
 
         key_attributes.can_verify_hash()?;
         key_attributes.permits_alg(alg.into())?;
         key_attributes.compatible_with_alg(alg.into())?;
 
-        match alg {
-            AsymmetricSignature::RsaPkcs1v15Sign {
-                hash_alg: Hash::Sha256,
-            } => (),
-            _ => {
-                error!(
-                    "The TPM provider currently only supports \"RSA PKCS#1 v1.5 signature with hashing\" algorithm with SHA-256 as hashing algorithm for the PsaVerifyHash operation.");
-                return Err(ResponseStatus::PsaErrorNotSupported);
-            }
-        }
-
-        let _ = esapi_context
-            .verify_signature(password_context.context, &hash, signature)
-            .or_else(|e| Err(utils::to_response_status(e)))?;
+        verify_hash(
+            &mut esapi_context,
+            password_context,
+            key_attributes,
+            alg,
+            &hash,
+            signature,
+        )?;
 
         Ok(psa_verify_hash::Result {})
     }
-}
 
-impl Drop for TpmProvider {
-    fn drop(&mut self) {
-        info!("Dropping the TPM Provider.");
-    }
-}
+    fn psa_sign_message(
+        &self,
+        app_name: ApplicationName,
+        op: psa_sign_message::Operation,
+    ) -> Result<psa_sign_message::Result> {
+        let key_name = op.key_name;
+        let message = op.message;
+        let alg = op.alg;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
 
-/// Builder for TpmProvider
-#[derive(Default, Derivative)]
-#[derivative(Debug)]
-pub struct TpmProviderBuilder {
-    #[derivative(Debug = "ignore")]
-    key_id_store: Option<Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>>,
-    tcti: Option<Tcti>,
-    owner_hierarchy_auth: Option<String>,
-}
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
 
-impl TpmProviderBuilder {
-    pub fn new() -> TpmProviderBuilder {
-        TpmProviderBuilder {
-            key_id_store: None,
-            tcti: None,
-            owner_hierarchy_auth: None,
-        }
-    }
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
 
-    pub fn with_key_id_store(
-        mut self,
-        key_id_store: Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>,
-    ) -> TpmProviderBuilder {
-        self.key_id_store = Some(key_id_store);
+        key_attributes.can_sign_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
 
-        self
+        let hash = utils::hash_message(signature_hash_alg(alg)?, &message)?;
+
+        Ok(psa_sign_message::Result {
+            signature: sign_hash(
+                &mut esapi_context,
+                password_context,
+                key_attributes,
+                alg,
+                &hash,
+            )?,
+        })
     }
 
-    pub fn with_tcti(mut self, tcti: &str) -> TpmProviderBuilder {
-        // Convert from a String to the enum.
-        self.tcti = match tcti {
-            "device" => Some(Tcti::Device),
-            "mssim" => Some(Tcti::Mssim),
-            _ => {
-                error!("The string {} does not match a TCTI device.", tcti);
+    fn psa_verify_message(
+        &self,
+        app_name: ApplicationName,
+        op: psa_verify_message::Operation,
+    ) -> Result<psa_verify_message::Result> {
+        let key_name = op.key_name;
+        let message = op.message;
+        let alg = op.alg;
+        let signature = op.signature;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
+
+        key_attributes.can_verify_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let hash = utils::hash_message(signature_hash_alg(alg)?, &message)?;
+
+        verify_hash(
+            &mut esapi_context,
+            password_context,
+            key_attributes,
+            alg,
+            &hash,
+            signature,
+        )?;
+
+        Ok(psa_verify_message::Result {})
+    }
+
+    fn psa_asymmetric_encrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_asymmetric_encrypt::Operation,
+    ) -> Result<psa_asymmetric_encrypt::Result> {
+        let key_name = op.key_name;
+        let plaintext = op.plaintext;
+        let alg = op.alg;
+        let label = op.salt.unwrap_or_default();
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
+
+        key_attributes.can_encrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let scheme = utils::convert_asym_scheme_to_tpm(alg.into())?;
+
+        let ciphertext = esapi_context
+            .rsa_encrypt(password_context.context, &plaintext, scheme, &label)
+            .or_else(|e| {
+                error!("Error encrypting: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+
+        Ok(psa_asymmetric_encrypt::Result { ciphertext })
+    }
+
+    fn psa_asymmetric_decrypt(
+        &self,
+        app_name: ApplicationName,
+        op: psa_asymmetric_decrypt::Operation,
+    ) -> Result<psa_asymmetric_decrypt::Result> {
+        let key_name = op.key_name;
+        let ciphertext = op.ciphertext;
+        let alg = op.alg;
+        let label = op.salt.unwrap_or_default();
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
+
+        key_attributes.can_decrypt_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let scheme = utils::convert_asym_scheme_to_tpm(alg.into())?;
+
+        let plaintext = esapi_context
+            .rsa_decrypt(
+                password_context.context,
+                &password_context.auth_value,
+                &ciphertext,
+                scheme,
+                &label,
+            )
+            .or_else(|e| {
+                error!("Error decrypting: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+
+        Ok(psa_asymmetric_decrypt::Result { plaintext })
+    }
+
+    fn psa_mac_compute(
+        &self,
+        app_name: ApplicationName,
+        op: psa_mac_compute::Operation,
+    ) -> Result<psa_mac_compute::Result> {
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let input = op.input;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        if input.len() > MAX_HMAC_INPUT_LEN {
+            error!("The buffer given to the MAC operation is too big. Its length is {} and maximum authorised in the TPM provider is {}.", input.len(), MAX_HMAC_INPUT_LEN);
+            return Err(ResponseStatus::PsaErrorNotSupported);
+        }
+
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
+
+        key_attributes.can_sign_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let hash_alg = utils::convert_mac_to_tpm(alg.into())?;
+
+        let mac = esapi_context
+            .hmac(
+                password_context.context,
+                &password_context.auth_value,
+                &input,
+                hash_alg,
+            )
+            .or_else(|e| {
+                error!("Error computing the HMAC: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+
+        Ok(psa_mac_compute::Result { mac })
+    }
+
+    fn psa_mac_verify(
+        &self,
+        app_name: ApplicationName,
+        op: psa_mac_verify::Operation,
+    ) -> Result<psa_mac_verify::Result> {
+        let key_name = op.key_name;
+        let alg = op.alg;
+        let input = op.input;
+        let mac = op.mac;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        if input.len() > MAX_HMAC_INPUT_LEN {
+            error!("The buffer given to the MAC operation is too big. Its length is {} and maximum authorised in the TPM provider is {}.", input.len(), MAX_HMAC_INPUT_LEN);
+            return Err(ResponseStatus::PsaErrorNotSupported);
+        }
+
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
+
+        key_attributes.can_verify_message()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let hash_alg = utils::convert_mac_to_tpm(alg.into())?;
+
+        let expected_mac = esapi_context
+            .hmac(
+                password_context.context,
+                &password_context.auth_value,
+                &input,
+                hash_alg,
+            )
+            .or_else(|e| {
+                error!("Error computing the HMAC: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+
+        // The TPM does not offer an HMAC verification command, the comparison is done here in
+        // constant time to not leak information about the expected MAC.
+        if utils::constant_time_eq(&expected_mac, &mac) {
+            Ok(psa_mac_verify::Result {})
+        } else {
+            Err(ResponseStatus::PsaErrorInvalidSignature)
+        }
+    }
+
+    fn psa_raw_key_agreement(
+        &self,
+        app_name: ApplicationName,
+        op: psa_raw_key_agreement::Operation,
+    ) -> Result<psa_raw_key_agreement::Result> {
+        let key_name = op.private_key_name;
+        let alg = op.alg;
+        let peer_key = op.peer_key;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
+
+        key_attributes.can_derive_from()?;
+        key_attributes.permits_alg(alg.into())?;
+        key_attributes.compatible_with_alg(alg.into())?;
+
+        let peer_point = utils::peer_key_to_point(peer_key, key_attributes)?;
+
+        // TPM2_ECDH_ZGen multiplies the peer point by the private key, the shared secret is the x
+        // coordinate of the resulting point.
+        let shared_point = esapi_context
+            .ecdh_z_gen(
+                password_context.context,
+                &password_context.auth_value,
+                peer_point,
+            )
+            .or_else(|e| {
+                error!("Error computing the shared point: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+
+        Ok(psa_raw_key_agreement::Result {
+            shared_secret: utils::point_to_shared_secret(shared_point, key_attributes)?,
+        })
+    }
+
+    fn psa_generate_random(
+        &self,
+        op: psa_generate_random::Operation,
+    ) -> Result<psa_generate_random::Result> {
+        let size = op.size;
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let mut random_bytes = Vec::with_capacity(size);
+        while random_bytes.len() < size {
+            let chunk_len = std::cmp::min(size - random_bytes.len(), GET_RANDOM_CHUNK_LEN);
+            let chunk = esapi_context.get_random(chunk_len).or_else(|e| {
+                error!("Error getting random bytes: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+            if chunk.is_empty() {
+                error!("The TPM did not return any random bytes.");
+                return Err(ResponseStatus::PsaErrorInsufficientEntropy);
+            }
+            random_bytes.extend(chunk);
+        }
+        random_bytes.truncate(size);
+
+        Ok(psa_generate_random::Result { random_bytes })
+    }
+
+    fn psa_copy_key(
+        &self,
+        app_name: ApplicationName,
+        op: psa_copy_key::Operation,
+    ) -> Result<psa_copy_key::Result> {
+        let source_key_triple = KeyTriple::new(app_name.clone(), ProviderID::Tpm, op.key_name);
+        let target_key_triple = KeyTriple::new(app_name, ProviderID::Tpm, op.target_key_name);
+        let target_key_attributes = op.attributes;
+
+        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let (password_context, source_key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, source_key_triple)?;
+
+        check_copy_policy(source_key_attributes, target_key_attributes)?;
+
+        if store_handle
+            .exists(&target_key_triple)
+            .or_else(|e| Err(key_id_managers::to_response_status(e)))?
+        {
+            return Err(ResponseStatus::PsaErrorAlreadyExists);
+        }
+
+        // Each copy is an independent object: public keys, which are not protected by an
+        // authorisation value, are loaded again from their public part and the other keys are
+        // duplicated under a new authorisation value.
+        let target_password_context = if is_public_key(source_key_attributes) {
+            let public_key = esapi_context
+                .read_public_key(password_context.context)
+                .or_else(|e| {
+                    error!("Error reading a public key: {}.", e);
+                    Err(utils::to_response_status(e))
+                })?;
+            let context = esapi_context
+                .load_external_public_key(
+                    public_key,
+                    utils::parsec_to_tpm_params(target_key_attributes)?,
+                )
+                .or_else(|e| {
+                    error!("Error loading an external public key: {}.", e);
+                    Err(utils::to_response_status(e))
+                })?;
+            PasswordContext {
+                context,
+                auth_value: Vec::new(),
+            }
+        } else {
+            let (context, auth_value) = esapi_context
+                .change_key_auth_value(password_context.context, &password_context.auth_value)
+                .or_else(|e| {
+                    error!("Error duplicating a key under a new auth value: {}.", e);
+                    Err(utils::to_response_status(e))
+                })?;
+            PasswordContext {
+                context,
+                auth_value,
+            }
+        };
+
+        self.insert_key(
+            &mut esapi_context,
+            &mut *store_handle,
+            target_key_triple,
+            target_password_context,
+            target_key_attributes,
+        )?;
+
+        Ok(psa_copy_key::Result {})
+    }
+
+    fn attest_key(
+        &self,
+        app_name: ApplicationName,
+        op: attest_key::Operation,
+    ) -> Result<attest_key::Result> {
+        let key_name = op.key_name;
+        let nonce = op.nonce;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        if nonce.len() > MAX_NONCE_LEN {
+            error!(
+                "The nonce given is too big. Its length is {} and maximum authorised is {} in the TPM provider.",
+                nonce.len(),
+                MAX_NONCE_LEN
+            );
+            return Err(ResponseStatus::PsaErrorInvalidArgument);
+        }
+        if let Some(pcr_selection) = &op.pcr_selection {
+            check_pcr_selection(pcr_selection)?;
+        }
+
+        // The write lock is needed as the attestation key might be created.
+        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let (password_context, key_attributes) =
+            self.get_password_context(&mut esapi_context, &*store_handle, key_triple)?;
+
+        if !can_be_persistent(key_attributes) {
+            error!("Only the keys created or imported with their private part in the TPM can be attested.");
+            return Err(ResponseStatus::PsaErrorInvalidArgument);
+        }
+
+        let attestation_key = self.get_attestation_key(&mut esapi_context, &mut *store_handle)?;
+
+        let (certify_info, signature) = esapi_context
+            .certify(
+                password_context.context,
+                &password_context.auth_value,
+                attestation_key.context.clone(),
+                &attestation_key.auth_value,
+                &nonce,
+            )
+            .or_else(|e| {
+                error!("Error certifying the key: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+        let certify_signature =
+            utils::signature_data_to_bytes(signature.signature, attestation_key_attributes())?;
+
+        let (quote_info, quote_signature) = match op.pcr_selection {
+            Some(pcr_selection) => {
+                let (quote_info, signature) = esapi_context
+                    .quote(
+                        attestation_key.context.clone(),
+                        &attestation_key.auth_value,
+                        &nonce,
+                        &pcr_selection,
+                    )
+                    .or_else(|e| {
+                        error!("Error quoting the PCRs: {}.", e);
+                        Err(utils::to_response_status(e))
+                    })?;
+                (
+                    Some(quote_info),
+                    Some(utils::signature_data_to_bytes(
+                        signature.signature,
+                        attestation_key_attributes(),
+                    )?),
+                )
+            }
+            None => (None, None),
+        };
+
+        let pub_key_data = esapi_context
+            .read_public_key(attestation_key.context)
+            .or_else(|e| {
+                error!(
+                    "Error reading the public part of the attestation key: {}.",
+                    e
+                );
+                Err(utils::to_response_status(e))
+            })?;
+
+        Ok(attest_key::Result {
+            certify_info,
+            certify_signature,
+            quote_info,
+            quote_signature,
+            attestation_public_key: utils::pub_key_to_bytes(
+                pub_key_data,
+                attestation_key_attributes(),
+            )?,
+        })
+    }
+
+    fn seal_data(
+        &self,
+        app_name: ApplicationName,
+        op: seal_data::Operation,
+    ) -> Result<seal_data::Result> {
+        let key_name = op.key_name;
+        let data = op.data;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        if data.is_empty() || data.len() > MAX_SEALED_DATA_LEN {
+            error!(
+                "The data to seal must not be empty and its length must be at most {} in the TPM provider, it is {}.",
+                MAX_SEALED_DATA_LEN,
+                data.len()
+            );
+            return Err(ResponseStatus::PsaErrorInvalidArgument);
+        }
+        if let Some(pcr_selection) = &op.pcr_selection {
+            check_pcr_selection(pcr_selection)?;
+        }
+
+        let mut store_handle = self.key_id_store.write().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        // The sealed data object is created under the root key of the provider. Its policy digest
+        // is computed with a trial policy session over the PCR selection given. Without a PCR
+        // selection, the object is only protected by its authValue.
+        let (sealed_context, auth_value) = esapi_context
+            .seal(&data, AUTH_VAL_LEN, op.pcr_selection.as_deref())
+            .or_else(|e| {
+                error!("Error sealing data: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+
+        // The PCR selection is stored with the object so that it is always the one used to
+        // unseal it.
+        insert_stored_key(
+            &mut *store_handle,
+            key_triple,
+            StoredKey::Sealed(SealedDataContext {
+                context: sealed_context,
+                auth_value,
+                pcr_selection: op.pcr_selection,
+            }),
+            sealed_data_attributes(data.len()),
+        )?;
+
+        Ok(seal_data::Result {})
+    }
+
+    fn unseal_data(
+        &self,
+        app_name: ApplicationName,
+        op: unseal_data::Operation,
+    ) -> Result<unseal_data::Result> {
+        let key_name = op.key_name;
+        let key_triple = KeyTriple::new(app_name, ProviderID::Tpm, key_name);
+
+        if let Some(pcr_selection) = &op.pcr_selection {
+            check_pcr_selection(pcr_selection)?;
+        }
+
+        let store_handle = self.key_id_store.read().expect("Key store lock poisoned");
+        let mut esapi_context = self
+            .esapi_context
+            .lock()
+            .expect("ESAPI Context lock poisoned");
+
+        let key_info = get_key_info(&*store_handle, &key_triple)?;
+        let sealed_data_context = match get_stored_key(&key_info)? {
+            StoredKey::Sealed(sealed_data_context) => sealed_data_context,
+            _ => {
+                error!("Only the objects created with the SealData operation can be unsealed.");
+                return Err(ResponseStatus::PsaErrorInvalidArgument);
+            }
+        };
+
+        // The PCR selection stored when the data was sealed is always used. A PCR selection given
+        // by the caller has to be the same one.
+        if let Some(pcr_selection) = &op.pcr_selection {
+            if sealed_data_context.pcr_selection.as_ref() != Some(pcr_selection) {
+                error!("The PCR selection given is not the one the data was sealed with.");
+                return Err(ResponseStatus::PsaErrorNotPermitted);
+            }
+        }
+
+        // When the object is bound to a PCR selection, it is unsealed through a policy session
+        // which only succeeds if the PCRs still have the values they had when the data was sealed.
+        let data = esapi_context
+            .unseal(
+                sealed_data_context.context,
+                &sealed_data_context.auth_value,
+                sealed_data_context.pcr_selection.as_deref(),
+            )
+            .or_else(|e| {
+                error!("Error unsealing data: {}.", e);
+                Err(utils::to_response_status(e))
+            })?;
+
+        Ok(unseal_data::Result { data })
+    }
+}
+
+impl Drop for TpmProvider {
+    fn drop(&mut self) {
+        info!("Dropping the TPM Provider.");
+    }
+}
+
+/// Builder for TpmProvider
+#[derive(Default, Derivative)]
+#[derivative(Debug)]
+pub struct TpmProviderBuilder {
+    #[derivative(Debug = "ignore")]
+    key_id_store: Option<Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>>,
+    tcti: Option<Tcti>,
+    owner_hierarchy_auth: Option<Secret>,
+    persistent_keys: bool,
+}
+
+impl TpmProviderBuilder {
+    pub fn new() -> TpmProviderBuilder {
+        TpmProviderBuilder {
+            key_id_store: None,
+            tcti: None,
+            owner_hierarchy_auth: None,
+            persistent_keys: false,
+        }
+    }
+
+    pub fn with_key_id_store(
+        mut self,
+        key_id_store: Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>,
+    ) -> TpmProviderBuilder {
+        self.key_id_store = Some(key_id_store);
+
+        self
+    }
+
+    pub fn with_tcti(mut self, tcti: &str) -> TpmProviderBuilder {
+        // Convert from a String to the enum.
+        self.tcti = match tcti {
+            "device" => Some(Tcti::Device),
+            "mssim" => Some(Tcti::Mssim),
+            _ => {
+                error!("The string {} does not match a TCTI device.", tcti);
                 None
             }
         };
@@ -501,12 +1596,20 @@ impl TpmProviderBuilder {
         self
     }
 
-    pub fn with_owner_hierarchy_auth(mut self, owner_hierarchy_auth: String) -> TpmProviderBuilder {
+    pub fn with_owner_hierarchy_auth(mut self, owner_hierarchy_auth: Secret) -> TpmProviderBuilder {
         self.owner_hierarchy_auth = Some(owner_hierarchy_auth);
 
         self
     }
 
+    /// Make the keys persistent in the TPM, using TPM2_EvictControl, instead of storing their
+    /// saved contexts. Only the persistent handle of those keys is stored in the Key ID Manager.
+    pub fn with_persistent_keys(mut self, persistent_keys: bool) -> TpmProviderBuilder {
+        self.persistent_keys = persistent_keys;
+
+        self
+    }
+
     /// Create an instance of TpmProvider
     ///
     /// # Safety
@@ -527,6 +1630,7 @@ impl TpmProviderBuilder {
                     .ok_or_else(|| {
                         std::io::Error::new(ErrorKind::InvalidData, "missing owner hierarchy auth")
                     })?
+                    .expose()
                     .as_bytes(),
             )
             .or_else(|e| {
@@ -536,6 +1640,7 @@ impl TpmProviderBuilder {
                     "failed initializing TSS context",
                 ))
             })?,
+            self.persistent_keys,
         )
         .ok_or_else(|| {
             std::io::Error::new(ErrorKind::InvalidData, "failed initializing TPM provider")
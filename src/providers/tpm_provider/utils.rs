@@ -14,8 +14,59 @@
 // limitations under the License.
 
 use log::error;
-use parsec_interface::requests::ResponseStatus;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{ResponseStatus, Result};
+use picky_asn1::wrapper::IntegerAsn1;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::convert::TryFrom;
+use tss_esapi::constants::{
+    TPM2_ALG_SHA1, TPM2_ALG_SHA256, TPM2_ALG_SHA384, TPM2_ALG_SHA512, TPM2_ECC_NIST_P192,
+    TPM2_ECC_NIST_P224, TPM2_ECC_NIST_P256, TPM2_ECC_NIST_P384, TPM2_ECC_NIST_P521,
+};
 use tss_esapi::response_code::{Error, Tss2ResponseCodeKind};
+use tss_esapi::tss2_esys::{TPM2_ALG_ID, TPM2_ECC_CURVE};
+use tss_esapi::utils::{AsymSchemeUnion, KeyParams, PublicKey, Signature, SignatureData};
+
+// Public exponent value for all RSA keys.
+const PUBLIC_EXPONENT: [u8; 3] = [0x01, 0x00, 0x01];
+
+// The RSA Public Key data are DER encoded with the following representation:
+// RSAPublicKey ::= SEQUENCE {
+//     modulus            INTEGER,  -- n
+//     publicExponent     INTEGER   -- e
+// }
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RsaPublicKey {
+    pub modulus: IntegerAsn1,
+    pub public_exponent: IntegerAsn1,
+}
+
+// The RSA Private Key data are DER encoded with the following representation:
+// RSAPrivateKey ::= SEQUENCE {
+//     version           INTEGER,  -- 0
+//     modulus           INTEGER,  -- n
+//     publicExponent    INTEGER,  -- e
+//     privateExponent   INTEGER,  -- d
+//     prime1            INTEGER,  -- p
+//     prime2            INTEGER,  -- q
+//     exponent1         INTEGER,  -- d mod (p-1)
+//     exponent2         INTEGER,  -- d mod (q-1)
+//     coefficient       INTEGER   -- (inverse of q) mod p
+// }
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RsaPrivateKey {
+    pub version: IntegerAsn1,
+    pub modulus: IntegerAsn1,
+    pub public_exponent: IntegerAsn1,
+    pub private_exponent: IntegerAsn1,
+    pub prime1: IntegerAsn1,
+    pub prime2: IntegerAsn1,
+    pub exponent1: IntegerAsn1,
+    pub exponent2: IntegerAsn1,
+    pub coefficient: IntegerAsn1,
+}
 
 /// Convert the TSS library specific error values to ResponseStatus values that are returned on
 /// the wire protocol
@@ -45,6 +96,8 @@ pub fn to_response_status(error: Error) -> ResponseStatus {
                     }
                     Tss2ResponseCodeKind::Memory => ResponseStatus::PsaErrorInsufficientMemory,
                     Tss2ResponseCodeKind::Retry => ResponseStatus::PsaErrorHardwareFailure,
+                    Tss2ResponseCodeKind::NvSpace => ResponseStatus::PsaErrorInsufficientStorage,
+                    Tss2ResponseCodeKind::PolicyFail => ResponseStatus::PsaErrorNotPermitted,
                     s @ Tss2ResponseCodeKind::Asymmetric
                     | s @ Tss2ResponseCodeKind::Hash
                     | s @ Tss2ResponseCodeKind::KeySize
@@ -75,3 +128,480 @@ pub fn to_response_status(error: Error) -> ResponseStatus {
         }
     }
 }
+
+/// Get the kind of the TPM response code of an error, if it has one.
+pub fn error_kind(error: &Error) -> Option<Tss2ResponseCodeKind> {
+    match error {
+        Error::Tss2Error(e) => e.kind(),
+        Error::WrapperError(_) => None,
+    }
+}
+
+/// Get the TPM key parameters needed to create or load a key with the attributes given.
+///
+/// # Errors
+///
+/// Only RSA, Elliptic Curve and HMAC keys are supported. Returns `PsaErrorNotSupported` otherwise
+/// or if the permitted algorithm of the key can not be used by the TPM provider.
+pub fn parsec_to_tpm_params(attributes: KeyAttributes) -> Result<KeyParams> {
+    match attributes.key_type {
+        KeyType::RsaKeyPair | KeyType::RsaPublicKey => {
+            // This should never panic on 32 bits or more machines.
+            let size = usize::try_from(attributes.key_bits).expect("Conversion to usize failed.");
+            let scheme = convert_asym_scheme_to_tpm(attributes.key_policy.key_algorithm)?;
+            // Zero means that the default public exponent, 0x10001, is used.
+            if let Algorithm::AsymmetricEncryption(_) = attributes.key_policy.key_algorithm {
+                Ok(KeyParams::RsaEncrypt {
+                    size,
+                    scheme,
+                    pub_exponent: 0,
+                })
+            } else {
+                Ok(KeyParams::RsaSign {
+                    size,
+                    scheme,
+                    pub_exponent: 0,
+                })
+            }
+        }
+        KeyType::EccKeyPair { .. } | KeyType::EccPublicKey { .. } => Ok(KeyParams::Ecc {
+            curve: convert_curve_to_tpm(attributes)?,
+            scheme: convert_asym_scheme_to_tpm(attributes.key_policy.key_algorithm)?,
+        }),
+        // HMAC keys are keyed-hash objects created under the Storage Root Key.
+        KeyType::Hmac => Ok(KeyParams::KeyedHash {
+            hash_alg: convert_mac_to_tpm(attributes.key_policy.key_algorithm)?,
+        }),
+        _ => {
+            error!("The TPM provider only supports RSA, Elliptic Curve and HMAC keys.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+/// Convert a PSA asymmetric signature, encryption or key agreement algorithm to the TPM scheme
+/// used for it.
+///
+/// # Errors
+///
+/// The hashing algorithm of the scheme is converted with `convert_hash_to_tpm`. Returns
+/// `PsaErrorNotSupported` if the algorithm or its hashing algorithm has no TPM equivalent.
+pub fn convert_asym_scheme_to_tpm(algorithm: Algorithm) -> Result<AsymSchemeUnion> {
+    match algorithm {
+        Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPkcs1v15Sign { hash_alg }) => {
+            Ok(AsymSchemeUnion::RSASSA(convert_hash_to_tpm(hash_alg)?))
+        }
+        Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPss { hash_alg }) => {
+            Ok(AsymSchemeUnion::RSAPSS(convert_hash_to_tpm(hash_alg)?))
+        }
+        Algorithm::AsymmetricSignature(AsymmetricSignature::Ecdsa { hash_alg }) => {
+            Ok(AsymSchemeUnion::ECDSA(convert_hash_to_tpm(hash_alg)?))
+        }
+        Algorithm::AsymmetricEncryption(AsymmetricEncryption::RsaPkcs1v15Crypt) => {
+            Ok(AsymSchemeUnion::RSAES)
+        }
+        Algorithm::AsymmetricEncryption(AsymmetricEncryption::RsaOaep { hash_alg }) => {
+            Ok(AsymSchemeUnion::RSAOAEP(convert_hash_to_tpm(hash_alg)?))
+        }
+        // The hashing algorithm of the ECDH scheme is not used by TPM2_ECDH_ZGen.
+        Algorithm::KeyAgreement(KeyAgreement::Raw(RawKeyAgreement::Ecdh)) => {
+            Ok(AsymSchemeUnion::ECDH(TPM2_ALG_SHA256))
+        }
+        _ => {
+            error!("The TPM provider currently only supports RSA PKCS#1 v1.5, RSA PSS and ECDSA signature algorithms, RSA PKCS#1 v1.5 and RSA OAEP encryption algorithms and raw ECDH key agreement.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+// Size of the chunks in which a message is given to the hash function.
+const HASH_CHUNK_SIZE: usize = 4096;
+
+/// Hash a message in software, as the TPM can only hash messages of a limited size in one
+/// command.
+///
+/// # Errors
+///
+/// Only the SHA-256, SHA-384 and SHA-512 algorithms are implemented. Returns
+/// `PsaErrorNotSupported` otherwise.
+pub fn hash_message(hash: Hash, message: &[u8]) -> Result<Vec<u8>> {
+    match hash {
+        Hash::Sha256 => Ok(digest_in_chunks::<Sha256>(message)),
+        Hash::Sha384 => Ok(digest_in_chunks::<Sha384>(message)),
+        Hash::Sha512 => Ok(digest_in_chunks::<Sha512>(message)),
+        _ => {
+            error!("The TPM provider currently only supports SHA-256, SHA-384 and SHA-512 to hash messages.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+fn digest_in_chunks<D: Digest>(message: &[u8]) -> Vec<u8> {
+    let mut hasher = D::new();
+    for chunk in message.chunks(HASH_CHUNK_SIZE) {
+        hasher.update(chunk);
+    }
+
+    hasher.finalize().to_vec()
+}
+
+/// Convert a PSA hash algorithm to the TPM algorithm identifier of the same hash function.
+///
+/// Whether the algorithm is implemented by the TPM is only known when it is used: the TPM
+/// then returns an error which is converted by `to_response_status`.
+///
+/// # Errors
+///
+/// Only SHA-1 and the SHA-256, SHA-384 and SHA-512 algorithms of the SHA-2 family have an
+/// identifier. Returns `PsaErrorNotSupported` otherwise.
+pub fn convert_hash_to_tpm(hash: Hash) -> Result<TPM2_ALG_ID> {
+    match hash {
+        Hash::Sha1 => Ok(TPM2_ALG_SHA1),
+        Hash::Sha256 => Ok(TPM2_ALG_SHA256),
+        Hash::Sha384 => Ok(TPM2_ALG_SHA384),
+        Hash::Sha512 => Ok(TPM2_ALG_SHA512),
+        _ => {
+            error!("The TPM provider currently only supports SHA-1, SHA-256, SHA-384 and SHA-512 as hashing algorithms.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+/// Check that a digest given to a signature operation has the size of the output of the hashing
+/// algorithm of the signature algorithm.
+pub fn check_digest_size(signature_alg: AsymmetricSignature, digest: &[u8]) -> Result<()> {
+    let hash_alg = match signature_alg {
+        AsymmetricSignature::RsaPkcs1v15Sign { hash_alg }
+        | AsymmetricSignature::RsaPss { hash_alg }
+        | AsymmetricSignature::Ecdsa { hash_alg } => hash_alg,
+        _ => return Ok(()),
+    };
+    let digest_size = match hash_alg {
+        Hash::Sha1 => 20,
+        Hash::Sha256 => 32,
+        Hash::Sha384 => 48,
+        Hash::Sha512 => 64,
+        _ => return Ok(()),
+    };
+
+    if digest.len() != digest_size {
+        error!(
+            "The digest should be {} bytes long for this algorithm ({} given).",
+            digest_size,
+            digest.len()
+        );
+        Err(ResponseStatus::PsaErrorInvalidArgument)
+    } else {
+        Ok(())
+    }
+}
+
+/// Convert a PSA MAC algorithm to the hashing algorithm of the TPM keyed-hash object used for it.
+///
+/// # Errors
+///
+/// Only full length HMAC is supported, with the hashing algorithms of `convert_hash_to_tpm`.
+/// Returns `PsaErrorNotSupported` otherwise.
+pub fn convert_mac_to_tpm(algorithm: Algorithm) -> Result<TPM2_ALG_ID> {
+    match algorithm {
+        Algorithm::Mac(Mac::FullLength(FullLengthMac::Hmac { hash_alg })) => {
+            convert_hash_to_tpm(hash_alg)
+        }
+        _ => {
+            error!("The TPM provider currently only supports full length HMAC as MAC algorithm.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+/// Compare two buffers in a time which only depends on their lengths.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Only the NIST curves (SECP R1 family) are supported by the TPM.
+fn convert_curve_to_tpm(key_attributes: KeyAttributes) -> Result<TPM2_ECC_CURVE> {
+    match key_attributes.key_type {
+        KeyType::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        }
+        | KeyType::EccPublicKey {
+            curve_family: EccFamily::SecpR1,
+        } => match key_attributes.key_bits {
+            192 => Ok(TPM2_ECC_NIST_P192),
+            224 => Ok(TPM2_ECC_NIST_P224),
+            256 => Ok(TPM2_ECC_NIST_P256),
+            384 => Ok(TPM2_ECC_NIST_P384),
+            521 => Ok(TPM2_ECC_NIST_P521),
+            bits => {
+                error!("The SECP R1 curve with {} bits is not supported.", bits);
+                Err(ResponseStatus::PsaErrorNotSupported)
+            }
+        },
+        _ => {
+            error!("The TPM provider only supports curves of the SECP R1 family.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+// Size in bytes of the field elements (coordinates or signature components) of the curve.
+fn ecc_field_size(key_attributes: KeyAttributes) -> usize {
+    // This should never panic on 32 bits or more machines.
+    usize::try_from((key_attributes.key_bits + 7) / 8).expect("Conversion to usize failed.")
+}
+
+// Pads with zeros a big-endian unsigned value to the size given.
+fn pad_to_size(value: Vec<u8>, size: usize) -> Result<Vec<u8>> {
+    if value.len() > size {
+        error!(
+            "Value of {} bytes can not fit in a field element of {} bytes.",
+            value.len(),
+            size
+        );
+        return Err(ResponseStatus::PsaErrorCommunicationFailure);
+    }
+    let mut padded = vec![0; size - value.len()];
+    padded.extend(value);
+    Ok(padded)
+}
+
+/// Convert the public key read from the TPM to the format expected by the PSA export operation.
+///
+/// RSA public keys are exported as a DER-encoded `RSAPublicKey` structure and Elliptic Curve public
+/// keys as an uncompressed point (`0x04 || x || y`).
+pub fn pub_key_to_bytes(pub_key: PublicKey, key_attributes: KeyAttributes) -> Result<Vec<u8>> {
+    match pub_key {
+        PublicKey::Rsa(key) => {
+            let key = RsaPublicKey {
+                // To produce a valid ASN.1 RSAPublicKey structure, 0x00 is put in front of the
+                // positive modulus if highest significant bit is one, to differentiate it from a
+                // negative number.
+                modulus: IntegerAsn1::from_unsigned_bytes_be(key),
+                public_exponent: IntegerAsn1::from_signed_bytes_be(PUBLIC_EXPONENT.to_vec()),
+            };
+            picky_asn1_der::to_vec(&key).or_else(|err| {
+                error!("Could not serialise key elements: {}.", err);
+                Err(ResponseStatus::PsaErrorCommunicationFailure)
+            })
+        }
+        PublicKey::Ecc { x, y } => {
+            let field_size = ecc_field_size(key_attributes);
+            let mut key_data = vec![0x04];
+            key_data.append(&mut pad_to_size(x, field_size)?);
+            key_data.append(&mut pad_to_size(y, field_size)?);
+            Ok(key_data)
+        }
+    }
+}
+
+/// Parse the public key data given to the PSA import operation to a TPM public key.
+///
+/// # Errors
+///
+/// Only positive RSA public keys with the `0x10001` exponent and uncompressed Elliptic Curve points
+/// are supported.
+pub fn bytes_to_pub_key(key_data: Vec<u8>, key_attributes: KeyAttributes) -> Result<PublicKey> {
+    match key_attributes.key_type {
+        KeyType::RsaPublicKey => {
+            let public_key: RsaPublicKey =
+                picky_asn1_der::from_bytes(&key_data).or_else(|err| {
+                    error!("Could not deserialise key elements: {}.", err);
+                    Err(ResponseStatus::PsaErrorInvalidArgument)
+                })?;
+
+            if public_key.modulus.is_negative() || public_key.public_exponent.is_negative() {
+                error!("Only positive modulus and public exponent are supported.");
+                return Err(ResponseStatus::PsaErrorInvalidArgument);
+            }
+
+            if public_key.public_exponent.as_unsigned_bytes_be() != PUBLIC_EXPONENT {
+                error!("The TPM Provider only supports 0x101 as public exponent for RSA public keys, {:?} given.", public_key.public_exponent.as_unsigned_bytes_be());
+                return Err(ResponseStatus::PsaErrorNotSupported);
+            }
+            let key_data = public_key.modulus.as_unsigned_bytes_be();
+
+            let len = key_data.len();
+            if len != 128 && len != 256 {
+                error!(
+                    "The TPM provider only supports 1024 and 2048 bits RSA public keys ({} bits given).",
+                    len * 8
+                );
+                return Err(ResponseStatus::PsaErrorNotSupported);
+            }
+
+            Ok(PublicKey::Rsa(key_data.to_vec()))
+        }
+        KeyType::EccPublicKey { .. } => {
+            let field_size = ecc_field_size(key_attributes);
+            if key_data.len() != 1 + 2 * field_size || key_data[0] != 0x04 {
+                error!("Elliptic Curve public keys must be given as an uncompressed point.");
+                return Err(ResponseStatus::PsaErrorInvalidArgument);
+            }
+
+            Ok(PublicKey::Ecc {
+                x: key_data[1..=field_size].to_vec(),
+                y: key_data[field_size + 1..].to_vec(),
+            })
+        }
+        _ => {
+            error!("The TPM provider only supports importing RSA and Elliptic Curve public keys.");
+            Err(ResponseStatus::PsaErrorNotSupported)
+        }
+    }
+}
+
+/// Parse a DER-encoded `RSAPrivateKey` structure to the public key of the pair and its first
+/// prime, which is the sensitive part of RSA keys in the TPM.
+///
+/// # Errors
+///
+/// Only two-prime 1024 and 2048 bits RSA keys with the `0x10001` public exponent and matching the
+/// size of the key attributes are supported.
+pub fn bytes_to_rsa_key_pair(
+    key_data: Vec<u8>,
+    key_attributes: KeyAttributes,
+) -> Result<(PublicKey, Vec<u8>)> {
+    let private_key: RsaPrivateKey = picky_asn1_der::from_bytes(&key_data).or_else(|err| {
+        error!("Could not deserialise key elements: {}.", err);
+        Err(ResponseStatus::PsaErrorInvalidArgument)
+    })?;
+
+    if private_key.version.as_unsigned_bytes_be() != [0] {
+        error!("Only two-prime RSA private keys (version 0) are supported.");
+        return Err(ResponseStatus::PsaErrorNotSupported);
+    }
+
+    if private_key.modulus.is_negative()
+        || private_key.public_exponent.is_negative()
+        || private_key.prime1.is_negative()
+    {
+        error!("Only positive modulus, public exponent and primes are supported.");
+        return Err(ResponseStatus::PsaErrorInvalidArgument);
+    }
+
+    if private_key.public_exponent.as_unsigned_bytes_be() != PUBLIC_EXPONENT {
+        error!(
+            "The TPM Provider only supports 0x101 as public exponent for RSA keys, {:?} given.",
+            private_key.public_exponent.as_unsigned_bytes_be()
+        );
+        return Err(ResponseStatus::PsaErrorNotSupported);
+    }
+
+    let modulus = private_key.modulus.as_unsigned_bytes_be();
+    let len = modulus.len();
+    if len != 128 && len != 256 {
+        error!(
+            "The TPM provider only supports 1024 and 2048 bits RSA keys ({} bits given).",
+            len * 8
+        );
+        return Err(ResponseStatus::PsaErrorNotSupported);
+    }
+    // This should never panic on 32 bits or more machines.
+    let key_bits = usize::try_from(key_attributes.key_bits).expect("Conversion to usize failed.");
+    if len * 8 != key_bits {
+        error!(
+            "The size of the key ({} bits) does not match the one of its attributes ({} bits).",
+            len * 8,
+            key_attributes.key_bits
+        );
+        return Err(ResponseStatus::PsaErrorInvalidArgument);
+    }
+
+    let prime = private_key.prime1.as_unsigned_bytes_be();
+    if prime.len() > len / 2 {
+        error!("The first prime of the RSA key is larger than half of the modulus.");
+        return Err(ResponseStatus::PsaErrorInvalidArgument);
+    }
+
+    Ok((
+        PublicKey::Rsa(modulus.to_vec()),
+        pad_to_size(prime.to_vec(), len / 2)?,
+    ))
+}
+
+/// Parse the public key of the peer given to the raw key agreement operation to a TPM point on
+/// the curve of the private key.
+///
+/// # Errors
+///
+/// The peer public key must be given as an uncompressed point.
+pub fn peer_key_to_point(peer_key: Vec<u8>, key_attributes: KeyAttributes) -> Result<PublicKey> {
+    let curve_family = match key_attributes.key_type {
+        KeyType::EccKeyPair { curve_family } => curve_family,
+        _ => {
+            error!("Raw key agreement is only supported with Elliptic Curve key pairs.");
+            return Err(ResponseStatus::PsaErrorInvalidArgument);
+        }
+    };
+    let peer_attributes = KeyAttributes {
+        key_type: KeyType::EccPublicKey { curve_family },
+        ..key_attributes
+    };
+
+    bytes_to_pub_key(peer_key, peer_attributes)
+}
+
+/// Convert the shared point computed by the TPM to the shared secret returned by the raw key
+/// agreement operation: its x coordinate, zero-padded to the size of the curve.
+pub fn point_to_shared_secret(point: PublicKey, key_attributes: KeyAttributes) -> Result<Vec<u8>> {
+    match point {
+        PublicKey::Ecc { x, .. } => pad_to_size(x, ecc_field_size(key_attributes)),
+        _ => {
+            error!("The TPM did not return an Elliptic Curve point.");
+            Err(ResponseStatus::PsaErrorCommunicationFailure)
+        }
+    }
+}
+
+/// Convert the signature produced by the TPM to the format expected by the PSA sign operation.
+///
+/// ECDSA signatures are represented as the concatenation of the `r` and `s` values, each of them
+/// being zero-padded to the size of the curve.
+pub fn signature_data_to_bytes(
+    data: SignatureData,
+    key_attributes: KeyAttributes,
+) -> Result<Vec<u8>> {
+    match data {
+        SignatureData::RsaSignature(signature) => Ok(signature),
+        SignatureData::EcdsaSignature { r, s } => {
+            let field_size = ecc_field_size(key_attributes);
+            let mut signature = pad_to_size(r, field_size)?;
+            signature.append(&mut pad_to_size(s, field_size)?);
+            Ok(signature)
+        }
+    }
+}
+
+/// Convert a PSA signature to the TPM signature structure used for verification.
+pub fn parsec_to_tpm_signature(
+    data: Vec<u8>,
+    key_attributes: KeyAttributes,
+    signature_alg: AsymmetricSignature,
+) -> Result<Signature> {
+    let scheme = convert_asym_scheme_to_tpm(Algorithm::AsymmetricSignature(signature_alg))?;
+    let signature = match signature_alg {
+        AsymmetricSignature::Ecdsa { .. } => {
+            let field_size = ecc_field_size(key_attributes);
+            if data.len() != 2 * field_size {
+                error!(
+                    "ECDSA signatures for this key should be {} bytes long ({} given).",
+                    2 * field_size,
+                    data.len()
+                );
+                return Err(ResponseStatus::PsaErrorInvalidSignature);
+            }
+            SignatureData::EcdsaSignature {
+                r: data[..field_size].to_vec(),
+                s: data[field_size..].to_vec(),
+            }
+        }
+        _ => SignatureData::RsaSignature(data),
+    };
+
+    Ok(Signature { scheme, signature })
+}
@@ -13,6 +13,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 //! Service utilities
+mod secret;
 mod service_builder;
 
+pub use secret::{Secret, SecretSources};
 pub use service_builder::{CoreSettings, ServiceBuilder, ServiceConfig};
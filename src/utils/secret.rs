@@ -0,0 +1,273 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Secret values of the configuration
+//!
+//! Secrets, such as PINs or authentication values, can be given directly in the configuration
+//! file, read from a file, read from an environment variable or read from a systemd credential.
+//! Environment variables are removed once read so that the secret is not inherited by child
+//! processes nor readable from the environment of the service.
+use log::error;
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Environment variable set by systemd to the directory containing the credentials of the service
+const CREDENTIALS_DIRECTORY_ENV: &str = "CREDENTIALS_DIRECTORY";
+
+/// Secret string which is wiped from memory when dropped and never printed
+#[derive(Deserialize, Clone)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Get the secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Secret(value)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secret(<redacted>)")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Sources from which a secret of the configuration can be read
+///
+/// At most one of them can be given for a secret.
+#[derive(Debug, Clone, Copy)]
+pub struct SecretSources<'a> {
+    /// Name of the configuration field of the secret
+    pub name: &'a str,
+    /// Secret given directly in the configuration file
+    pub value: &'a Option<Secret>,
+    /// Path of a file containing the secret
+    pub file: &'a Option<PathBuf>,
+    /// Name of an environment variable containing the secret
+    pub env: &'a Option<String>,
+    /// Name of a systemd credential containing the secret
+    pub credential: &'a Option<String>,
+}
+
+impl SecretSources<'_> {
+    /// Read the secret from the source given, if any.
+    ///
+    /// A single trailing newline is removed from the secrets read from files and credentials. The
+    /// environment variable a secret is read from is removed from the environment.
+    ///
+    /// # Errors
+    /// If more than one source is given or if the secret can not be read from its source, an
+    /// error of kind `InvalidData` is returned.
+    pub fn read(&self) -> Result<Option<Secret>> {
+        let sources_count = [
+            self.value.is_some(),
+            self.file.is_some(),
+            self.env.is_some(),
+            self.credential.is_some(),
+        ]
+        .iter()
+        .filter(|given| **given)
+        .count();
+        if sources_count > 1 {
+            error!(
+                "Only one of {0}, {0}_file, {0}_env and {0}_credential can be given.",
+                self.name
+            );
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "secret given by more than one source",
+            ));
+        }
+
+        if let Some(value) = self.value {
+            Ok(Some(value.clone()))
+        } else if let Some(file) = self.file {
+            read_secret_file(self.name, file).map(Some)
+        } else if let Some(env) = self.env {
+            let value = env::var(env).or_else(|e| {
+                error!(
+                    "Error reading {} from the environment variable {} ({}).",
+                    self.name, env, e
+                );
+                Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "failed reading secret from environment",
+                ))
+            })?;
+            env::remove_var(env);
+            Ok(Some(Secret(value)))
+        } else if let Some(credential) = self.credential {
+            if credential.is_empty() || credential.contains('/') {
+                error!("The systemd credential name of {} is not valid.", self.name);
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "invalid credential name",
+                ));
+            }
+            let directory = env::var_os(CREDENTIALS_DIRECTORY_ENV).ok_or_else(|| {
+                error!(
+                    "{} is read from a systemd credential but {} is not set.",
+                    self.name, CREDENTIALS_DIRECTORY_ENV
+                );
+                Error::new(ErrorKind::InvalidData, "missing credentials directory")
+            })?;
+            read_secret_file(self.name, &Path::new(&directory).join(credential)).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+fn read_secret_file(name: &str, path: &Path) -> Result<Secret> {
+    let mut contents = Zeroizing::new(fs::read_to_string(path).or_else(|e| {
+        error!(
+            "Error reading {} from the file {} ({}).",
+            name,
+            path.display(),
+            e
+        );
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            "failed reading secret from file",
+        ))
+    })?);
+
+    if contents.ends_with('\n') {
+        let _ = contents.pop();
+        if contents.ends_with('\r') {
+            let _ = contents.pop();
+        }
+    }
+
+    // The contents read are wiped from memory when dropped, only the copy in the Secret remains.
+    Ok(Secret(String::from(contents.as_str())))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Secret, SecretSources};
+    use std::env;
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    fn sources<'a>(
+        value: &'a Option<Secret>,
+        file: &'a Option<PathBuf>,
+        env: &'a Option<String>,
+        credential: &'a Option<String>,
+    ) -> SecretSources<'a> {
+        SecretSources {
+            name: "secret",
+            value,
+            file,
+            env,
+            credential,
+        }
+    }
+
+    #[test]
+    fn debug_does_not_print_secret() {
+        let secret = Secret::from(String::from("very_secret_pin"));
+
+        assert!(!format!("{:?}", secret).contains("very_secret_pin"));
+        assert!(!format!("{:?}", Some(secret)).contains("very_secret_pin"));
+    }
+
+    #[test]
+    fn read_from_value() {
+        let value = Some(Secret::from(String::from("pin")));
+        let secret = sources(&value, &None, &None, &None)
+            .read()
+            .unwrap()
+            .expect("Secret should be given");
+
+        assert_eq!(secret.expose(), "pin");
+    }
+
+    #[test]
+    fn read_from_file() {
+        let path = PathBuf::from(env!("OUT_DIR").to_owned() + "/read_from_file_secret");
+        fs::write(&path, "file_pin\n").unwrap();
+
+        let file = Some(path.clone());
+        let secret = sources(&None, &file, &None, &None)
+            .read()
+            .unwrap()
+            .expect("Secret should be given");
+
+        assert_eq!(secret.expose(), "file_pin");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn read_from_env() {
+        env::set_var("PARSEC_TEST_READ_FROM_ENV_SECRET", "env_pin");
+
+        let env = Some(String::from("PARSEC_TEST_READ_FROM_ENV_SECRET"));
+        let secret = sources(&None, &None, &env, &None)
+            .read()
+            .unwrap()
+            .expect("Secret should be given");
+
+        assert_eq!(secret.expose(), "env_pin");
+        assert!(env::var_os("PARSEC_TEST_READ_FROM_ENV_SECRET").is_none());
+    }
+
+    #[test]
+    fn read_without_source() {
+        assert!(sources(&None, &None, &None, &None)
+            .read()
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn read_from_two_sources() {
+        let value = Some(Secret::from(String::from("pin")));
+        let env = Some(String::from("PARSEC_TEST_READ_FROM_TWO_SOURCES_SECRET"));
+        let error = sources(&value, &None, &env, &None)
+            .read()
+            .expect_err("Only one source can be given");
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn read_invalid_credential_name() {
+        let credential = Some(String::from("../pin"));
+        let error = sources(&None, &None, &None, &credential)
+            .read()
+            .expect_err("Credential names can not contain slashes");
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -48,6 +48,8 @@ use crate::providers::mbed_provider::MbedProviderBuilder;
 use crate::providers::pkcs11_provider::Pkcs11ProviderBuilder;
 #[cfg(feature = "tpm-provider")]
 use crate::providers::tpm_provider::TpmProviderBuilder;
+#[cfg(any(feature = "pkcs11-provider", feature = "tpm-provider"))]
+use crate::utils::SecretSources;
 #[cfg(any(
     feature = "mbed-crypto-provider",
     feature = "pkcs11-provider",
@@ -62,7 +64,7 @@ const WIRE_PROTOCOL_VERSION_MAJOR: u8 = 1;
 const DEFAULT_BODY_LEN_LIMIT: usize = 1 << 19;
 
 type KeyIdManager = Arc<RwLock<dyn ManageKeyIDs + Send + Sync>>;
-type Provider = Box<dyn Provide + Send + Sync>;
+type Provider = Arc<dyn Provide + Send + Sync>;
 
 #[derive(Copy, Clone, Deserialize, Debug)]
 pub struct CoreSettings {
@@ -112,7 +114,12 @@ impl ServiceBuilder {
             return Err(Error::new(ErrorKind::InvalidData, "need one provider"));
         }
 
-        let backend_handlers = build_backend_handlers(providers)?;
+        let body_len_limit = config
+            .core_settings
+            .body_len_limit
+            .unwrap_or(DEFAULT_BODY_LEN_LIMIT);
+
+        let backend_handlers = build_backend_handlers(providers, body_len_limit)?;
 
         let dispatcher = DispatcherBuilder::new()
             .with_backends(backend_handlers)
@@ -123,12 +130,7 @@ impl ServiceBuilder {
         Ok(FrontEndHandlerBuilder::new()
             .with_dispatcher(dispatcher)
             .with_authenticator(AuthType::Direct, direct_authenticator)
-            .with_body_len_limit(
-                config
-                    .core_settings
-                    .body_len_limit
-                    .unwrap_or(DEFAULT_BODY_LEN_LIMIT),
-            )
+            .with_body_len_limit(body_len_limit)
             .build()?)
     }
 
@@ -155,6 +157,7 @@ impl ServiceBuilder {
 
 fn build_backend_handlers(
     mut providers: HashMap<ProviderID, Provider>,
+    body_len_limit: usize,
 ) -> Result<HashMap<ProviderID, BackEndHandler>> {
     let mut map = HashMap::new();
 
@@ -170,22 +173,30 @@ fn build_backend_handlers(
                 ))
             })?);
 
+        // Operations which do not involve any key are sent to the Core provider and executed
+        // by the Mbed Crypto provider, if it is available.
+        if provider_id == ProviderID::MbedCrypto {
+            core_provider_builder = core_provider_builder.with_hash_provider(provider.clone());
+        }
+
         let backend_handler = BackEndHandlerBuilder::new()
             .with_provider(provider)
             .with_converter(Box::from(ProtobufConverter {}))
             .with_provider_id(provider_id)
             .with_content_type(BodyType::Protobuf)
             .with_accept_type(BodyType::Protobuf)
+            .with_body_len_limit(body_len_limit)
             .build()?;
         let _ = map.insert(provider_id, backend_handler);
     }
 
     let core_provider_backend = BackEndHandlerBuilder::new()
-        .with_provider(Box::from(core_provider_builder.build()?))
+        .with_provider(Arc::new(core_provider_builder.build()?))
         .with_converter(Box::from(ProtobufConverter {}))
         .with_provider_id(ProviderID::Core)
         .with_content_type(BodyType::Protobuf)
         .with_accept_type(BodyType::Protobuf)
+        .with_body_len_limit(body_len_limit)
         .build()?;
 
     let _ = map.insert(ProviderID::Core, core_provider_backend);
@@ -244,7 +255,7 @@ unsafe fn get_provider(config: &ProviderConfig, key_id_manager: KeyIdManager) ->
         #[cfg(feature = "mbed-crypto-provider")]
         ProviderConfig::MbedCrypto { .. } => {
             info!("Creating a Mbed Crypto Provider.");
-            Ok(Box::from(
+            Ok(Arc::new(
                 MbedProviderBuilder::new()
                     .with_key_id_store(key_id_manager)
                     .build()?,
@@ -254,16 +265,35 @@ unsafe fn get_provider(config: &ProviderConfig, key_id_manager: KeyIdManager) ->
         ProviderConfig::Pkcs11 {
             library_path,
             slot_number,
+            token_label,
+            serial_number,
             user_pin,
+            user_pin_file,
+            user_pin_env,
+            user_pin_credential,
+            session_pool_size,
+            adopted_keys,
             ..
         } => {
             info!("Creating a PKCS 11 Provider.");
-            Ok(Box::from(
+            let user_pin = SecretSources {
+                name: "user_pin",
+                value: user_pin,
+                file: user_pin_file,
+                env: user_pin_env,
+                credential: user_pin_credential,
+            }
+            .read()?;
+            Ok(Arc::new(
                 Pkcs11ProviderBuilder::new()
                     .with_key_id_store(key_id_manager)
                     .with_pkcs11_library_path(library_path.clone())
                     .with_slot_number(*slot_number)
-                    .with_user_pin(user_pin.clone())
+                    .with_token_label(token_label.clone())
+                    .with_serial_number(serial_number.clone())
+                    .with_user_pin(user_pin)
+                    .with_session_pool_size(*session_pool_size)
+                    .with_adopted_keys(adopted_keys.clone())
                     .build()?,
             ))
         }
@@ -271,14 +301,31 @@ unsafe fn get_provider(config: &ProviderConfig, key_id_manager: KeyIdManager) ->
         ProviderConfig::Tpm {
             tcti,
             owner_hierarchy_auth,
+            owner_hierarchy_auth_file,
+            owner_hierarchy_auth_env,
+            owner_hierarchy_auth_credential,
+            persistent_keys,
             ..
         } => {
             info!("Creating a TPM Provider.");
-            Ok(Box::from(
+            let owner_hierarchy_auth = SecretSources {
+                name: "owner_hierarchy_auth",
+                value: owner_hierarchy_auth,
+                file: owner_hierarchy_auth_file,
+                env: owner_hierarchy_auth_env,
+                credential: owner_hierarchy_auth_credential,
+            }
+            .read()?
+            .ok_or_else(|| {
+                error!("The owner hierarchy auth of the TPM provider is missing.");
+                Error::new(ErrorKind::InvalidData, "missing owner hierarchy auth")
+            })?;
+            Ok(Arc::new(
                 TpmProviderBuilder::new()
                     .with_key_id_store(key_id_manager)
                     .with_tcti(tcti)
-                    .with_owner_hierarchy_auth(owner_hierarchy_auth.clone())
+                    .with_owner_hierarchy_auth(owner_hierarchy_auth)
+                    .with_persistent_keys(persistent_keys.unwrap_or(false))
                     .build()?,
             ))
         }
@@ -13,8 +13,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use parsec_client_test::TestClient;
-use parsec_interface::requests::ProviderID;
+use parsec_interface::operations::psa_algorithm::Hash;
 use parsec_interface::requests::Result;
+use parsec_interface::requests::{Opcode, ProviderID};
 use std::collections::HashSet;
 
 #[test]
@@ -35,7 +36,23 @@ fn list_opcodes() {
     let opcodes = client
         .list_opcodes(ProviderID::MbedCrypto)
         .expect("list providers failed");
-    assert_eq!(opcodes.len(), 7);
+    assert_eq!(opcodes.len(), 25);
+}
+
+#[test]
+fn hash_through_core_provider() -> Result<()> {
+    let mut client = TestClient::new();
+    let opcodes = client
+        .list_opcodes(ProviderID::Core)
+        .expect("list opcodes failed");
+    assert!(opcodes.contains(&Opcode::PsaHashCompute));
+    assert!(opcodes.contains(&Opcode::PsaHashCompare));
+
+    client.set_provider(Some(ProviderID::Core));
+    let hash = client.hash_compute(Hash::Sha256, vec![0x61, 0x62, 0x63])?;
+    assert_eq!(hash.len(), 32);
+
+    client.hash_compare(Hash::Sha256, vec![0x61, 0x62, 0x63], hash)
 }
 
 #[cfg(feature = "testing")]
@@ -26,8 +26,6 @@ cleanup () {
     if [ -n "$PARSEC_PID" ]; then kill $PARSEC_PID || true ; fi
     # Stop tpm_server if running
     if [ -n "$TPM_SRV_PID" ]; then kill $TPM_SRV_PID || true; fi
-    # Remove the slot_number line added by find_slot_number.sh
-    sed -i '/^slot_number =.*/d' $CONFIG_PATH
     # Remove fake mapping and temp files
     if [ -d "mappings" ]; then rm -rf -- "mappings"; fi
     if [ -f "NVChip" ]; then rm "NVChip" ; fi
@@ -104,11 +102,6 @@ if [ "$PROVIDER_NAME" = "tpm" ] || [ "$PROVIDER_NAME" = "all" ]; then
     tpm2_changeauth -c owner tpm_pass 2>/dev/null
 fi
 
-if [ "$PROVIDER_NAME" = "pkcs11" ] || [ "$PROVIDER_NAME" = "all" ]; then
-    # Find and append the slot number at the end of the configuration file.
-    tests/per_provider/provider_cfg/pkcs11/find_slot_number.sh $CONFIG_PATH
-fi
-
 echo "Build test"
 RUST_BACKTRACE=1 cargo build $FEATURES
 
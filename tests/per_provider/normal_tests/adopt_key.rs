@@ -0,0 +1,66 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{Opcode, ProviderID, Result};
+
+const HASH: [u8; 32] = [
+    0x69, 0x3E, 0xDB, 0x1B, 0x22, 0x79, 0x03, 0xF4, 0xC0, 0xBF, 0xD6, 0x91, 0x76, 0x37, 0x84, 0xA2,
+    0x94, 0x8E, 0x92, 0x50, 0x35, 0xC2, 0x8C, 0x5C, 0x3C, 0xCA, 0xFE, 0x18, 0xE8, 0x81, 0x37, 0x78,
+];
+
+// The key is imported in the token and adopted through the configuration of the PKCS 11
+// provider tests only.
+#[test]
+fn adopted_key_attributes() -> Result<()> {
+    let mut client = TestClient::new();
+
+    if client.get_cached_provider(Opcode::PsaSignHash) != ProviderID::Pkcs11 {
+        return Ok(());
+    }
+
+    client.set_auth(String::from("adopting_client").into_bytes());
+    let attributes = client.get_key_attributes(String::from("adopted_rsa_key"))?;
+
+    assert_eq!(attributes.key_type, KeyType::RsaKeyPair);
+    assert_eq!(attributes.key_bits, 2048);
+    assert!(attributes.key_policy.key_usage_flags.sign_hash);
+    assert!(attributes.key_policy.key_usage_flags.verify_hash);
+    assert!(!attributes.key_policy.key_usage_flags.export);
+    assert_eq!(
+        attributes.key_policy.key_algorithm,
+        Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPkcs1v15Sign {
+            hash_alg: Hash::Sha256,
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn adopted_key_sign_and_verify() -> Result<()> {
+    let key_name = String::from("adopted_rsa_key");
+    let mut client = TestClient::new();
+
+    if client.get_cached_provider(Opcode::PsaSignHash) != ProviderID::Pkcs11 {
+        return Ok(());
+    }
+
+    client.set_auth(String::from("adopting_client").into_bytes());
+    let signature = client.sign_with_rsa_sha256(key_name.clone(), HASH.to_vec())?;
+
+    client.verify_with_rsa_sha256(key_name, HASH.to_vec(), signature)
+}
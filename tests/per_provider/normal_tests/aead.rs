@@ -0,0 +1,138 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::opcode_supported;
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{Opcode, ResponseStatus, Result};
+
+const NONCE: [u8; 12] = [
+    0x3C, 0xCA, 0xFE, 0x18, 0xE8, 0x81, 0x37, 0x78, 0x69, 0x3E, 0xDB, 0x1B,
+];
+const ADDITIONAL_DATA: [u8; 8] = [0xDE, 0xAD, 0xBE, 0xEF, 0xCA, 0xFE, 0xBA, 0xBE];
+const PLAINTEXT: [u8; 16] = [
+    0x94, 0x8E, 0x92, 0x50, 0x35, 0xC2, 0x8C, 0x5C, 0x22, 0x79, 0x03, 0xF4, 0xC0, 0xBF, 0xD6, 0x91,
+];
+
+fn aead_key_attributes(key_type: KeyType, key_bits: u32, alg: Aead) -> KeyAttributes {
+    KeyAttributes {
+        key_type,
+        key_bits,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: true,
+                decrypt: true,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::Aead(alg),
+        },
+    }
+}
+
+fn aead_encrypt_and_decrypt(key_name: String, key_type: KeyType, alg: Aead) -> Result<()> {
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaAeadEncrypt) {
+        return Ok(());
+    }
+
+    client.generate_key(key_name.clone(), aead_key_attributes(key_type, 256, alg))?;
+
+    let ciphertext = client.aead_encrypt(
+        key_name.clone(),
+        alg,
+        NONCE.to_vec(),
+        ADDITIONAL_DATA.to_vec(),
+        PLAINTEXT.to_vec(),
+    )?;
+    // Default tag of 16 bytes.
+    assert_eq!(ciphertext.len(), PLAINTEXT.len() + 16);
+
+    let plaintext = client.aead_decrypt(
+        key_name.clone(),
+        alg,
+        NONCE.to_vec(),
+        ADDITIONAL_DATA.to_vec(),
+        ciphertext.clone(),
+    )?;
+    assert_eq!(PLAINTEXT.to_vec(), plaintext);
+
+    // Modifying the additional data should make the authentication fail.
+    let status = client
+        .aead_decrypt(
+            key_name,
+            alg,
+            NONCE.to_vec(),
+            vec![0xDE, 0xAD, 0xBE, 0xEF],
+            ciphertext,
+        )
+        .expect_err("Authentication should have failed");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidSignature);
+
+    Ok(())
+}
+
+#[test]
+fn aead_encrypt_and_decrypt_aes_gcm() -> Result<()> {
+    aead_encrypt_and_decrypt(
+        String::from("aead_encrypt_and_decrypt_aes_gcm"),
+        KeyType::Aes,
+        Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm),
+    )
+}
+
+#[test]
+fn aead_encrypt_and_decrypt_chacha20_poly1305() -> Result<()> {
+    aead_encrypt_and_decrypt(
+        String::from("aead_encrypt_and_decrypt_chacha20_poly1305"),
+        KeyType::Chacha20,
+        Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Chacha20Poly1305),
+    )
+}
+
+#[test]
+fn aead_encrypt_not_permitted() -> Result<()> {
+    let key_name = String::from("aead_encrypt_not_permitted");
+    let mut client = TestClient::new();
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+
+    if !opcode_supported(&mut client, Opcode::PsaAeadEncrypt) {
+        return Ok(());
+    }
+
+    let mut key_attributes = aead_key_attributes(KeyType::Aes, 128, alg);
+    key_attributes.key_policy.key_usage_flags.encrypt = false;
+    client.generate_key(key_name.clone(), key_attributes)?;
+
+    let status = client
+        .aead_encrypt(
+            key_name,
+            alg,
+            NONCE.to_vec(),
+            ADDITIONAL_DATA.to_vec(),
+            PLAINTEXT.to_vec(),
+        )
+        .expect_err("Encryption should not be permitted");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
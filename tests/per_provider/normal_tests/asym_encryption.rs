@@ -0,0 +1,143 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{ResponseStatus, Result};
+
+const PLAINTEXT_MESSAGE: [u8; 32] = [
+    0x69, 0x3E, 0xDB, 0x1B, 0x22, 0x79, 0x03, 0xF4, 0xC0, 0xBF, 0xD6, 0x91, 0x76, 0x37, 0x84, 0xA2,
+    0x94, 0x8E, 0x92, 0x50, 0x35, 0xC2, 0x8C, 0x5C, 0x3C, 0xCA, 0xFE, 0x18, 0xE8, 0x81, 0x37, 0x78,
+];
+
+fn encryption_key_attributes(alg: AsymmetricEncryption, decrypt: bool) -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: 1024,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: true,
+                decrypt,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricEncryption(alg),
+        },
+    }
+}
+
+#[test]
+fn asym_encrypt_no_key() {
+    let key_name = String::from("asym_encrypt_no_key");
+    let mut client = TestClient::new();
+    let status = client
+        .asymmetric_encrypt(
+            key_name,
+            AsymmetricEncryption::RsaPkcs1v15Crypt,
+            PLAINTEXT_MESSAGE.to_vec(),
+            None,
+        )
+        .expect_err("Key should not exist.");
+    assert_eq!(status, ResponseStatus::PsaErrorDoesNotExist);
+}
+
+#[test]
+fn asym_encrypt_and_decrypt_rsa_pkcs() -> Result<()> {
+    let key_name = String::from("asym_encrypt_and_decrypt_rsa_pkcs");
+    let mut client = TestClient::new();
+    let alg = AsymmetricEncryption::RsaPkcs1v15Crypt;
+
+    client.generate_key(key_name.clone(), encryption_key_attributes(alg, true))?;
+
+    let ciphertext =
+        client.asymmetric_encrypt(key_name.clone(), alg, PLAINTEXT_MESSAGE.to_vec(), None)?;
+    assert_eq!(ciphertext.len(), 128);
+
+    let plaintext = client.asymmetric_decrypt(key_name, alg, ciphertext, None)?;
+    assert_eq!(PLAINTEXT_MESSAGE.to_vec(), plaintext);
+
+    Ok(())
+}
+
+#[test]
+fn asym_encrypt_and_decrypt_rsa_oaep() -> Result<()> {
+    let key_name = String::from("asym_encrypt_and_decrypt_rsa_oaep");
+    let mut client = TestClient::new();
+    let alg = AsymmetricEncryption::RsaOaep {
+        hash_alg: Hash::Sha256,
+    };
+
+    client.generate_key(key_name.clone(), encryption_key_attributes(alg, true))?;
+
+    let ciphertext =
+        client.asymmetric_encrypt(key_name.clone(), alg, PLAINTEXT_MESSAGE.to_vec(), None)?;
+    let plaintext = client.asymmetric_decrypt(key_name, alg, ciphertext, None)?;
+    assert_eq!(PLAINTEXT_MESSAGE.to_vec(), plaintext);
+
+    Ok(())
+}
+
+#[test]
+fn asym_encrypt_and_decrypt_rsa_oaep_with_label() -> Result<()> {
+    let key_name = String::from("asym_encrypt_and_decrypt_rsa_oaep_with_label");
+    let mut client = TestClient::new();
+    let alg = AsymmetricEncryption::RsaOaep {
+        hash_alg: Hash::Sha256,
+    };
+    let label = b"parsec\0".to_vec();
+
+    client.generate_key(key_name.clone(), encryption_key_attributes(alg, true))?;
+
+    let ciphertext = client.asymmetric_encrypt(
+        key_name.clone(),
+        alg,
+        PLAINTEXT_MESSAGE.to_vec(),
+        Some(label.clone()),
+    )?;
+    let plaintext =
+        client.asymmetric_decrypt(key_name.clone(), alg, ciphertext.clone(), Some(label))?;
+    assert_eq!(PLAINTEXT_MESSAGE.to_vec(), plaintext);
+
+    // Decrypting with a different label must fail.
+    let _ = client
+        .asymmetric_decrypt(key_name, alg, ciphertext, Some(b"other\0".to_vec()))
+        .expect_err("Decryption with the wrong label should fail");
+
+    Ok(())
+}
+
+#[test]
+fn asym_decrypt_not_permitted() -> Result<()> {
+    let key_name = String::from("asym_decrypt_not_permitted");
+    let mut client = TestClient::new();
+    let alg = AsymmetricEncryption::RsaPkcs1v15Crypt;
+
+    client.generate_key(key_name.clone(), encryption_key_attributes(alg, false))?;
+
+    let ciphertext =
+        client.asymmetric_encrypt(key_name.clone(), alg, PLAINTEXT_MESSAGE.to_vec(), None)?;
+    let status = client
+        .asymmetric_decrypt(key_name, alg, ciphertext, None)
+        .expect_err("Decryption should not be permitted");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
@@ -15,7 +15,7 @@
 use parsec_client_test::TestClient;
 use parsec_interface::operations::psa_algorithm::*;
 use parsec_interface::operations::psa_key_attributes::*;
-use parsec_interface::requests::{ResponseStatus, Result};
+use parsec_interface::requests::{Opcode, ProviderID, ResponseStatus, Result};
 
 const HASH: [u8; 32] = [
     0x69, 0x3E, 0xDB, 0x1B, 0x22, 0x79, 0x03, 0xF4, 0xC0, 0xBF, 0xD6, 0x91, 0x76, 0x37, 0x84, 0xA2,
@@ -55,6 +55,160 @@ fn asym_sign_and_verify_rsa_pkcs() -> Result<()> {
     client.verify_with_rsa_sha256(key_name, HASH.to_vec(), signature)
 }
 
+#[test]
+fn asym_sign_and_verify_rsa_pss() -> Result<()> {
+    let key_name = String::from("asym_sign_and_verify_rsa_pss");
+    let mut client = TestClient::new();
+
+    let alg = AsymmetricSignature::RsaPss {
+        hash_alg: Hash::Sha256,
+    };
+    let key_attributes = KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: 1024,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: true,
+                verify_hash: true,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(alg),
+        },
+    };
+
+    client.generate_key(key_name.clone(), key_attributes)?;
+
+    let signature = client.sign(key_name.clone(), alg, HASH.to_vec())?;
+
+    client.verify(key_name, alg, HASH.to_vec(), signature)
+}
+
+#[test]
+fn asym_sign_and_verify_ecdsa() -> Result<()> {
+    let key_name = String::from("asym_sign_and_verify_ecdsa");
+    let mut client = TestClient::new();
+
+    // The Mbed Crypto provider does not support ECDSA for now.
+    if client.get_cached_provider(Opcode::PsaSignHash) == ProviderID::MbedCrypto {
+        return Ok(());
+    }
+
+    let alg = AsymmetricSignature::Ecdsa {
+        hash_alg: Hash::Sha256,
+    };
+    let key_attributes = KeyAttributes {
+        key_type: KeyType::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        },
+        key_bits: 256,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: true,
+                verify_hash: true,
+                sign_message: false,
+                verify_message: false,
+                export: true,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(alg),
+        },
+    };
+
+    client.generate_key(key_name.clone(), key_attributes)?;
+
+    let signature = client.sign(key_name.clone(), alg, HASH.to_vec())?;
+    // Concatenation of the r and s values of the signature.
+    assert_eq!(signature.len(), 64);
+
+    let public_key = client.export_public_key(key_name.clone())?;
+    // Uncompressed point format.
+    assert_eq!(public_key.len(), 65);
+    assert_eq!(public_key[0], 0x04);
+
+    client.verify(key_name, alg, HASH.to_vec(), signature)
+}
+
+fn asym_sign_and_verify_rsa_pkcs_with_hash(key_name: String, hash_alg: Hash) -> Result<()> {
+    let mut client = TestClient::new();
+
+    // The PKCS 11 provider only supports signing SHA-256 digests for now.
+    if client.get_cached_provider(Opcode::PsaSignHash) == ProviderID::Pkcs11 {
+        return Ok(());
+    }
+
+    let alg = AsymmetricSignature::RsaPkcs1v15Sign { hash_alg };
+    let key_attributes = KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: 1024,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: true,
+                verify_hash: true,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(alg),
+        },
+    };
+
+    client.generate_key(key_name.clone(), key_attributes)?;
+
+    let digest = match hash_alg {
+        Hash::Sha384 => vec![0xA5; 48],
+        Hash::Sha512 => vec![0xA5; 64],
+        _ => panic!("Unexpected hashing algorithm."),
+    };
+
+    let signature = client.sign(key_name.clone(), alg, digest.clone())?;
+
+    client.verify(key_name.clone(), alg, digest, signature.clone())?;
+
+    // A SHA-256 digest does not match the hashing algorithm of the key.
+    let status = client
+        .verify(key_name.clone(), alg, HASH.to_vec(), signature)
+        .expect_err("Verification should fail.");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidArgument);
+    let status = client
+        .sign(key_name, alg, HASH.to_vec())
+        .expect_err("Signing should fail.");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidArgument);
+
+    Ok(())
+}
+
+#[test]
+fn asym_sign_and_verify_rsa_pkcs_sha384() -> Result<()> {
+    asym_sign_and_verify_rsa_pkcs_with_hash(
+        String::from("asym_sign_and_verify_rsa_pkcs_sha384"),
+        Hash::Sha384,
+    )
+}
+
+#[test]
+fn asym_sign_and_verify_rsa_pkcs_sha512() -> Result<()> {
+    asym_sign_and_verify_rsa_pkcs_with_hash(
+        String::from("asym_sign_and_verify_rsa_pkcs_sha512"),
+        Hash::Sha512,
+    )
+}
+
 #[test]
 fn asym_verify_fail() -> Result<()> {
     let key_name = String::from("asym_verify_fail");
@@ -127,3 +281,126 @@ fn only_verify_from_internet() -> Result<()> {
 
     client.verify_with_rsa_sha256(key_name, digest, signature)
 }
+
+fn rsa_message_sign_key_attributes() -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: 1024,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: true,
+                verify_hash: true,
+                sign_message: true,
+                verify_message: true,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPkcs1v15Sign {
+                hash_alg: Hash::Sha256,
+            }),
+        },
+    }
+}
+
+#[test]
+fn sign_and_verify_message() -> Result<()> {
+    let key_name = String::from("sign_and_verify_message");
+    let mut client = TestClient::new();
+    let alg = AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: Hash::Sha256,
+    };
+    let message = b"Les carottes sont cuites.".to_vec();
+
+    client.generate_key(key_name.clone(), rsa_message_sign_key_attributes())?;
+
+    let signature = client.sign_message(key_name.clone(), alg, message.clone())?;
+
+    // The message is signed as its SHA-256 digest would be.
+    let digest = vec![
+        0x02, 0x2b, 0x26, 0xb1, 0xc3, 0x18, 0xdb, 0x73, 0x36, 0xef, 0x6f, 0x50, 0x9c, 0x35, 0xdd,
+        0xaa, 0xe1, 0x3d, 0x21, 0xdf, 0x83, 0x68, 0x0f, 0x48, 0xae, 0x5d, 0x8a, 0x5d, 0x37, 0x3c,
+        0xc1, 0x05,
+    ];
+    client.verify_with_rsa_sha256(key_name.clone(), digest, signature.clone())?;
+
+    client.verify_message(key_name, alg, message, signature)
+}
+
+#[test]
+fn sign_and_verify_large_message() -> Result<()> {
+    let key_name = String::from("sign_and_verify_large_message");
+    let mut client = TestClient::new();
+    let alg = AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: Hash::Sha256,
+    };
+    let message = vec![0xa5; 100_000];
+
+    client.generate_key(key_name.clone(), rsa_message_sign_key_attributes())?;
+
+    let signature = client.sign_message(key_name.clone(), alg, message.clone())?;
+
+    client.verify_message(key_name, alg, message, signature)
+}
+
+#[test]
+fn verify_message_fail() -> Result<()> {
+    let key_name = String::from("verify_message_fail");
+    let mut client = TestClient::new();
+    let alg = AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: Hash::Sha256,
+    };
+
+    client.generate_key(key_name.clone(), rsa_message_sign_key_attributes())?;
+
+    let signature = client.sign_message(key_name.clone(), alg, b"Original message".to_vec())?;
+
+    let status = client
+        .verify_message(key_name, alg, b"Tampered message".to_vec(), signature)
+        .expect_err("Verification should fail.");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidSignature);
+
+    Ok(())
+}
+
+#[test]
+fn sign_and_verify_message_only_usage() -> Result<()> {
+    let key_name = String::from("sign_and_verify_message_only_usage");
+    let mut client = TestClient::new();
+    let alg = AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: Hash::Sha256,
+    };
+    let message = b"Les carottes sont cuites.".to_vec();
+    let mut key_attributes = rsa_message_sign_key_attributes();
+    key_attributes.key_policy.key_usage_flags.sign_hash = false;
+    key_attributes.key_policy.key_usage_flags.verify_hash = false;
+
+    client.generate_key(key_name.clone(), key_attributes)?;
+
+    let signature = client.sign_message(key_name.clone(), alg, message.clone())?;
+
+    client.verify_message(key_name, alg, message, signature)
+}
+
+#[test]
+fn sign_message_not_permitted() -> Result<()> {
+    let key_name = String::from("sign_message_not_permitted");
+    let mut client = TestClient::new();
+    let alg = AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: Hash::Sha256,
+    };
+    let mut key_attributes = rsa_message_sign_key_attributes();
+    key_attributes.key_policy.key_usage_flags.sign_message = false;
+
+    client.generate_key(key_name.clone(), key_attributes)?;
+
+    let status = client
+        .sign_message(key_name, alg, b"Les carottes sont cuites.".to_vec())
+        .expect_err("The key does not permit signing messages.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::opcode_supported;
+use parsec_client_test::TestClient;
+use parsec_interface::requests::{Opcode, ResponseStatus, Result};
+
+const NONCE: [u8; 16] = [
+    0x8E, 0x92, 0x50, 0x35, 0xC2, 0x8C, 0x5C, 0x3C, 0xCA, 0xFE, 0x18, 0xE8, 0x81, 0x37, 0x78, 0x69,
+];
+
+#[test]
+fn attest_key_without_create() {
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::AttestKey) {
+        return;
+    }
+
+    let status = client
+        .attest_key(
+            String::from("attest_key_without_create"),
+            NONCE.to_vec(),
+            None,
+        )
+        .expect_err("Key should not exist.");
+    assert_eq!(status, ResponseStatus::PsaErrorDoesNotExist);
+}
+
+#[test]
+fn attest_key() -> Result<()> {
+    let key_name = String::from("attest_key");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::AttestKey) {
+        return Ok(());
+    }
+
+    client.generate_rsa_sign_key(key_name.clone())?;
+    let result = client.attest_key(key_name, NONCE.to_vec(), None)?;
+
+    assert!(!result.certify_info.is_empty());
+    assert!(!result.certify_signature.is_empty());
+    assert!(!result.attestation_public_key.is_empty());
+    assert!(result.quote_info.is_none());
+    assert!(result.quote_signature.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn attest_key_with_quote() -> Result<()> {
+    let key_name = String::from("attest_key_with_quote");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::AttestKey) {
+        return Ok(());
+    }
+
+    client.generate_rsa_sign_key(key_name.clone())?;
+    let first = client.attest_key(key_name.clone(), NONCE.to_vec(), Some(vec![0, 1, 2, 3]))?;
+    let second = client.attest_key(key_name, NONCE.to_vec(), Some(vec![0, 1, 2, 3]))?;
+
+    assert!(first.quote_info.is_some());
+    assert!(first.quote_signature.is_some());
+    // The same attestation key is used for both operations.
+    assert_eq!(first.attestation_public_key, second.attestation_public_key);
+
+    Ok(())
+}
+
+#[test]
+fn attest_key_invalid_pcr() -> Result<()> {
+    let key_name = String::from("attest_key_invalid_pcr");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::AttestKey) {
+        return Ok(());
+    }
+
+    client.generate_rsa_sign_key(key_name.clone())?;
+    let status = client
+        .attest_key(key_name, NONCE.to_vec(), Some(vec![24]))
+        .expect_err("PCR 24 does not exist.");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidArgument);
+
+    Ok(())
+}
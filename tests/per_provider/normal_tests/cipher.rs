@@ -0,0 +1,145 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::opcode_supported;
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{Opcode, ResponseStatus, Result};
+
+// Test vector from NIST SP 800-38A, F.2.1 (CBC-AES128.Encrypt), first block.
+const KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+const IV: [u8; 16] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+];
+const PLAINTEXT: [u8; 16] = [
+    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+];
+const CIPHERTEXT: [u8; 16] = [
+    0x76, 0x49, 0xab, 0xac, 0x81, 0x19, 0xb2, 0x46, 0xce, 0xe9, 0x8e, 0x9b, 0x12, 0xe9, 0x19, 0x7d,
+];
+
+fn cipher_key_attributes(alg: Cipher, encrypt: bool) -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::Aes,
+        key_bits: 128,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt,
+                decrypt: true,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::Cipher(alg),
+        },
+    }
+}
+
+#[test]
+fn cipher_encrypt_cbc_no_padding_test_vector() -> Result<()> {
+    let key_name = String::from("cipher_encrypt_cbc_no_padding_test_vector");
+    let mut client = TestClient::new();
+    let alg = Cipher::CbcNoPadding;
+
+    if !opcode_supported(&mut client, Opcode::PsaCipherEncrypt) {
+        return Ok(());
+    }
+
+    client.import_key_with_attributes(
+        key_name.clone(),
+        cipher_key_attributes(alg, true),
+        KEY.to_vec(),
+    )?;
+
+    let ciphertext =
+        client.cipher_encrypt(key_name.clone(), alg, IV.to_vec(), PLAINTEXT.to_vec())?;
+    assert_eq!(CIPHERTEXT.to_vec(), ciphertext);
+
+    let plaintext = client.cipher_decrypt(key_name, alg, IV.to_vec(), ciphertext)?;
+    assert_eq!(PLAINTEXT.to_vec(), plaintext);
+
+    Ok(())
+}
+
+#[test]
+fn cipher_encrypt_and_decrypt_cbc_pkcs7() -> Result<()> {
+    let key_name = String::from("cipher_encrypt_and_decrypt_cbc_pkcs7");
+    let mut client = TestClient::new();
+    let alg = Cipher::CbcPkcs7;
+    let message = b"Not a multiple of the block size".to_vec();
+
+    if !opcode_supported(&mut client, Opcode::PsaCipherEncrypt) {
+        return Ok(());
+    }
+
+    client.generate_key(key_name.clone(), cipher_key_attributes(alg, true))?;
+
+    let ciphertext = client.cipher_encrypt(key_name.clone(), alg, IV.to_vec(), message.clone())?;
+    assert_eq!(ciphertext.len() % 16, 0);
+
+    let plaintext = client.cipher_decrypt(key_name, alg, IV.to_vec(), ciphertext)?;
+    assert_eq!(message, plaintext);
+
+    Ok(())
+}
+
+#[test]
+fn cipher_encrypt_and_decrypt_ctr() -> Result<()> {
+    let key_name = String::from("cipher_encrypt_and_decrypt_ctr");
+    let mut client = TestClient::new();
+    let alg = Cipher::Ctr;
+    let message = b"Stream cipher mode".to_vec();
+
+    if !opcode_supported(&mut client, Opcode::PsaCipherEncrypt) {
+        return Ok(());
+    }
+
+    client.generate_key(key_name.clone(), cipher_key_attributes(alg, true))?;
+
+    let ciphertext = client.cipher_encrypt(key_name.clone(), alg, IV.to_vec(), message.clone())?;
+    assert_eq!(ciphertext.len(), message.len());
+
+    let plaintext = client.cipher_decrypt(key_name, alg, IV.to_vec(), ciphertext)?;
+    assert_eq!(message, plaintext);
+
+    Ok(())
+}
+
+#[test]
+fn cipher_encrypt_not_permitted() -> Result<()> {
+    let key_name = String::from("cipher_encrypt_not_permitted");
+    let mut client = TestClient::new();
+    let alg = Cipher::Ctr;
+
+    if !opcode_supported(&mut client, Opcode::PsaCipherEncrypt) {
+        return Ok(());
+    }
+
+    client.generate_key(key_name.clone(), cipher_key_attributes(alg, false))?;
+
+    let status = client
+        .cipher_encrypt(key_name, alg, IV.to_vec(), PLAINTEXT.to_vec())
+        .expect_err("Encryption should not be permitted");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
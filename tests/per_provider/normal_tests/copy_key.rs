@@ -0,0 +1,142 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::opcode_supported;
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{Opcode, ResponseStatus, Result};
+
+const HASH: [u8; 32] = [
+    0x69, 0x3E, 0xDB, 0x1B, 0x22, 0x79, 0x03, 0xF4, 0xC0, 0xBF, 0xD6, 0x91, 0x76, 0x37, 0x84, 0xA2,
+    0x94, 0x8E, 0x92, 0x50, 0x35, 0xC2, 0x8C, 0x5C, 0x3C, 0xCA, 0xFE, 0x18, 0xE8, 0x81, 0x37, 0x78,
+];
+
+fn rsa_sign_key_attributes(sign_hash: bool, export: bool, copy: bool) -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: 1024,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash,
+                verify_hash: true,
+                sign_message: false,
+                verify_message: false,
+                export,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPkcs1v15Sign {
+                hash_alg: Hash::Sha256,
+            }),
+        },
+    }
+}
+
+#[test]
+fn copy_key_without_create() {
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaCopyKey) {
+        return;
+    }
+
+    let status = client
+        .copy_key(
+            String::from("copy_key_without_create"),
+            String::from("copy_key_without_create_copy"),
+            rsa_sign_key_attributes(true, false, false),
+        )
+        .expect_err("Key should not exist.");
+    assert_eq!(status, ResponseStatus::PsaErrorDoesNotExist);
+}
+
+#[test]
+fn copy_key_with_narrower_policy() -> Result<()> {
+    let mut client = TestClient::new();
+    let key_name = String::from("copy_key_with_narrower_policy");
+    let copy_name = String::from("copy_key_with_narrower_policy_copy");
+
+    if !opcode_supported(&mut client, Opcode::PsaCopyKey) {
+        return Ok(());
+    }
+
+    client.generate_key(key_name.clone(), rsa_sign_key_attributes(true, false, true))?;
+    client.copy_key(
+        key_name.clone(),
+        copy_name.clone(),
+        rsa_sign_key_attributes(false, false, false),
+    )?;
+
+    let status = client
+        .sign_with_rsa_sha256(copy_name.clone(), HASH.to_vec())
+        .expect_err("Signing with the copy should not be permitted.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    // Both keys share the same key material.
+    let signature = client.sign_with_rsa_sha256(key_name, HASH.to_vec())?;
+    client.verify_with_rsa_sha256(copy_name, HASH.to_vec(), signature)
+}
+
+#[test]
+fn copy_key_with_wider_policy() -> Result<()> {
+    let mut client = TestClient::new();
+    let key_name = String::from("copy_key_with_wider_policy");
+
+    if !opcode_supported(&mut client, Opcode::PsaCopyKey) {
+        return Ok(());
+    }
+
+    client.generate_key(key_name.clone(), rsa_sign_key_attributes(true, false, true))?;
+
+    let status = client
+        .copy_key(
+            key_name,
+            String::from("copy_key_with_wider_policy_copy"),
+            rsa_sign_key_attributes(true, true, false),
+        )
+        .expect_err("The copy should not be allowed to be exported.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
+
+#[test]
+fn copy_key_not_permitted() -> Result<()> {
+    let mut client = TestClient::new();
+    let key_name = String::from("copy_key_not_permitted");
+
+    if !opcode_supported(&mut client, Opcode::PsaCopyKey) {
+        return Ok(());
+    }
+
+    client.generate_key(
+        key_name.clone(),
+        rsa_sign_key_attributes(true, false, false),
+    )?;
+
+    let status = client
+        .copy_key(
+            key_name,
+            String::from("copy_key_not_permitted_copy"),
+            rsa_sign_key_attributes(false, false, false),
+        )
+        .expect_err("The source key does not allow copies.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
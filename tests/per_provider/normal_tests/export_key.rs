@@ -0,0 +1,96 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::opcode_supported;
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{Opcode, ResponseStatus, Result};
+
+const KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+
+fn aes_key_attributes(export: bool) -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::Aes,
+        key_bits: 128,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: false,
+                verify_message: false,
+                export,
+                encrypt: true,
+                decrypt: true,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::Cipher(Cipher::Ctr),
+        },
+    }
+}
+
+// The AES keys of these tests can only be imported by the providers implementing ciphers.
+fn aes_keys_supported(client: &mut TestClient) -> bool {
+    opcode_supported(client, Opcode::PsaExportKey)
+        && opcode_supported(client, Opcode::PsaCipherEncrypt)
+}
+
+#[test]
+fn export_key_without_create() {
+    let mut client = TestClient::new();
+    let key_name = String::from("export_key_without_create");
+    let status = client
+        .export_key(key_name)
+        .expect_err("Key should not exist.");
+    assert_eq!(status, ResponseStatus::PsaErrorDoesNotExist);
+}
+
+#[test]
+fn import_and_export_key() -> Result<()> {
+    let mut client = TestClient::new();
+    let key_name = String::from("import_and_export_key");
+
+    if !aes_keys_supported(&mut client) {
+        return Ok(());
+    }
+
+    client.import_key_with_attributes(key_name.clone(), aes_key_attributes(true), KEY.to_vec())?;
+
+    assert_eq!(KEY.to_vec(), client.export_key(key_name)?);
+
+    Ok(())
+}
+
+#[test]
+fn export_key_not_permitted() -> Result<()> {
+    let mut client = TestClient::new();
+    let key_name = String::from("export_key_not_permitted");
+
+    if !aes_keys_supported(&mut client) {
+        return Ok(());
+    }
+
+    client.import_key_with_attributes(key_name.clone(), aes_key_attributes(false), KEY.to_vec())?;
+
+    let status = client
+        .export_key(key_name)
+        .expect_err("Export should not be permitted.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use parsec_client_test::TestClient;
+use parsec_interface::requests::{ResponseStatus, Result};
+
+#[test]
+fn generate_random_bytes() -> Result<()> {
+    let mut client = TestClient::new();
+
+    let random_bytes = client.generate_random(32)?;
+    assert_eq!(random_bytes.len(), 32);
+
+    let other_random_bytes = client.generate_random(32)?;
+    assert_ne!(random_bytes, other_random_bytes);
+
+    Ok(())
+}
+
+#[test]
+fn generate_random_several_chunks() -> Result<()> {
+    let mut client = TestClient::new();
+
+    // Bigger than what a TPM returns with a single TPM2_GetRandom command.
+    let random_bytes = client.generate_random(1000)?;
+    assert_eq!(random_bytes.len(), 1000);
+
+    Ok(())
+}
+
+#[test]
+fn generate_random_too_big() {
+    let mut client = TestClient::new();
+
+    // Bigger than the default body length limit of the service.
+    let status = client
+        .generate_random(1 << 20)
+        .expect_err("The size requested should be over the body length limit.");
+    assert_eq!(status, ResponseStatus::BodySizeExceedsLimit);
+}
@@ -0,0 +1,89 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::opcode_supported;
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::requests::{Opcode, ResponseStatus, Result};
+
+const MESSAGE: [u8; 3] = [0x61, 0x62, 0x63];
+// "abc" hashed with SHA-256, from FIPS 180-2.
+const SHA256_DIGEST: [u8; 32] = [
+    0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+    0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+];
+// "abc" hashed with SHA3-256, from the NIST SHA-3 examples.
+const SHA3_256_DIGEST: [u8; 32] = [
+    0x3a, 0x98, 0x5d, 0xa7, 0x4f, 0xe2, 0x25, 0xb2, 0x04, 0x5c, 0x17, 0x2d, 0x6b, 0xd3, 0x90, 0xbd,
+    0x85, 0x5f, 0x08, 0x6e, 0x3e, 0x9d, 0x52, 0x5b, 0x46, 0xbf, 0xe2, 0x45, 0x11, 0x43, 0x15, 0x32,
+];
+
+#[test]
+fn hash_compute_sha256() -> Result<()> {
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaHashCompute) {
+        return Ok(());
+    }
+
+    let hash = client.hash_compute(Hash::Sha256, MESSAGE.to_vec())?;
+    assert_eq!(SHA256_DIGEST.to_vec(), hash);
+
+    Ok(())
+}
+
+#[test]
+fn hash_compute_and_compare_sha512() -> Result<()> {
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaHashCompute) {
+        return Ok(());
+    }
+
+    let hash = client.hash_compute(Hash::Sha512, MESSAGE.to_vec())?;
+    assert_eq!(hash.len(), 64);
+
+    client.hash_compare(Hash::Sha512, MESSAGE.to_vec(), hash)
+}
+
+#[test]
+fn hash_compare_fail() {
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaHashCompute) {
+        return;
+    }
+
+    let mut hash = SHA256_DIGEST.to_vec();
+    hash[0] ^= 0xff;
+
+    let status = client
+        .hash_compare(Hash::Sha256, MESSAGE.to_vec(), hash)
+        .expect_err("Comparison should fail.");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidSignature);
+}
+
+#[test]
+fn hash_compute_and_compare_sha3_256() -> Result<()> {
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaHashCompute) {
+        return Ok(());
+    }
+
+    let hash = client.hash_compute(Hash::Sha3_256, MESSAGE.to_vec())?;
+    assert_eq!(SHA3_256_DIGEST.to_vec(), hash);
+
+    client.hash_compare(Hash::Sha3_256, MESSAGE.to_vec(), hash)
+}
@@ -27,6 +27,51 @@ const KEY_DATA: [u8; 140] = [
     39, 22, 141, 173, 85, 26, 58, 9, 128, 27, 57, 131, 2, 3, 1, 0, 1,
 ];
 
+// A 1024 bits RSA key pair, DER-encoded as a `RSAPrivateKey` structure.
+const KEY_PAIR_DATA: [u8; 609] = [
+    48, 130, 2, 93, 2, 1, 0, 2, 129, 129, 0, 222, 205, 42, 82, 234, 141, 245, 34, 98, 20, 10, 81,
+    115, 141, 87, 17, 38, 139, 220, 245, 148, 58, 64, 238, 71, 48, 250, 122, 239, 139, 77, 203,
+    172, 95, 26, 121, 84, 174, 17, 156, 166, 89, 24, 126, 106, 104, 77, 238, 57, 9, 52, 15, 227,
+    217, 69, 162, 184, 101, 241, 157, 149, 237, 167, 15, 220, 225, 142, 136, 155, 11, 66, 65, 230,
+    195, 166, 206, 17, 132, 105, 7, 248, 171, 214, 180, 211, 231, 129, 19, 222, 151, 177, 228, 253,
+    76, 2, 193, 134, 92, 148, 25, 63, 135, 9, 83, 210, 106, 87, 238, 223, 178, 76, 90, 253, 95,
+    183, 227, 62, 162, 116, 192, 17, 74, 128, 29, 70, 153, 139, 15, 2, 3, 1, 0, 1, 2, 129, 129, 0,
+    182, 145, 250, 119, 157, 122, 156, 0, 3, 204, 150, 51, 238, 115, 72, 128, 102, 76, 191, 208,
+    129, 25, 71, 49, 186, 38, 153, 106, 121, 182, 118, 22, 74, 246, 87, 148, 74, 222, 164, 209,
+    239, 194, 28, 127, 34, 164, 188, 15, 84, 175, 132, 248, 236, 101, 147, 89, 102, 175, 42, 209,
+    78, 21, 67, 10, 5, 103, 254, 221, 112, 41, 18, 82, 43, 2, 156, 27, 234, 87, 135, 187, 25, 128,
+    107, 64, 60, 161, 40, 62, 122, 238, 79, 177, 71, 130, 52, 201, 64, 184, 228, 109, 45, 95, 28,
+    248, 245, 52, 215, 233, 231, 108, 90, 229, 26, 156, 26, 46, 192, 18, 252, 218, 54, 128, 221,
+    134, 126, 248, 250, 17, 2, 65, 0, 254, 172, 11, 140, 41, 192, 62, 99, 61, 208, 37, 57, 145,
+    164, 147, 75, 70, 21, 218, 85, 33, 2, 78, 123, 249, 32, 242, 97, 106, 86, 12, 112, 60, 178, 36,
+    223, 244, 196, 251, 63, 207, 210, 24, 29, 220, 107, 127, 90, 58, 238, 134, 135, 41, 48, 193,
+    229, 212, 161, 119, 184, 146, 231, 30, 247, 2, 65, 0, 223, 246, 147, 180, 223, 13, 250, 7, 149,
+    45, 204, 253, 169, 71, 45, 110, 141, 62, 107, 0, 149, 145, 45, 2, 25, 61, 33, 180, 171, 3, 54,
+    129, 216, 170, 15, 219, 14, 18, 192, 209, 217, 65, 176, 181, 114, 217, 204, 73, 37, 238, 176,
+    82, 214, 227, 122, 13, 159, 129, 240, 86, 106, 242, 54, 169, 2, 64, 59, 192, 1, 181, 144, 214,
+    25, 205, 14, 227, 150, 216, 58, 227, 113, 235, 103, 54, 25, 83, 127, 187, 26, 206, 219, 84,
+    111, 137, 139, 121, 68, 209, 208, 107, 187, 91, 16, 2, 103, 48, 65, 129, 249, 70, 136, 64, 112,
+    80, 171, 34, 235, 77, 42, 204, 213, 177, 38, 129, 251, 164, 194, 82, 151, 97, 2, 65, 0, 220,
+    23, 97, 72, 89, 251, 78, 62, 173, 103, 121, 15, 190, 142, 232, 34, 192, 67, 26, 188, 84, 63,
+    122, 207, 153, 37, 238, 61, 177, 225, 82, 107, 128, 20, 127, 200, 113, 168, 20, 61, 37, 23,
+    221, 36, 51, 93, 189, 216, 20, 162, 224, 60, 72, 88, 251, 212, 239, 111, 174, 83, 254, 125, 81,
+    33, 2, 64, 73, 38, 241, 28, 182, 47, 192, 57, 124, 47, 54, 186, 178, 15, 7, 120, 240, 246, 214,
+    23, 246, 221, 172, 164, 142, 143, 227, 134, 119, 149, 183, 166, 163, 124, 237, 156, 115, 197,
+    137, 227, 140, 193, 147, 213, 13, 51, 225, 18, 127, 152, 53, 37, 26, 161, 38, 81, 14, 24, 82,
+    162, 253, 94, 65, 145,
+];
+
+// The public part of the key pair above, DER-encoded as a `RSAPublicKey` structure.
+const KEY_PAIR_PUBLIC_DATA: [u8; 140] = [
+    48, 129, 137, 2, 129, 129, 0, 222, 205, 42, 82, 234, 141, 245, 34, 98, 20, 10, 81, 115, 141,
+    87, 17, 38, 139, 220, 245, 148, 58, 64, 238, 71, 48, 250, 122, 239, 139, 77, 203, 172, 95, 26,
+    121, 84, 174, 17, 156, 166, 89, 24, 126, 106, 104, 77, 238, 57, 9, 52, 15, 227, 217, 69, 162,
+    184, 101, 241, 157, 149, 237, 167, 15, 220, 225, 142, 136, 155, 11, 66, 65, 230, 195, 166, 206,
+    17, 132, 105, 7, 248, 171, 214, 180, 211, 231, 129, 19, 222, 151, 177, 228, 253, 76, 2, 193,
+    134, 92, 148, 25, 63, 135, 9, 83, 210, 106, 87, 238, 223, 178, 76, 90, 253, 95, 183, 227, 62,
+    162, 116, 192, 17, 74, 128, 29, 70, 153, 139, 15, 2, 3, 1, 0, 1,
+];
+
 #[test]
 fn import_key() -> Result<()> {
     let mut client = TestClient::new();
@@ -91,3 +136,75 @@ fn import_key_twice() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn import_rsa_key_pair() -> Result<()> {
+    let mut client = TestClient::new();
+    let key_name = String::from("import_rsa_key_pair");
+    let alg = AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: Hash::Sha256,
+    };
+    let key_attributes = KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: 1024,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: true,
+                verify_hash: true,
+                sign_message: false,
+                verify_message: false,
+                export: true,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(alg),
+        },
+    };
+
+    client.import_key_with_attributes(key_name.clone(), key_attributes, KEY_PAIR_DATA.to_vec())?;
+
+    assert_eq!(
+        client.export_public_key(key_name.clone())?,
+        KEY_PAIR_PUBLIC_DATA.to_vec()
+    );
+
+    let hash = vec![0xa5; 32];
+    let signature = client.sign(key_name.clone(), alg, hash.clone())?;
+    client.verify(key_name, alg, hash, signature)
+}
+
+#[test]
+fn import_invalid_rsa_key_pair() {
+    let mut client = TestClient::new();
+    let key_name = String::from("import_invalid_rsa_key_pair");
+    let key_attributes = KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: 1024,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: true,
+                verify_hash: true,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPkcs1v15Sign {
+                hash_alg: Hash::Sha256,
+            }),
+        },
+    };
+
+    // The public key is not a valid key pair.
+    let status = client
+        .import_key_with_attributes(key_name, key_attributes, KEY_DATA.to_vec())
+        .expect_err("Importing a public key as a key pair should fail.");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidArgument);
+}
@@ -0,0 +1,95 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{ResponseStatus, Result};
+
+fn ecdh_key_attributes(derive: bool) -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        },
+        key_bits: 256,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive,
+            },
+            key_algorithm: Algorithm::KeyAgreement(KeyAgreement::Raw(RawKeyAgreement::Ecdh)),
+        },
+    }
+}
+
+#[test]
+fn raw_key_agreement_no_key() {
+    let key_name = String::from("raw_key_agreement_no_key");
+    let mut client = TestClient::new();
+    let status = client
+        .raw_key_agreement(RawKeyAgreement::Ecdh, key_name, vec![0x04; 65])
+        .expect_err("Key should not exist.");
+    assert_eq!(status, ResponseStatus::PsaErrorDoesNotExist);
+}
+
+#[test]
+fn raw_key_agreement_ecdh() -> Result<()> {
+    let key_name_a = String::from("raw_key_agreement_ecdh_a");
+    let key_name_b = String::from("raw_key_agreement_ecdh_b");
+    let mut client = TestClient::new();
+
+    client.generate_key(key_name_a.clone(), ecdh_key_attributes(true))?;
+    client.generate_key(key_name_b.clone(), ecdh_key_attributes(true))?;
+
+    let public_key_a = client.export_public_key(key_name_a.clone())?;
+    let public_key_b = client.export_public_key(key_name_b.clone())?;
+
+    let shared_secret_a =
+        client.raw_key_agreement(RawKeyAgreement::Ecdh, key_name_a, public_key_b)?;
+    let shared_secret_b =
+        client.raw_key_agreement(RawKeyAgreement::Ecdh, key_name_b, public_key_a)?;
+
+    // The shared secret is the x coordinate of the shared point.
+    assert_eq!(shared_secret_a.len(), 32);
+    assert_eq!(shared_secret_a, shared_secret_b);
+
+    Ok(())
+}
+
+#[test]
+fn raw_key_agreement_not_permitted() -> Result<()> {
+    let key_name_a = String::from("raw_key_agreement_not_permitted_a");
+    let key_name_b = String::from("raw_key_agreement_not_permitted_b");
+    let mut client = TestClient::new();
+
+    client.generate_key(key_name_a.clone(), ecdh_key_attributes(false))?;
+    client.generate_key(key_name_b.clone(), ecdh_key_attributes(true))?;
+
+    let public_key_b = client.export_public_key(key_name_b)?;
+
+    let status = client
+        .raw_key_agreement(RawKeyAgreement::Ecdh, key_name_a, public_key_b)
+        .expect_err("The key is not allowed to be used for key agreement.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
@@ -135,3 +135,47 @@ fn wrong_permitted_algorithm() {
 
     assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
 }
+
+#[test]
+fn get_key_attributes() {
+    let mut client = TestClient::new();
+    let key_name = String::from("get_key_attributes");
+
+    let key_attributes = KeyAttributes {
+        key_type: KeyType::RsaKeyPair,
+        key_bits: 1024,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: true,
+                verify_hash: true,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::AsymmetricSignature(AsymmetricSignature::RsaPkcs1v15Sign {
+                hash_alg: Hash::Sha256,
+            }),
+        },
+    };
+
+    client
+        .generate_key(key_name.clone(), key_attributes)
+        .unwrap();
+
+    assert_eq!(client.get_key_attributes(key_name).unwrap(), key_attributes);
+}
+
+#[test]
+fn get_key_attributes_without_create() {
+    let mut client = TestClient::new();
+    let key_name = String::from("get_key_attributes_without_create");
+
+    let status = client.get_key_attributes(key_name).unwrap_err();
+
+    assert_eq!(status, ResponseStatus::PsaErrorDoesNotExist);
+}
@@ -0,0 +1,205 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::opcode_supported;
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::operations::psa_key_derivation::Output;
+use parsec_interface::requests::{Opcode, ResponseStatus, Result};
+
+// Test vector from RFC 5869, A.1 (Test Case 1).
+const IKM: [u8; 22] = [0x0b; 22];
+const SALT: [u8; 13] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+];
+const INFO: [u8; 10] = [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9];
+const OKM: [u8; 42] = [
+    0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a, 0x90, 0x43, 0x4f, 0x64, 0xd0, 0x36, 0x2f, 0x2a,
+    0x2d, 0x2d, 0x0a, 0x90, 0xcf, 0x1a, 0x5a, 0x4c, 0x5d, 0xb0, 0x2d, 0x56, 0xec, 0xc4, 0xc5, 0xbf,
+    0x34, 0x00, 0x72, 0x08, 0xd5, 0xb8, 0x87, 0x18, 0x58, 0x65,
+];
+
+const HKDF_SHA256: KeyDerivation = KeyDerivation::Hkdf {
+    hash_alg: Hash::Sha256,
+};
+
+fn derive_key_attributes(export: bool) -> KeyAttributes {
+    KeyAttributes {
+        key_type: KeyType::Derive,
+        key_bits: 176,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: false,
+                verify_message: false,
+                export,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: true,
+            },
+            key_algorithm: Algorithm::KeyDerivation(HKDF_SHA256),
+        },
+    }
+}
+
+#[test]
+fn key_derivation_hkdf_bytes_test_vector() -> Result<()> {
+    let key_name = String::from("key_derivation_hkdf_bytes_test_vector");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaKeyDerivation) {
+        return Ok(());
+    }
+
+    client.import_key_with_attributes(
+        key_name.clone(),
+        derive_key_attributes(true),
+        IKM.to_vec(),
+    )?;
+
+    let output = client.key_derivation(
+        key_name,
+        HKDF_SHA256,
+        SALT.to_vec(),
+        INFO.to_vec(),
+        Output::Bytes { length: OKM.len() },
+    )?;
+    assert_eq!(OKM.to_vec(), output);
+
+    Ok(())
+}
+
+#[test]
+fn key_derivation_bytes_too_big() -> Result<()> {
+    let key_name = String::from("key_derivation_bytes_too_big");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaKeyDerivation) {
+        return Ok(());
+    }
+
+    client.import_key_with_attributes(
+        key_name.clone(),
+        derive_key_attributes(true),
+        IKM.to_vec(),
+    )?;
+
+    // Bigger than the default body length limit of the service.
+    let status = client
+        .key_derivation(
+            key_name,
+            HKDF_SHA256,
+            SALT.to_vec(),
+            INFO.to_vec(),
+            Output::Bytes { length: 1 << 20 },
+        )
+        .expect_err("The length requested should be over the body length limit.");
+    assert_eq!(status, ResponseStatus::BodySizeExceedsLimit);
+
+    Ok(())
+}
+
+#[test]
+fn key_derivation_bytes_not_exportable() -> Result<()> {
+    let key_name = String::from("key_derivation_bytes_not_exportable");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaKeyDerivation) {
+        return Ok(());
+    }
+
+    client.import_key_with_attributes(
+        key_name.clone(),
+        derive_key_attributes(false),
+        IKM.to_vec(),
+    )?;
+
+    let status = client
+        .key_derivation(
+            key_name,
+            HKDF_SHA256,
+            SALT.to_vec(),
+            INFO.to_vec(),
+            Output::Bytes { length: OKM.len() },
+        )
+        .expect_err("Deriving raw bytes from a non-exportable key should fail.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
+
+#[test]
+fn key_derivation_hkdf_to_aes_key() -> Result<()> {
+    let key_name = String::from("key_derivation_hkdf_to_aes_key");
+    let derived_key_name = String::from("key_derivation_hkdf_to_aes_key_derived");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::PsaKeyDerivation) {
+        return Ok(());
+    }
+
+    client.import_key_with_attributes(
+        key_name.clone(),
+        derive_key_attributes(false),
+        IKM.to_vec(),
+    )?;
+
+    let alg = Cipher::Ctr;
+    let derived_key_attributes = KeyAttributes {
+        key_type: KeyType::Aes,
+        key_bits: 128,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: false,
+                verify_message: false,
+                export: false,
+                encrypt: true,
+                decrypt: true,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::Cipher(alg),
+        },
+    };
+
+    let output = client.key_derivation(
+        key_name,
+        HKDF_SHA256,
+        SALT.to_vec(),
+        INFO.to_vec(),
+        Output::Key {
+            key_name: derived_key_name.clone(),
+            attributes: derived_key_attributes,
+        },
+    )?;
+    assert!(output.is_empty());
+
+    let iv = vec![0; 16];
+    let plaintext = vec![0x2a; 32];
+    let ciphertext =
+        client.cipher_encrypt(derived_key_name.clone(), alg, iv.clone(), plaintext.clone())?;
+    assert_eq!(
+        plaintext,
+        client.cipher_decrypt(derived_key_name, alg, iv, ciphertext)?
+    );
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use parsec_client_test::TestClient;
+use parsec_interface::operations::psa_algorithm::*;
+use parsec_interface::operations::psa_key_attributes::*;
+use parsec_interface::requests::{Opcode, ProviderID, ResponseStatus, Result};
+
+const HMAC_SHA256: Mac = Mac::FullLength(FullLengthMac::Hmac {
+    hash_alg: Hash::Sha256,
+});
+
+// Test vector from RFC 4231, Test Case 1.
+const KEY: [u8; 20] = [0x0b; 20];
+const INPUT: [u8; 8] = [0x48, 0x69, 0x20, 0x54, 0x68, 0x65, 0x72, 0x65];
+const MAC: [u8; 32] = [
+    0xb0, 0x34, 0x4c, 0x61, 0xd8, 0xdb, 0x38, 0x53, 0x5c, 0xa8, 0xaf, 0xce, 0xaf, 0x0b, 0xf1, 0x2b,
+    0x88, 0x1d, 0xc2, 0x00, 0xc9, 0x83, 0x3d, 0xa7, 0x26, 0xe9, 0x37, 0x6c, 0x2e, 0x32, 0xcf, 0xf7,
+];
+
+fn mac_key_attributes(key_type: KeyType, key_bits: u32, alg: Mac) -> KeyAttributes {
+    KeyAttributes {
+        key_type,
+        key_bits,
+        key_policy: KeyPolicy {
+            key_usage_flags: UsageFlags {
+                sign_hash: false,
+                verify_hash: false,
+                sign_message: true,
+                verify_message: true,
+                export: false,
+                encrypt: false,
+                decrypt: false,
+                cache: false,
+                copy: false,
+                derive: false,
+            },
+            key_algorithm: Algorithm::Mac(alg),
+        },
+    }
+}
+
+#[test]
+fn mac_compute_no_key() {
+    let key_name = String::from("mac_compute_no_key");
+    let mut client = TestClient::new();
+    let status = client
+        .mac_compute(key_name, HMAC_SHA256, INPUT.to_vec())
+        .expect_err("Key should not exist.");
+    assert_eq!(status, ResponseStatus::PsaErrorDoesNotExist);
+}
+
+#[test]
+fn mac_compute_and_verify_hmac() -> Result<()> {
+    let key_name = String::from("mac_compute_and_verify_hmac");
+    let mut client = TestClient::new();
+
+    client.generate_key(
+        key_name.clone(),
+        mac_key_attributes(KeyType::Hmac, 256, HMAC_SHA256),
+    )?;
+
+    let mac = client.mac_compute(key_name.clone(), HMAC_SHA256, INPUT.to_vec())?;
+    assert_eq!(mac.len(), 32);
+
+    client.mac_verify(key_name, HMAC_SHA256, INPUT.to_vec(), mac)
+}
+
+#[test]
+fn mac_verify_fail() -> Result<()> {
+    let key_name = String::from("mac_verify_fail");
+    let mut client = TestClient::new();
+
+    client.generate_key(
+        key_name.clone(),
+        mac_key_attributes(KeyType::Hmac, 256, HMAC_SHA256),
+    )?;
+
+    let mut mac = client.mac_compute(key_name.clone(), HMAC_SHA256, INPUT.to_vec())?;
+    mac[0] ^= 0xff;
+
+    let status = client
+        .mac_verify(key_name, HMAC_SHA256, INPUT.to_vec(), mac)
+        .expect_err("Verification should fail.");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidSignature);
+
+    Ok(())
+}
+
+#[test]
+fn mac_compute_hmac_test_vector() -> Result<()> {
+    let key_name = String::from("mac_compute_hmac_test_vector");
+    let mut client = TestClient::new();
+
+    // Importing HMAC keys is not supported by the TPM provider.
+    if client.get_cached_provider(Opcode::PsaMacCompute) == ProviderID::Tpm {
+        return Ok(());
+    }
+
+    client.import_key_with_attributes(
+        key_name.clone(),
+        mac_key_attributes(KeyType::Hmac, 160, HMAC_SHA256),
+        KEY.to_vec(),
+    )?;
+
+    let mac = client.mac_compute(key_name.clone(), HMAC_SHA256, INPUT.to_vec())?;
+    assert_eq!(MAC.to_vec(), mac);
+
+    client.mac_verify(key_name, HMAC_SHA256, INPUT.to_vec(), mac)
+}
+
+#[test]
+fn mac_compute_and_verify_cmac() -> Result<()> {
+    let key_name = String::from("mac_compute_and_verify_cmac");
+    let mut client = TestClient::new();
+    let alg = Mac::FullLength(FullLengthMac::Cmac);
+
+    // CMAC is only supported by the Mbed Crypto provider.
+    if client.get_cached_provider(Opcode::PsaMacCompute) != ProviderID::MbedCrypto {
+        return Ok(());
+    }
+
+    client.generate_key(key_name.clone(), mac_key_attributes(KeyType::Aes, 128, alg))?;
+
+    let mac = client.mac_compute(key_name.clone(), alg, INPUT.to_vec())?;
+    assert_eq!(mac.len(), 16);
+
+    client.mac_verify(key_name, alg, INPUT.to_vec(), mac)
+}
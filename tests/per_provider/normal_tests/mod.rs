@@ -12,11 +12,37 @@
 // WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+mod adopt_key;
+mod aead;
+mod asym_encryption;
 mod asym_sign_verify;
+mod attest_key;
 mod auth;
 mod basic;
+mod cipher;
+mod copy_key;
 mod create_destroy_key;
+mod export_key;
 mod export_public_key;
+mod generate_random;
+mod hash;
 mod import_key;
+mod key_agreement;
 mod key_attributes;
+mod key_derivation;
+mod mac;
 mod ping;
+mod seal_data;
+
+use parsec_client_test::TestClient;
+use parsec_interface::requests::Opcode;
+
+/// Checks if the provider under test lists the operation as supported. The tests of operations
+/// that are not implemented by all providers stop early when this is not the case.
+pub fn opcode_supported(client: &mut TestClient, opcode: Opcode) -> bool {
+    let provider = client.get_cached_provider(opcode);
+    client
+        .list_opcodes(provider)
+        .expect("list opcodes failed")
+        .contains(&opcode)
+}
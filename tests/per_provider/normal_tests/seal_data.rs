@@ -0,0 +1,142 @@
+// Copyright (c) 2020, Arm Limited, All Rights Reserved
+// SPDX-License-Identifier: Apache-2.0
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may
+// not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//          http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::opcode_supported;
+use parsec_client_test::TestClient;
+use parsec_interface::requests::{Opcode, ResponseStatus, Result};
+
+const SECRET: [u8; 32] = [
+    0x69, 0x3E, 0xDB, 0x1B, 0x22, 0x79, 0x03, 0xF4, 0xC0, 0xBF, 0xD6, 0x91, 0x76, 0x37, 0x84, 0xA2,
+    0x94, 0x8E, 0x92, 0x50, 0x35, 0xC2, 0x8C, 0x5C, 0x3C, 0xCA, 0xFE, 0x18, 0xE8, 0x81, 0x37, 0x78,
+];
+
+#[test]
+fn seal_and_unseal() -> Result<()> {
+    let key_name = String::from("seal_and_unseal");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::SealData) {
+        return Ok(());
+    }
+
+    client.seal_data(key_name.clone(), SECRET.to_vec(), None)?;
+    let data = client.unseal_data(key_name, None)?;
+
+    assert_eq!(data, SECRET.to_vec());
+
+    Ok(())
+}
+
+#[test]
+fn seal_and_unseal_with_pcr_policy() -> Result<()> {
+    let key_name = String::from("seal_and_unseal_with_pcr_policy");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::SealData) {
+        return Ok(());
+    }
+
+    client.seal_data(key_name.clone(), SECRET.to_vec(), Some(vec![0, 1, 2, 3]))?;
+    let data = client.unseal_data(key_name, Some(vec![0, 1, 2, 3]))?;
+
+    assert_eq!(data, SECRET.to_vec());
+
+    Ok(())
+}
+
+#[test]
+fn unseal_with_stored_pcr_selection() -> Result<()> {
+    let key_name = String::from("unseal_with_stored_pcr_selection");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::SealData) {
+        return Ok(());
+    }
+
+    // The PCR selection the data was sealed with is used when none is given.
+    client.seal_data(key_name.clone(), SECRET.to_vec(), Some(vec![0, 1, 2, 3]))?;
+    let data = client.unseal_data(key_name, None)?;
+
+    assert_eq!(data, SECRET.to_vec());
+
+    Ok(())
+}
+
+#[test]
+fn unseal_with_pcr_selection_without_policy() -> Result<()> {
+    let key_name = String::from("unseal_with_pcr_selection_without_policy");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::SealData) {
+        return Ok(());
+    }
+
+    client.seal_data(key_name.clone(), SECRET.to_vec(), None)?;
+    let status = client
+        .unseal_data(key_name, Some(vec![0]))
+        .expect_err("The PCR selection should not be the one the data was sealed with.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
+
+#[test]
+fn unseal_with_other_pcr_selection() -> Result<()> {
+    let key_name = String::from("unseal_with_other_pcr_selection");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::SealData) {
+        return Ok(());
+    }
+
+    client.seal_data(key_name.clone(), SECRET.to_vec(), Some(vec![0, 1, 2, 3]))?;
+    let status = client
+        .unseal_data(key_name, Some(vec![4]))
+        .expect_err("The policy of the object should not be satisfied.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}
+
+#[test]
+fn seal_too_much_data() {
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::SealData) {
+        return;
+    }
+
+    let status = client
+        .seal_data(String::from("seal_too_much_data"), vec![0xA5; 129], None)
+        .expect_err("The data should be too big to be sealed.");
+    assert_eq!(status, ResponseStatus::PsaErrorInvalidArgument);
+}
+
+#[test]
+fn sealed_data_can_not_be_exported() -> Result<()> {
+    let key_name = String::from("sealed_data_can_not_be_exported");
+    let mut client = TestClient::new();
+
+    if !opcode_supported(&mut client, Opcode::SealData) {
+        return Ok(());
+    }
+
+    client.seal_data(key_name.clone(), SECRET.to_vec(), None)?;
+    let status = client
+        .export_key(key_name)
+        .expect_err("Sealed data should not be exportable.");
+    assert_eq!(status, ResponseStatus::PsaErrorNotPermitted);
+
+    Ok(())
+}